@@ -0,0 +1,20 @@
+/// Set to regenerate the checked in header at `include/ssl_loglib.h` after changing the C API
+const UPDATE_HEADER_VAR: &str = "SSL_LOGLIB_FFI_UPDATE_HEADER";
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed={}", UPDATE_HEADER_VAR);
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let bindings = cbindgen::generate(&crate_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to generate C header: {}", e);
+        std::process::exit(1);
+    });
+    // Builds only write to OUT_DIR, so the source tree stays untouched in read-only or vendored checkouts
+    bindings.write_to_file(format!("{}/ssl_loglib.h", out_dir));
+    if std::env::var_os(UPDATE_HEADER_VAR).is_some() {
+        bindings.write_to_file(format!("{}/include/ssl_loglib.h", crate_dir));
+    }
+}
@@ -0,0 +1,133 @@
+#ifndef SSL_LOGLIB_H
+#define SSL_LOGLIB_H
+
+/* This file is generated by cbindgen. Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of a reader operation
+ */
+typedef enum SslLogStatus {
+  /**
+   * The operation succeeded
+   */
+  SSL_LOG_STATUS_OK = 0,
+  /**
+   * There are no more messages in the log
+   */
+  SSL_LOG_STATUS_END = 1,
+  /**
+   * The operation failed. Call `ssl_log_last_error` for a description.
+   */
+  SSL_LOG_STATUS_ERROR = -1,
+} SslLogStatus;
+
+/**
+ * An open log file
+ */
+typedef struct SslLogReader SslLogReader;
+
+/**
+ * A single message from a log file
+ */
+typedef struct SslLogRecord {
+  /**
+   * Receive timestamp in nanoseconds since the Unix epoch
+   */
+  int64_t timestamp;
+  /**
+   * Message type ID (ex. 3 for Refbox2013)
+   */
+  int32_t message_type;
+  /**
+   * Serialized message payload, valid until the next call with the same reader
+   */
+  const uint8_t *data;
+  /**
+   * Size of the payload in bytes
+   */
+  size_t size;
+} SslLogRecord;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Opens the log file at `path`, returning NULL on error
+ *
+ * # Safety
+ *
+ * `path` must be a valid, null-terminated string.
+ */
+struct SslLogReader *ssl_log_open(const char *path);
+
+/**
+ * Closes a reader opened with `ssl_log_open`
+ *
+ * # Safety
+ *
+ * `reader` must be NULL or a reader returned by `ssl_log_open` that has not already been closed.
+ */
+void ssl_log_close(struct SslLogReader *reader);
+
+/**
+ * Reads the next message into `record`
+ *
+ * Returns `SSL_LOG_STATUS_END` when there are no more messages. The payload pointer in `record`
+ * stays valid until the next call that takes the same reader.
+ *
+ * # Safety
+ *
+ * `reader` must be a valid reader and `record` must point to writable memory for one `SslLogRecord`.
+ */
+enum SslLogStatus ssl_log_next(struct SslLogReader *reader,
+                               struct SslLogRecord *record);
+
+/**
+ * Returns 1 if the log file has an index, 0 if it does not, and -1 on error
+ *
+ * # Safety
+ *
+ * `reader` must be a valid reader.
+ */
+int32_t ssl_log_is_indexed(struct SslLogReader *reader);
+
+/**
+ * Moves the reader back to the first message in the log
+ *
+ * # Safety
+ *
+ * `reader` must be a valid reader.
+ */
+enum SslLogStatus ssl_log_rewind(struct SslLogReader *reader);
+
+/**
+ * Uses the log index to move the reader to the first message received at or after `timestamp` (in nanoseconds)
+ *
+ * Fails if the log file is not indexed.
+ *
+ * # Safety
+ *
+ * `reader` must be a valid reader.
+ */
+enum SslLogStatus ssl_log_seek_to_timestamp(struct SslLogReader *reader,
+                                            int64_t timestamp);
+
+/**
+ * Returns a description of the last error on this thread, or NULL if there has not been one
+ *
+ * The string stays valid until the next failed call on this thread.
+ */
+const char *ssl_log_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SSL_LOGLIB_H */
@@ -0,0 +1,327 @@
+//! C interface for reading SSL game logs
+//!
+//! Messages are returned as raw records. Callers decode the protobuf payloads with their own generated code.
+
+use ssl_loglib::index::IndexMessage;
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::raw::RawMessage;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::io;
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Result of a reader operation
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SslLogStatus {
+    /// The operation succeeded
+    Ok = 0,
+    /// There are no more messages in the log
+    End = 1,
+    /// The operation failed. Call `ssl_log_last_error` for a description.
+    Error = -1,
+}
+
+/// An open log file
+pub struct SslLogReader {
+    reader: LogFileReader,
+    index: Option<IndexMessage>,
+    current: Option<RawMessage>,
+}
+
+/// A single message from a log file
+#[repr(C)]
+pub struct SslLogRecord {
+    /// Receive timestamp in nanoseconds since the Unix epoch
+    pub timestamp: i64,
+    /// Message type ID (ex. 3 for Refbox2013)
+    pub message_type: i32,
+    /// Serialized message payload, valid until the next call with the same reader
+    pub data: *const u8,
+    /// Size of the payload in bytes
+    pub size: usize,
+}
+
+fn status_from_result(result: io::Result<()>) -> SslLogStatus {
+    match result {
+        Ok(()) => SslLogStatus::Ok,
+        Err(e) => {
+            set_last_error(e);
+            SslLogStatus::Error
+        }
+    }
+}
+
+/// Opens the log file at `path`, returning NULL on error
+///
+/// # Safety
+///
+/// `path` must be a valid, null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ssl_log_open(path: *const c_char) -> *mut SslLogReader {
+    if path.is_null() {
+        set_last_error("Log path is NULL");
+        return ptr::null_mut();
+    }
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(p) => p,
+        Err(e) => {
+            set_last_error(e);
+            return ptr::null_mut();
+        }
+    };
+    match LogFileReader::new(path) {
+        Ok(reader) => Box::into_raw(Box::new(SslLogReader {
+            reader,
+            index: None,
+            current: None,
+        })),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Closes a reader opened with `ssl_log_open`
+///
+/// # Safety
+///
+/// `reader` must be NULL or a reader returned by `ssl_log_open` that has not already been closed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ssl_log_close(reader: *mut SslLogReader) {
+    if !reader.is_null() {
+        drop(unsafe { Box::from_raw(reader) });
+    }
+}
+
+/// Reads the next message into `record`
+///
+/// Returns `SSL_LOG_STATUS_END` when there are no more messages. The payload pointer in `record`
+/// stays valid until the next call that takes the same reader.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader and `record` must point to writable memory for one `SslLogRecord`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ssl_log_next(reader: *mut SslLogReader, record: *mut SslLogRecord) -> SslLogStatus {
+    let (Some(reader), Some(record)) = (unsafe { reader.as_mut() }, unsafe { record.as_mut() }) else {
+        set_last_error("Reader or record is NULL");
+        return SslLogStatus::Error;
+    };
+    reader.current = None;
+    match reader.reader.get_next_raw_message() {
+        Ok(message) => {
+            let message = reader.current.insert(message);
+            *record = SslLogRecord {
+                timestamp: message.timestamp,
+                message_type: i32::from(message.message_type),
+                data: message.data.as_ptr(),
+                size: message.data.len(),
+            };
+            SslLogStatus::Ok
+        }
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => SslLogStatus::End,
+        Err(e) => {
+            set_last_error(e);
+            SslLogStatus::Error
+        }
+    }
+}
+
+/// Returns 1 if the log file has an index, 0 if it does not, and -1 on error
+///
+/// # Safety
+///
+/// `reader` must be a valid reader.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ssl_log_is_indexed(reader: *mut SslLogReader) -> i32 {
+    let Some(reader) = (unsafe { reader.as_mut() }) else {
+        set_last_error("Reader is NULL");
+        return -1;
+    };
+    match reader.reader.is_indexed() {
+        Ok(indexed) => indexed as i32,
+        Err(e) => {
+            set_last_error(e);
+            -1
+        }
+    }
+}
+
+/// Moves the reader back to the first message in the log
+///
+/// # Safety
+///
+/// `reader` must be a valid reader.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ssl_log_rewind(reader: *mut SslLogReader) -> SslLogStatus {
+    let Some(reader) = (unsafe { reader.as_mut() }) else {
+        set_last_error("Reader is NULL");
+        return SslLogStatus::Error;
+    };
+    status_from_result(reader.reader.rewind())
+}
+
+/// Uses the log index to move the reader to the first message received at or after `timestamp` (in nanoseconds)
+///
+/// Fails if the log file is not indexed.
+///
+/// # Safety
+///
+/// `reader` must be a valid reader.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ssl_log_seek_to_timestamp(reader: *mut SslLogReader, timestamp: i64) -> SslLogStatus {
+    let Some(reader) = (unsafe { reader.as_mut() }) else {
+        set_last_error("Reader is NULL");
+        return SslLogStatus::Error;
+    };
+    let result = (|| {
+        if reader.index.is_none() {
+            if !reader.reader.is_indexed()? {
+                return Err(io::Error::new(io::ErrorKind::Unsupported, "Log file is not indexed"));
+            }
+            reader.index = Some(reader.reader.read_index()?);
+        }
+        match &reader.index {
+            Some(index) => reader.reader.seek_to_timestamp(index, timestamp),
+            None => Ok(()),
+        }
+    })();
+    status_from_result(result)
+}
+
+/// Returns a description of the last error on this thread, or NULL if there has not been one
+///
+/// The string stays valid until the next failed call on this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn ssl_log_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssl_loglib::raw::MessageType;
+    use ssl_loglib::test_util::{at, write_raw_log};
+    use std::path::Path;
+
+    fn messages() -> Vec<RawMessage> {
+        (0..3)
+            .map(|i| RawMessage {
+                timestamp: at(f64::from(i)),
+                message_type: MessageType::Refbox2013,
+                data: vec![i as u8; i as usize + 1],
+            })
+            .collect()
+    }
+
+    fn open(path: &Path) -> *mut SslLogReader {
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        let reader = unsafe { ssl_log_open(path.as_ptr()) };
+        assert!(!reader.is_null());
+        reader
+    }
+
+    fn empty_record() -> SslLogRecord {
+        SslLogRecord {
+            timestamp: 0,
+            message_type: 0,
+            data: ptr::null(),
+            size: 0,
+        }
+    }
+
+    /// Reads the next record, returning its status, timestamp, and payload
+    fn next(reader: *mut SslLogReader) -> (SslLogStatus, i64, Vec<u8>) {
+        let mut record = empty_record();
+        let status = unsafe { ssl_log_next(reader, &mut record) };
+        let data = match status {
+            SslLogStatus::Ok => unsafe { std::slice::from_raw_parts(record.data, record.size) }.to_vec(),
+            _ => Vec::new(),
+        };
+        (status, record.timestamp, data)
+    }
+
+    fn last_error() -> String {
+        let error = ssl_log_last_error();
+        assert!(!error.is_null());
+        unsafe { CStr::from_ptr(error) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn records_are_read_in_order_until_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let reader = open(&write_raw_log(dir.path(), "game.log", &messages(), false));
+        assert_eq!(unsafe { ssl_log_is_indexed(reader) }, 0);
+
+        let mut record = empty_record();
+        assert_eq!(unsafe { ssl_log_next(reader, &mut record) }, SslLogStatus::Ok);
+        assert_eq!(record.timestamp, at(0.0));
+        assert_eq!(record.message_type, 3);
+        assert_eq!(record.size, 1);
+        assert_eq!(next(reader), (SslLogStatus::Ok, at(1.0), vec![1, 1]));
+        assert_eq!(next(reader), (SslLogStatus::Ok, at(2.0), vec![2, 2, 2]));
+        assert_eq!(next(reader).0, SslLogStatus::End);
+
+        assert_eq!(unsafe { ssl_log_rewind(reader) }, SslLogStatus::Ok);
+        assert_eq!(next(reader), (SslLogStatus::Ok, at(0.0), vec![0]));
+        unsafe { ssl_log_close(reader) };
+    }
+
+    #[test]
+    fn seek_uses_the_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let reader = open(&write_raw_log(dir.path(), "game.log", &messages(), true));
+        assert_eq!(unsafe { ssl_log_is_indexed(reader) }, 1);
+
+        assert_eq!(unsafe { ssl_log_seek_to_timestamp(reader, at(0.5)) }, SslLogStatus::Ok);
+        assert_eq!(next(reader), (SslLogStatus::Ok, at(1.0), vec![1, 1]));
+        unsafe { ssl_log_close(reader) };
+    }
+
+    #[test]
+    fn seek_in_unindexed_log_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let reader = open(&write_raw_log(dir.path(), "game.log", &messages(), false));
+
+        assert_eq!(unsafe { ssl_log_seek_to_timestamp(reader, at(0.5)) }, SslLogStatus::Error);
+        assert_eq!(last_error(), "Log file is not indexed");
+        // The read position is unchanged
+        assert_eq!(next(reader), (SslLogStatus::Ok, at(0.0), vec![0]));
+        unsafe { ssl_log_close(reader) };
+    }
+
+    #[test]
+    fn null_arguments_are_errors() {
+        assert!(unsafe { ssl_log_open(ptr::null()) }.is_null());
+        assert_eq!(last_error(), "Log path is NULL");
+
+        let mut record = empty_record();
+        assert_eq!(unsafe { ssl_log_next(ptr::null_mut(), &mut record) }, SslLogStatus::Error);
+        assert_eq!(last_error(), "Reader or record is NULL");
+        assert_eq!(unsafe { ssl_log_is_indexed(ptr::null_mut()) }, -1);
+        assert_eq!(unsafe { ssl_log_rewind(ptr::null_mut()) }, SslLogStatus::Error);
+        assert_eq!(unsafe { ssl_log_seek_to_timestamp(ptr::null_mut(), 0) }, SslLogStatus::Error);
+        assert_eq!(last_error(), "Reader is NULL");
+        unsafe { ssl_log_close(ptr::null_mut()) };
+    }
+
+    #[test]
+    fn open_failure_sets_the_last_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = CString::new(dir.path().join("missing.log").to_str().unwrap()).unwrap();
+
+        assert!(unsafe { ssl_log_open(path.as_ptr()) }.is_null());
+        assert!(!last_error().is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+use numpy::IntoPyArray;
+use protobuf::Message;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::{Number, Value};
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::vision::messages_robocup_ssl_detection_tracked::TrackedFrame;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use ssl_loglib::raw::{MessageType, RawMessage};
+use ssl_loglib::tracker::is_from_source;
+use ssl_loglib::{LogMessage, MessageBody};
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+fn io_error(e: io::Error) -> PyErr {
+    PyIOError::new_err(e.to_string())
+}
+
+/// A JSON number as the Python number type it is converted to
+#[derive(Debug, PartialEq)]
+enum JsonNumber {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+impl From<&Number> for JsonNumber {
+    fn from(n: &Number) -> Self {
+        match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => JsonNumber::Int(i),
+            (None, Some(u)) => JsonNumber::UInt(u),
+            _ => JsonNumber::Float(n.as_f64().unwrap_or(f64::NAN)),
+        }
+    }
+}
+
+fn json_to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(n) => match JsonNumber::from(n) {
+            JsonNumber::Int(i) => i.into_pyobject(py)?.into_any(),
+            JsonNumber::UInt(u) => u.into_pyobject(py)?.into_any(),
+            JsonNumber::Float(f) => f.into_pyobject(py)?.into_any(),
+        },
+        Value::String(s) => s.into_pyobject(py)?.into_any(),
+        Value::Array(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(json_to_py(py, value)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, value) in fields {
+                dict.set_item(key, json_to_py(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/// Reads the next message from `reader`, returning None at the end of the log
+fn next_raw_message(reader: &mut LogFileReader) -> PyResult<Option<RawMessage>> {
+    match reader.get_next_raw_message() {
+        Ok(message) => Ok(Some(message)),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(io_error(e)),
+    }
+}
+
+/// A single message from a log file
+#[pyclass(name = "LogMessage", module = "ssl_loglib", frozen)]
+struct PyLogMessage {
+    message: LogMessage,
+}
+
+#[pymethods]
+impl PyLogMessage {
+    /// Receive timestamp in nanoseconds since the Unix epoch
+    #[getter]
+    fn timestamp(&self) -> PyResult<i64> {
+        self.message
+            .timestamp
+            .timestamp_nanos_opt()
+            .ok_or(PyValueError::new_err("Message timestamp out of range"))
+    }
+
+    /// Message type name (ex. "Refbox2013")
+    #[getter(r#type)]
+    fn message_type(&self) -> String {
+        format!("{:?}", self.message.body.message_type())
+    }
+
+    /// Message contents as a dict, using the protobuf field names
+    #[getter]
+    fn body<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let json = self.message.body.to_json().map_err(|e| PyValueError::new_err(e.to_string()))?;
+        json_to_py(py, &json)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LogMessage(timestamp={}, type={:?})",
+            self.message.timestamp.to_rfc3339(),
+            self.message.body.message_type()
+        )
+    }
+}
+
+/// Iterates over the messages in a log file, optionally only returning the given message types
+#[pyclass(name = "LogFileReader", module = "ssl_loglib")]
+struct PyLogFileReader {
+    reader: LogFileReader,
+    types: Option<HashSet<MessageType>>,
+}
+
+#[pymethods]
+impl PyLogFileReader {
+    #[new]
+    #[pyo3(signature = (path, types=None))]
+    fn new(path: PathBuf, types: Option<Vec<String>>) -> PyResult<Self> {
+        let types = types
+            .map(|names| {
+                names
+                    .iter()
+                    .map(|name| MessageType::from_str(name).map_err(PyValueError::new_err))
+                    .collect::<PyResult<HashSet<_>>>()
+            })
+            .transpose()?;
+        Ok(PyLogFileReader {
+            reader: LogFileReader::new(path).map_err(io_error)?,
+            types,
+        })
+    }
+
+    fn is_indexed(&mut self) -> PyResult<bool> {
+        self.reader.is_indexed().map_err(io_error)
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<PyLogMessage>> {
+        while let Some(raw_message) = next_raw_message(&mut self.reader)? {
+            if self.types.as_ref().is_some_and(|t| !t.contains(&raw_message.message_type)) {
+                continue;
+            }
+            let message = LogMessage::try_from(&raw_message).map_err(io_error)?;
+            return Ok(Some(PyLogMessage { message }));
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the bodies of all referee messages in a log file as a list of dicts
+#[pyfunction]
+fn referee_messages<'py>(py: Python<'py>, path: PathBuf) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+    for message in ssl_loglib::get_all_referee_messages(path).map_err(io_error)? {
+        if let MessageBody::Refbox2013(_) = &message.body {
+            let json = message.body.to_json().map_err(|e| PyValueError::new_err(e.to_string()))?;
+            list.append(json_to_py(py, &json)?)?;
+        }
+    }
+    Ok(list)
+}
+
+/// Calls `handle` with the receive timestamp and packet of every tracker message from `source`
+fn for_each_tracker_packet(
+    path: PathBuf,
+    source: Option<&str>,
+    mut handle: impl FnMut(i64, &TrackerWrapperPacket),
+) -> io::Result<()> {
+    let mut reader = LogFileReader::new(path)?;
+    loop {
+        let raw_message = match reader.get_next_raw_message() {
+            Ok(message) => message,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        if raw_message.message_type != MessageType::VisionTracker2020 {
+            continue;
+        }
+        let packet = TrackerWrapperPacket::parse_from_bytes(&raw_message.data)?;
+        if source.is_some_and(|s| !is_from_source(&packet, s)) {
+            continue;
+        }
+        handle(raw_message.timestamp, &packet);
+    }
+    Ok(())
+}
+
+/// Tracked robot data with one entry per robot per frame
+#[derive(Default)]
+struct RobotColumns {
+    timestamp: Vec<i64>,
+    frame_timestamp: Vec<f64>,
+    team: Vec<i32>,
+    id: Vec<u32>,
+    x: Vec<f32>,
+    y: Vec<f32>,
+    orientation: Vec<f32>,
+    vel_x: Vec<f32>,
+    vel_y: Vec<f32>,
+    vel_angular: Vec<f32>,
+    visibility: Vec<f32>,
+}
+
+impl RobotColumns {
+    fn push_frame(&mut self, receive_time: i64, frame: &TrackedFrame) {
+        for robot in &frame.robots {
+            self.timestamp.push(receive_time);
+            self.frame_timestamp.push(frame.timestamp());
+            self.team.push(robot.robot_id.team_color() as i32);
+            self.id.push(robot.robot_id.id());
+            self.x.push(robot.pos.x());
+            self.y.push(robot.pos.y());
+            self.orientation.push(robot.orientation());
+            self.vel_x.push(robot.vel.as_ref().map_or(f32::NAN, |v| v.x()));
+            self.vel_y.push(robot.vel.as_ref().map_or(f32::NAN, |v| v.y()));
+            self.vel_angular.push(robot.vel_angular.unwrap_or(f32::NAN));
+            self.visibility.push(robot.visibility.unwrap_or(f32::NAN));
+        }
+    }
+
+    fn into_dict(self, py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("timestamp", self.timestamp.into_pyarray(py))?;
+        dict.set_item("frame_timestamp", self.frame_timestamp.into_pyarray(py))?;
+        dict.set_item("team", self.team.into_pyarray(py))?;
+        dict.set_item("id", self.id.into_pyarray(py))?;
+        dict.set_item("x", self.x.into_pyarray(py))?;
+        dict.set_item("y", self.y.into_pyarray(py))?;
+        dict.set_item("orientation", self.orientation.into_pyarray(py))?;
+        dict.set_item("vel_x", self.vel_x.into_pyarray(py))?;
+        dict.set_item("vel_y", self.vel_y.into_pyarray(py))?;
+        dict.set_item("vel_angular", self.vel_angular.into_pyarray(py))?;
+        dict.set_item("visibility", self.visibility.into_pyarray(py))?;
+        Ok(dict)
+    }
+}
+
+/// Tracked ball data with one entry per ball per frame
+#[derive(Default)]
+struct BallColumns {
+    timestamp: Vec<i64>,
+    frame_timestamp: Vec<f64>,
+    x: Vec<f32>,
+    y: Vec<f32>,
+    z: Vec<f32>,
+    vel_x: Vec<f32>,
+    vel_y: Vec<f32>,
+    vel_z: Vec<f32>,
+    visibility: Vec<f32>,
+}
+
+impl BallColumns {
+    fn push_frame(&mut self, receive_time: i64, frame: &TrackedFrame) {
+        for ball in &frame.balls {
+            self.timestamp.push(receive_time);
+            self.frame_timestamp.push(frame.timestamp());
+            self.x.push(ball.pos.x());
+            self.y.push(ball.pos.y());
+            self.z.push(ball.pos.z());
+            self.vel_x.push(ball.vel.as_ref().map_or(f32::NAN, |v| v.x()));
+            self.vel_y.push(ball.vel.as_ref().map_or(f32::NAN, |v| v.y()));
+            self.vel_z.push(ball.vel.as_ref().map_or(f32::NAN, |v| v.z()));
+            self.visibility.push(ball.visibility.unwrap_or(f32::NAN));
+        }
+    }
+
+    fn into_dict(self, py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("timestamp", self.timestamp.into_pyarray(py))?;
+        dict.set_item("frame_timestamp", self.frame_timestamp.into_pyarray(py))?;
+        dict.set_item("x", self.x.into_pyarray(py))?;
+        dict.set_item("y", self.y.into_pyarray(py))?;
+        dict.set_item("z", self.z.into_pyarray(py))?;
+        dict.set_item("vel_x", self.vel_x.into_pyarray(py))?;
+        dict.set_item("vel_y", self.vel_y.into_pyarray(py))?;
+        dict.set_item("vel_z", self.vel_z.into_pyarray(py))?;
+        dict.set_item("visibility", self.visibility.into_pyarray(py))?;
+        Ok(dict)
+    }
+}
+
+/// Returns tracked robot data from a log file as a dict of numpy arrays, with one entry per robot per frame
+///
+/// Pass a tracker source name or UUID as `source` to only include data from one tracker.
+/// Missing velocities and visibilities are NaN. Teams are 1 for yellow and 2 for blue.
+#[pyfunction]
+#[pyo3(signature = (path, source=None))]
+fn tracked_robots<'py>(py: Python<'py>, path: PathBuf, source: Option<&str>) -> PyResult<Bound<'py, PyDict>> {
+    let mut columns = RobotColumns::default();
+    for_each_tracker_packet(path, source, |receive_time, packet| {
+        if let Some(frame) = packet.tracked_frame.as_ref() {
+            columns.push_frame(receive_time, frame);
+        }
+    })
+    .map_err(io_error)?;
+    columns.into_dict(py)
+}
+
+/// Returns tracked ball data from a log file as a dict of numpy arrays, with one entry per ball per frame
+///
+/// Pass a tracker source name or UUID as `source` to only include data from one tracker.
+/// Missing velocities and visibilities are NaN.
+#[pyfunction]
+#[pyo3(signature = (path, source=None))]
+fn tracked_balls<'py>(py: Python<'py>, path: PathBuf, source: Option<&str>) -> PyResult<Bound<'py, PyDict>> {
+    let mut columns = BallColumns::default();
+    for_each_tracker_packet(path, source, |receive_time, packet| {
+        if let Some(frame) = packet.tracked_frame.as_ref() {
+            columns.push_frame(receive_time, frame);
+        }
+    })
+    .map_err(io_error)?;
+    columns.into_dict(py)
+}
+
+#[pymodule(name = "ssl_loglib")]
+fn ssl_loglib_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyLogMessage>()?;
+    m.add_class::<PyLogFileReader>()?;
+    m.add_function(wrap_pyfunction!(referee_messages, m)?)?;
+    m.add_function(wrap_pyfunction!(tracked_robots, m)?)?;
+    m.add_function(wrap_pyfunction!(tracked_balls, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssl_loglib::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+    use ssl_loglib::test_util::{at, message, tracked, with_robot_motion, write_log};
+
+    #[test]
+    fn json_numbers_keep_their_integer_type() {
+        let number = |value: Value| match value {
+            Value::Number(n) => JsonNumber::from(&n),
+            _ => unreachable!(),
+        };
+        assert_eq!(number(Value::from(-3)), JsonNumber::Int(-3));
+        assert_eq!(number(Value::from(u64::MAX)), JsonNumber::UInt(u64::MAX));
+        assert_eq!(number(Value::from(0.25)), JsonNumber::Float(0.25));
+    }
+
+    #[test]
+    fn tracker_frames_from_one_source_are_collected_into_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let robots = [(TeamColor::TEAM_COLOR_YELLOW, 3, [1.0, 2.0]), (TeamColor::TEAM_COLOR_BLUE, 5, [-1.0, 0.5])];
+        let moving = with_robot_motion(tracked("A", 1, 10.0, Some([0.5, 0.25]), &robots), 1.5, Some([0.5, 0.0]), None);
+        let path = write_log(
+            dir.path(),
+            "game.log",
+            &[
+                message(0.0, MessageBody::VisionTracker2020(moving)),
+                message(0.01, MessageBody::VisionTracker2020(tracked("B", 1, 10.0, None, &robots))),
+                message(0.02, MessageBody::VisionTracker2020(tracked("A", 2, 10.02, None, &robots[..1]))),
+            ],
+        );
+
+        let mut robot_columns = RobotColumns::default();
+        let mut ball_columns = BallColumns::default();
+        for_each_tracker_packet(path, Some("A"), |receive_time, packet| {
+            let frame = packet.tracked_frame.as_ref().unwrap();
+            robot_columns.push_frame(receive_time, frame);
+            ball_columns.push_frame(receive_time, frame);
+        })
+        .unwrap();
+
+        assert_eq!(robot_columns.timestamp, [at(0.0), at(0.0), at(0.02)]);
+        assert_eq!(robot_columns.frame_timestamp, [10.0, 10.0, 10.02]);
+        assert_eq!(robot_columns.team, [1, 2, 1]);
+        assert_eq!(robot_columns.id, [3, 5, 3]);
+        assert_eq!(robot_columns.x, [1.0, -1.0, 1.0]);
+        assert_eq!(robot_columns.orientation, [1.5, 1.5, 0.0]);
+        assert_eq!(robot_columns.vel_x[..2], [0.5, 0.5]);
+        assert!(robot_columns.vel_x[2].is_nan());
+        assert!(robot_columns.vel_angular.iter().all(|v| v.is_nan()));
+
+        assert_eq!(ball_columns.timestamp, [at(0.0)]);
+        assert_eq!((ball_columns.x[0], ball_columns.y[0], ball_columns.z[0]), (0.5, 0.25, 0.0));
+        assert!(ball_columns.vel_x[0].is_nan());
+        assert!(ball_columns.visibility[0].is_nan());
+    }
+}
@@ -0,0 +1,235 @@
+//! WebAssembly bindings for reading SSL game logs in the browser
+
+use protobuf::Message;
+use serde::Serialize;
+use serde_json::Value;
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use ssl_loglib::raw::{MessageType, RawMessage};
+use ssl_loglib::tracker::source_label;
+use ssl_loglib::{LogMessage, MessageBody};
+use std::collections::BTreeMap;
+use std::io::{self, Cursor};
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(e: impl ToString) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// Converts JSON to a JS value with plain objects instead of Maps
+fn json_to_js(value: &Value) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(to_js_error)
+}
+
+fn nanos_to_millis(timestamp: i64) -> f64 {
+    timestamp as f64 / 1.0e6
+}
+
+fn message_body_to_js(message: &RawMessage) -> Result<JsValue, JsError> {
+    let message = LogMessage::try_from(message).map_err(to_js_error)?;
+    json_to_js(&message.body.to_json().map_err(to_js_error)?)
+}
+
+/// Receive timestamp and position of a message, so it can be decoded when it is looked up
+struct MessageOffset {
+    timestamp: i64,
+    offset: i64,
+}
+
+/// Messages from one tracker source
+#[derive(Default)]
+struct TrackerMessages {
+    /// UUIDs the source sent messages with, usually one unless the tracker was restarted
+    uuids: Vec<String>,
+    messages: Vec<MessageOffset>,
+}
+
+/// Returns the offset of the last message received at or before `timestamp`, assuming `messages` is sorted by
+/// timestamp
+fn latest_at(messages: &[MessageOffset], timestamp: i64) -> Option<i64> {
+    let count = messages.partition_point(|m| m.timestamp <= timestamp);
+    count.checked_sub(1).map(|i| messages[i].offset)
+}
+
+/// Iterates over the messages in a log file loaded into memory
+#[wasm_bindgen]
+pub struct LogReader {
+    reader: LogFileReader<Cursor<Vec<u8>>>,
+}
+
+#[wasm_bindgen]
+impl LogReader {
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<LogReader, JsError> {
+        Ok(LogReader {
+            reader: LogFileReader::from_reader(Cursor::new(data)).map_err(to_js_error)?,
+        })
+    }
+
+    /// Returns the next message as `{ timestamp, type, body }`, or `undefined` at the end of the log
+    ///
+    /// `timestamp` is the receive time in milliseconds since the Unix epoch.
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_message(&mut self) -> Result<JsValue, JsError> {
+        let message = match self.reader.get_next_raw_message() {
+            Ok(m) => m,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(JsValue::UNDEFINED),
+            Err(e) => return Err(to_js_error(e)),
+        };
+        let json = serde_json::json!({
+            "timestamp": nanos_to_millis(message.timestamp),
+            "type": format!("{:?}", message.message_type),
+            "body": LogMessage::try_from(&message).map_err(to_js_error)?.body.to_json().map_err(to_js_error)?,
+        });
+        json_to_js(&json)
+    }
+}
+
+/// Looks up the match state at any point in a log file loaded into memory
+#[wasm_bindgen]
+pub struct LogSnapshots {
+    start_time: i64,
+    end_time: i64,
+    message_counts: BTreeMap<String, usize>,
+    geometry: Option<Value>,
+    /// Messages are only decoded when they are looked up, to keep the memory use close to the size of the log
+    reader: LogFileReader<Cursor<Vec<u8>>>,
+    referee: Vec<MessageOffset>,
+    /// Tracker messages for each tracker source, by source name or UUID
+    trackers: BTreeMap<String, TrackerMessages>,
+}
+
+#[wasm_bindgen]
+impl LogSnapshots {
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<LogSnapshots, JsError> {
+        let mut snapshots = LogSnapshots {
+            start_time: 0,
+            end_time: 0,
+            message_counts: BTreeMap::new(),
+            geometry: None,
+            reader: LogFileReader::from_reader(Cursor::new(data)).map_err(to_js_error)?,
+            referee: Vec::new(),
+            trackers: BTreeMap::new(),
+        };
+        let mut first_timestamp = None;
+
+        loop {
+            let offset = snapshots.reader.position().map_err(to_js_error)?;
+            let message = match snapshots.reader.get_next_raw_message() {
+                Ok(m) => m,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(to_js_error(e)),
+            };
+            *snapshots
+                .message_counts
+                .entry(format!("{:?}", message.message_type))
+                .or_default() += 1;
+            if message.message_type == MessageType::Index2021 {
+                continue;
+            }
+            first_timestamp.get_or_insert(message.timestamp);
+            snapshots.end_time = message.timestamp;
+
+            let position = MessageOffset {
+                timestamp: message.timestamp,
+                offset,
+            };
+            match message.message_type {
+                MessageType::Refbox2013 => snapshots.referee.push(position),
+                MessageType::VisionTracker2020 => {
+                    let packet = TrackerWrapperPacket::parse_from_bytes(&message.data).map_err(to_js_error)?;
+                    let tracker = snapshots.trackers.entry(source_label(&packet).to_string()).or_default();
+                    if !tracker.uuids.iter().any(|uuid| uuid == packet.uuid()) {
+                        tracker.uuids.push(packet.uuid().to_string());
+                    }
+                    tracker.messages.push(position);
+                }
+                MessageType::Vision2014 if snapshots.geometry.is_none() => {
+                    let packet = SSL_WrapperPacket::parse_from_bytes(&message.data).map_err(to_js_error)?;
+                    if packet.geometry.is_some() {
+                        let body = MessageBody::Vision2014(packet);
+                        snapshots.geometry = body.to_json().map_err(to_js_error)?.get("geometry").cloned();
+                    }
+                }
+                _ => {}
+            }
+        }
+        snapshots.start_time = first_timestamp.unwrap_or_default();
+
+        Ok(snapshots)
+    }
+
+    /// Receive time of the first message in milliseconds since the Unix epoch
+    #[wasm_bindgen(getter)]
+    pub fn start_time(&self) -> f64 {
+        nanos_to_millis(self.start_time)
+    }
+
+    /// Time between the first and last message in seconds
+    #[wasm_bindgen(getter)]
+    pub fn duration(&self) -> f64 {
+        (self.end_time - self.start_time) as f64 / 1.0e9
+    }
+
+    /// Number of messages of each type
+    #[wasm_bindgen(getter)]
+    pub fn message_counts(&self) -> Result<JsValue, JsError> {
+        json_to_js(&serde_json::to_value(&self.message_counts).map_err(to_js_error)?)
+    }
+
+    /// The first vision geometry in the log, or `undefined` if there is none
+    #[wasm_bindgen(getter)]
+    pub fn geometry(&self) -> Result<JsValue, JsError> {
+        match &self.geometry {
+            Some(geometry) => json_to_js(geometry),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+
+    /// Names of the tracker sources in the log
+    #[wasm_bindgen(getter)]
+    pub fn tracker_sources(&self) -> Vec<String> {
+        self.trackers.keys().cloned().collect()
+    }
+
+    /// The latest referee message at `time` seconds from the beginning of the log
+    pub fn referee_at(&mut self, time: f64) -> Result<JsValue, JsError> {
+        let offset = latest_at(&self.referee, self.timestamp_at(time));
+        self.message_body_at(offset)
+    }
+
+    /// The latest tracker message from `source` at `time` seconds from the beginning of the log
+    ///
+    /// `source` is a source name or UUID. Uses the first tracker source if it is not given.
+    pub fn tracker_at(&mut self, time: f64, source: Option<String>) -> Result<JsValue, JsError> {
+        let tracker = match source {
+            Some(source) => self
+                .trackers
+                .iter()
+                .find(|(label, tracker)| **label == source || tracker.uuids.contains(&source))
+                .map(|(_, tracker)| tracker),
+            None => self.trackers.values().next(),
+        };
+        let offset = tracker.and_then(|t| latest_at(&t.messages, self.timestamp_at(time)));
+        self.message_body_at(offset)
+    }
+}
+
+impl LogSnapshots {
+    fn timestamp_at(&self, time: f64) -> i64 {
+        self.start_time + ssl_loglib::seconds_to_nanos(time)
+    }
+
+    /// Reads and decodes the message at `offset`, or returns `undefined` if there is no message
+    fn message_body_at(&mut self, offset: Option<i64>) -> Result<JsValue, JsError> {
+        let Some(offset) = offset else {
+            return Ok(JsValue::UNDEFINED);
+        };
+        self.reader.seek_to_offset(offset).map_err(to_js_error)?;
+        message_body_to_js(&self.reader.get_next_raw_message().map_err(to_js_error)?)
+    }
+}
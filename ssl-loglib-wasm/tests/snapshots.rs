@@ -0,0 +1,66 @@
+//! Run with `cargo test -p ssl-loglib-wasm --target wasm32-unknown-unknown`, using `wasm-bindgen-test-runner` as the
+//! runner for that target
+#![cfg(target_arch = "wasm32")]
+
+use serde_json::Value;
+use ssl_loglib::MessageBody;
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+use ssl_loglib::raw::{RawMessage, write_raw_message};
+use ssl_loglib::test_util::{START, message, referee, tracked};
+use ssl_loglib_wasm::{LogReader, LogSnapshots};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// A log with a referee command change after one second and a tracker frame every half second
+fn test_log() -> Vec<u8> {
+    let mut data = b"SSL_LOG_FILE".to_vec();
+    data.extend_from_slice(&1i32.to_be_bytes());
+    let messages = [
+        message(0.0, MessageBody::Refbox2013(referee(0.0, Stage::NORMAL_FIRST_HALF, Command::HALT, 0))),
+        message(0.5, MessageBody::VisionTracker2020(tracked("Tracker", 1, 1.0, None, &[]))),
+        message(1.0, MessageBody::Refbox2013(referee(1.0, Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 1))),
+        message(1.5, MessageBody::VisionTracker2020(tracked("Tracker", 2, 2.0, None, &[]))),
+    ];
+    for message in &messages {
+        write_raw_message(&mut data, &RawMessage::try_from(message).unwrap()).unwrap();
+    }
+    data
+}
+
+fn to_json(value: JsValue) -> Value {
+    serde_wasm_bindgen::from_value(value).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn reader_returns_every_message() {
+    let mut reader = LogReader::new(test_log()).unwrap();
+    let mut types = Vec::new();
+    loop {
+        let message = reader.next_message().unwrap();
+        if message.is_undefined() {
+            break;
+        }
+        types.push(to_json(message)["type"].as_str().unwrap().to_string());
+    }
+    assert_eq!(types, ["Refbox2013", "VisionTracker2020", "Refbox2013", "VisionTracker2020"]);
+}
+
+#[wasm_bindgen_test]
+fn snapshots_look_up_the_latest_messages() {
+    let mut snapshots = LogSnapshots::new(test_log()).unwrap();
+    assert_eq!(snapshots.start_time(), START as f64 / 1.0e6);
+    assert_eq!(snapshots.duration(), 1.5);
+    assert_eq!(snapshots.tracker_sources(), ["Tracker"]);
+
+    assert_eq!(to_json(snapshots.referee_at(0.9).unwrap())["command"], "HALT");
+    assert_eq!(to_json(snapshots.referee_at(1.0).unwrap())["command"], "FORCE_START");
+    // Looking up an earlier time again reads the message from its stored offset
+    assert_eq!(to_json(snapshots.referee_at(0.0).unwrap())["command"], "HALT");
+
+    assert!(snapshots.tracker_at(0.4, None).unwrap().is_undefined());
+    let tracker = to_json(snapshots.tracker_at(2.0, Some("Tracker".to_string())).unwrap());
+    assert_eq!(tracker["tracked_frame"]["frame_number"], 2);
+    let tracker = to_json(snapshots.tracker_at(1.0, Some("Tracker-uuid".to_string())).unwrap());
+    assert_eq!(tracker["tracked_frame"]["frame_number"], 1);
+    assert!(snapshots.tracker_at(2.0, Some("Other".to_string())).unwrap().is_undefined());
+}
@@ -0,0 +1,308 @@
+use crate::analysis::{Analyzer, Histogram, PerCamera, run};
+use crate::LogMessage;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// Detections are binned from -EXTENT to EXTENT millimeters for the coverage, which includes the largest field and
+/// its boundary. Detections outside the range are counted in the outermost bins.
+const COVERAGE_EXTENT: f64 = 8000.0;
+/// Coverage bin width in millimeters, which limits the precision of the coverage region
+const COVERAGE_BIN_WIDTH: f64 = 10.0;
+/// A frame number this far below the highest one is a vision restart rather than a late frame
+const RESTART_THRESHOLD: u32 = 1000;
+
+/// Part of the field seen by a camera, in millimeters
+#[derive(Clone, Copy, Debug)]
+pub struct Coverage {
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_y: f64,
+    pub max_y: f64,
+}
+
+impl Coverage {
+    /// Area in square meters
+    pub fn area(&self) -> f64 {
+        (self.max_x - self.min_x) * (self.max_y - self.min_y) / 1.0e6
+    }
+}
+
+/// Detection statistics for one camera
+#[derive(Clone, Debug)]
+pub struct CameraStats {
+    pub camera_id: u32,
+    /// Number of detection frames received from the camera
+    pub frames: usize,
+    /// Frames per second, based on `t_capture`
+    pub frame_rate: Option<f64>,
+    /// Frames missing from the sequence of `frame_number` values
+    pub dropped_frames: u64,
+    /// Frames received after a frame with a higher `frame_number`, which were not counted as dropped
+    pub out_of_order_frames: u64,
+    /// Frames received again with a `frame_number` that was already received
+    pub duplicate_frames: u64,
+    /// Number of times the `frame_number` jumped far back, which happens when the vision software restarts
+    pub restarts: u64,
+    /// Number of frames with at least one ball detection
+    pub frames_with_ball: usize,
+    /// Total number of robot detections of both teams
+    pub robot_detections: usize,
+    /// Region containing the middle 98% of ball and robot detections along each axis
+    pub coverage: Option<Coverage>,
+}
+
+impl CameraStats {
+    /// Fraction of frames with at least one ball detection
+    pub fn ball_fraction(&self) -> f64 {
+        self.frames_with_ball as f64 / self.frames.max(1) as f64
+    }
+
+    /// Average number of robots detected per frame
+    pub fn average_robots(&self) -> f64 {
+        self.robot_detections as f64 / self.frames.max(1) as f64
+    }
+
+    /// Fraction of frames that were dropped
+    pub fn drop_fraction(&self) -> f64 {
+        let expected = self.frames as u64 + self.dropped_frames;
+        self.dropped_frames as f64 / expected.max(1) as f64
+    }
+}
+
+/// Follows the `frame_number` sequence of one camera to count dropped, late, and duplicate frames
+#[derive(Default)]
+struct FrameSequence {
+    lowest: Option<u32>,
+    highest: Option<u32>,
+    /// Missing frame numbers that may still arrive late, as ranges from the first to the last missing number
+    ///
+    /// Gaps more than [`RESTART_THRESHOLD`] frames behind the highest frame number are forgotten, so this stays
+    /// small however long the log is.
+    gaps: BTreeMap<u32, u32>,
+    dropped: u64,
+    out_of_order: u64,
+    duplicates: u64,
+    restarts: u64,
+}
+
+impl FrameSequence {
+    fn push(&mut self, frame_number: u32) {
+        let (Some(lowest), Some(highest)) = (self.lowest, self.highest) else {
+            self.restart(frame_number);
+            return;
+        };
+        if frame_number > highest {
+            if frame_number > highest + 1 {
+                self.gaps.insert(highest + 1, frame_number - 1);
+                self.dropped += u64::from(frame_number - highest - 1);
+            }
+            self.highest = Some(frame_number);
+            let oldest_kept = frame_number.saturating_sub(RESTART_THRESHOLD);
+            self.gaps.retain(|_, last| *last >= oldest_kept);
+        } else if highest - frame_number > RESTART_THRESHOLD {
+            self.restarts += 1;
+            self.restart(frame_number);
+        } else if frame_number < lowest {
+            // A late frame from before the first frame that was received was never counted as dropped
+            self.out_of_order += 1;
+            self.lowest = Some(frame_number);
+        } else if self.fill_gap(frame_number) {
+            self.dropped -= 1;
+            self.out_of_order += 1;
+        } else {
+            self.duplicates += 1;
+        }
+    }
+
+    fn restart(&mut self, frame_number: u32) {
+        self.lowest = Some(frame_number);
+        self.highest = Some(frame_number);
+        self.gaps.clear();
+    }
+
+    /// Removes `frame_number` from the gap containing it, returning false if it is not missing
+    fn fill_gap(&mut self, frame_number: u32) -> bool {
+        let Some((&first, &last)) = self.gaps.range(..=frame_number).next_back() else {
+            return false;
+        };
+        if frame_number > last {
+            return false;
+        }
+        self.gaps.remove(&first);
+        if first < frame_number {
+            self.gaps.insert(first, frame_number - 1);
+        }
+        if frame_number < last {
+            self.gaps.insert(frame_number + 1, last);
+        }
+        true
+    }
+}
+
+struct CameraState {
+    frames: usize,
+    first_capture: Option<f64>,
+    last_capture: f64,
+    sequence: FrameSequence,
+    frames_with_ball: usize,
+    robot_detections: usize,
+    xs: Histogram,
+    ys: Histogram,
+}
+
+impl Default for CameraState {
+    fn default() -> Self {
+        let axis = || Histogram::new(-COVERAGE_EXTENT, COVERAGE_EXTENT, COVERAGE_BIN_WIDTH);
+        CameraState {
+            frames: 0,
+            first_capture: None,
+            last_capture: 0.0,
+            sequence: FrameSequence::default(),
+            frames_with_ball: 0,
+            robot_detections: 0,
+            xs: axis(),
+            ys: axis(),
+        }
+    }
+}
+
+/// Collects per-camera detection statistics from log messages
+#[derive(Default)]
+pub struct CameraStatsAnalyzer {
+    cameras: PerCamera<CameraState>,
+}
+
+impl CameraStatsAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Analyzer for CameraStatsAnalyzer {
+    type Output = Vec<CameraStats>;
+
+    /// Adds the detection frame in `message`, if it has one
+    fn push(&mut self, message: &LogMessage) {
+        let Some((state, detection)) = self.cameras.get(message) else {
+            return;
+        };
+        state.frames += 1;
+        state.first_capture.get_or_insert(detection.t_capture());
+        state.last_capture = state.last_capture.max(detection.t_capture());
+
+        state.sequence.push(detection.frame_number());
+
+        if !detection.balls.is_empty() {
+            state.frames_with_ball += 1;
+        }
+        state.robot_detections += detection.robots_yellow.len() + detection.robots_blue.len();
+
+        let balls = detection.balls.iter().map(|b| (b.x(), b.y()));
+        let robots = detection.robots_yellow.iter().chain(&detection.robots_blue).map(|r| (r.x(), r.y()));
+        for (x, y) in balls.chain(robots) {
+            state.xs.add(f64::from(x));
+            state.ys.add(f64::from(y));
+        }
+    }
+
+    /// Returns the statistics of each camera, ordered by camera ID
+    fn finish(self) -> Vec<CameraStats> {
+        self.cameras
+            .into_cameras()
+            .into_iter()
+            .map(|(camera_id, state)| {
+                let (xs, ys) = (&state.xs, &state.ys);
+                let coverage = match (xs.percentile(1.0), xs.percentile(99.0), ys.percentile(1.0), ys.percentile(99.0)) {
+                    (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) => Some(Coverage { min_x, max_x, min_y, max_y }),
+                    _ => None,
+                };
+                let capture_span = state.last_capture - state.first_capture.unwrap_or_default();
+                CameraStats {
+                    camera_id,
+                    frames: state.frames,
+                    frame_rate: (capture_span > 0.0).then(|| (state.frames - 1) as f64 / capture_span),
+                    dropped_frames: state.sequence.dropped,
+                    out_of_order_frames: state.sequence.out_of_order,
+                    duplicate_frames: state.sequence.duplicates,
+                    restarts: state.sequence.restarts,
+                    frames_with_ball: state.frames_with_ball,
+                    robot_detections: state.robot_detections,
+                    coverage,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Reads the log at `path` and returns the detection statistics of each camera
+pub fn analyze_cameras(path: impl AsRef<Path>) -> io::Result<Vec<CameraStats>> {
+    run(path, CameraStatsAnalyzer::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBody;
+    use crate::test_util::{detection, message};
+
+    fn stats(frames: &[(u32, u32)]) -> Vec<CameraStats> {
+        let mut analyzer = CameraStatsAnalyzer::new();
+        for (i, &(camera_id, frame_number)) in frames.iter().enumerate() {
+            let t_capture = i as f64 / 60.0;
+            let packet = detection(camera_id, frame_number, t_capture, &[[1.0, 2.0]], &[]);
+            analyzer.push(&message(t_capture, MessageBody::Vision2014(packet)));
+        }
+        analyzer.finish()
+    }
+
+    fn sequence(frame_numbers: &[u32]) -> FrameSequence {
+        let mut sequence = FrameSequence::default();
+        for &frame_number in frame_numbers {
+            sequence.push(frame_number);
+        }
+        sequence
+    }
+
+    #[test]
+    fn late_frames_only_count_against_drops_they_fill() {
+        let sequence = sequence(&[10, 11, 14, 12, 15]);
+        assert_eq!(sequence.dropped, 1);
+        assert_eq!(sequence.out_of_order, 1);
+        assert_eq!(sequence.duplicates, 0);
+    }
+
+    #[test]
+    fn duplicates_are_counted_separately() {
+        let sequence = sequence(&[10, 11, 11, 13, 10, 12, 12]);
+        assert_eq!(sequence.dropped, 0);
+        assert_eq!(sequence.out_of_order, 1);
+        assert_eq!(sequence.duplicates, 3);
+    }
+
+    #[test]
+    fn restarts_reseed_the_sequence() {
+        let sequence = sequence(&[50_000, 50_001, 50_003, 0, 1, 3, 2]);
+        assert_eq!(sequence.restarts, 1);
+        // One frame was dropped before and after the restart, and the late frame 2 filled the second gap
+        assert_eq!(sequence.dropped, 1);
+        assert_eq!(sequence.out_of_order, 1);
+        assert_eq!(sequence.duplicates, 0);
+    }
+
+    #[test]
+    fn cameras_are_counted_separately() {
+        let cameras = stats(&[(0, 1), (1, 100), (0, 2), (1, 102), (0, 3), (1, 103)]);
+        assert_eq!(cameras.len(), 2);
+        assert_eq!((cameras[0].camera_id, cameras[0].frames, cameras[0].dropped_frames), (0, 3, 0));
+        assert_eq!((cameras[1].camera_id, cameras[1].frames, cameras[1].dropped_frames), (1, 3, 1));
+        assert_eq!(cameras[0].ball_fraction(), 1.0);
+    }
+
+    #[test]
+    fn coverage_percentiles_come_from_the_histogram() {
+        let coverage = stats(&[(0, 1), (0, 2)])[0].coverage.unwrap();
+        assert!((coverage.min_x - 1000.0).abs() <= COVERAGE_BIN_WIDTH);
+        assert!((coverage.max_y - 2000.0).abs() <= COVERAGE_BIN_WIDTH);
+    }
+}
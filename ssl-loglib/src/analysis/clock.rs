@@ -0,0 +1,286 @@
+use crate::analysis::{Analyzer, run};
+use crate::{LogMessage, MessageBody};
+use std::io;
+use std::path::Path;
+
+/// The clocks that timestamps in a log are measured on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Clock {
+    /// Recorder clock, used for the receive timestamp of every message
+    Receive,
+    /// Vision PC clock, used for `t_capture` and `t_sent` in detection frames
+    Vision,
+    /// Game controller clock, used for `packet_timestamp` and `command_timestamp` in referee messages
+    Referee,
+}
+
+/// Linear relation between the receive clock and another clock
+///
+/// All times are in seconds since the Unix epoch.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockFit {
+    /// Receive time the fit is centered on
+    pub reference: f64,
+    /// Other clock minus receive clock at `reference`
+    ///
+    /// Messages are received some time after they are stamped, so this includes the smallest transmission delay.
+    pub offset: f64,
+    /// Seconds the other clock gains per second of receive time
+    pub drift: f64,
+    /// RMS distance of the fitted points from the line, in seconds
+    pub residual: f64,
+    /// Number of messages the fit is based on
+    pub samples: usize,
+}
+
+impl ClockFit {
+    /// Converts a receive time to the other clock
+    pub fn from_receive(&self, receive_time: f64) -> f64 {
+        receive_time + self.offset + self.drift * (receive_time - self.reference)
+    }
+
+    /// Converts a time on the other clock to receive time
+    pub fn to_receive(&self, time: f64) -> f64 {
+        (time - self.offset + self.drift * self.reference) / (1.0 + self.drift)
+    }
+
+    /// Fits a line through the smallest delay in each window of `samples`, given as (receive time, other clock)
+    ///
+    /// Samples may be in any order. Samples with a time on the other clock that is not positive were not stamped by
+    /// the sender and are ignored.
+    fn new(mut samples: Vec<(f64, f64)>) -> Option<ClockFit> {
+        samples.retain(|(receive_time, time)| receive_time.is_finite() && time.is_finite() && *time > 0.0);
+        // Receive times can go backwards when the recorder clock is adjusted or inputs were merged
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let first = samples.first()?.0;
+        let last = samples.last()?.0;
+        let span = last - first;
+        // Windows all have the same length, so the last one is not a short window with only a few samples
+        let window_count = (span / (span / FIT_WINDOWS).clamp(MIN_WINDOW, MAX_WINDOW)).ceil().max(1.0);
+        let window = span / window_count;
+
+        // A message stamped on the other clock and received with the smallest delay has the largest
+        // other minus receive difference, so keep the largest difference in each window
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        let mut current_window = None;
+        for &(receive_time, time) in &samples {
+            let window_index = if window > 0.0 {
+                ((receive_time - first) / window).min(window_count - 1.0) as u64
+            } else {
+                0
+            };
+            let difference = time - receive_time;
+            match points.last_mut() {
+                Some(point) if current_window == Some(window_index) => {
+                    if difference > point.1 {
+                        *point = (receive_time, difference);
+                    }
+                }
+                _ => {
+                    points.push((receive_time, difference));
+                    current_window = Some(window_index);
+                }
+            }
+        }
+
+        // A sender with a badly wrong timestamp in one window would pull the whole line towards it
+        let mut differences: Vec<f64> = points.iter().map(|(_, d)| *d).collect();
+        differences.sort_by(f64::total_cmp);
+        let median = differences[differences.len() / 2];
+        points.retain(|(_, d)| (d - median).abs() <= MAX_OUTLIER);
+
+        let count = points.len() as f64;
+        let reference = points.iter().map(|(r, _)| r).sum::<f64>() / count;
+        let mean_difference = points.iter().map(|(_, d)| d).sum::<f64>() / count;
+        let variance = points.iter().map(|(r, _)| (r - reference).powi(2)).sum::<f64>();
+        let drift = if variance > 0.0 {
+            points.iter().map(|(r, d)| (r - reference) * (d - mean_difference)).sum::<f64>() / variance
+        } else {
+            0.0
+        };
+        let residual = (points
+            .iter()
+            .map(|(r, d)| (d - mean_difference - drift * (r - reference)).powi(2))
+            .sum::<f64>()
+            / count)
+            .sqrt();
+        Some(ClockFit {
+            reference,
+            offset: mean_difference,
+            drift,
+            residual,
+            samples: samples.len(),
+        })
+    }
+}
+
+/// Number of windows a log is split into when fitting a clock
+const FIT_WINDOWS: f64 = 50.0;
+/// Shortest fit window in seconds, before the log is split into windows of equal length
+const MIN_WINDOW: f64 = 1.0;
+/// Longest fit window in seconds, before the log is split into windows of equal length
+const MAX_WINDOW: f64 = 10.0;
+/// Windows whose clock difference is further than this many seconds from the median are left out of the fit
+const MAX_OUTLIER: f64 = 1.0;
+
+/// Estimated relation between the clocks in a log
+#[derive(Clone, Debug, Default)]
+pub struct ClockModel {
+    /// Vision clock, if the log has detection frames
+    pub vision: Option<ClockFit>,
+    /// Referee clock, if the log has referee messages
+    pub referee: Option<ClockFit>,
+}
+
+impl ClockModel {
+    pub fn fit(&self, clock: Clock) -> Option<&ClockFit> {
+        match clock {
+            Clock::Receive => None,
+            Clock::Vision => self.vision.as_ref(),
+            Clock::Referee => self.referee.as_ref(),
+        }
+    }
+
+    /// Converts `time` in seconds from clock `from` to clock `to`
+    ///
+    /// Returns `None` if either clock is not in the model.
+    pub fn convert(&self, time: f64, from: Clock, to: Clock) -> Option<f64> {
+        let receive_time = match from {
+            Clock::Receive => time,
+            _ => self.fit(from)?.to_receive(time),
+        };
+        match to {
+            Clock::Receive => Some(receive_time),
+            _ => Some(self.fit(to)?.from_receive(receive_time)),
+        }
+    }
+
+    /// Converts a referee timestamp in microseconds, like `command_timestamp`, to vision time in seconds
+    pub fn referee_to_vision(&self, referee_timestamp: u64) -> Option<f64> {
+        self.convert(referee_timestamp as f64 / 1.0e6, Clock::Referee, Clock::Vision)
+    }
+
+    /// Converts a vision time in seconds, like `t_capture`, to a referee timestamp in microseconds
+    pub fn vision_to_referee(&self, vision_time: f64) -> Option<u64> {
+        self.convert(vision_time, Clock::Vision, Clock::Referee)
+            .map(|t| (t * 1.0e6).round() as u64)
+    }
+}
+
+/// Collects timestamps from log messages to estimate a [`ClockModel`]
+#[derive(Default)]
+pub struct ClockAnalyzer {
+    vision: Vec<(f64, f64)>,
+    legacy_vision: Vec<(f64, f64)>,
+    referee: Vec<(f64, f64)>,
+}
+
+impl ClockAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Analyzer for ClockAnalyzer {
+    type Output = ClockModel;
+
+    fn push(&mut self, message: &LogMessage) {
+        let receive_time = message.timestamp.timestamp_nanos_opt().unwrap_or_default() as f64 / 1.0e9;
+        match &message.body {
+            MessageBody::Vision2014(packet) => {
+                if let Some(detection) = packet.detection.as_ref() {
+                    self.vision.push((receive_time, detection.t_sent()));
+                }
+            }
+            MessageBody::Vision2010(packet) => {
+                if let Some(detection) = packet.detection.as_ref() {
+                    self.legacy_vision.push((receive_time, detection.t_sent()));
+                }
+            }
+            MessageBody::Refbox2013(referee) => {
+                self.referee.push((receive_time, referee.packet_timestamp() as f64 / 1.0e6));
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> ClockModel {
+        let vision = if self.vision.is_empty() { self.legacy_vision } else { self.vision };
+        ClockModel {
+            vision: ClockFit::new(vision),
+            referee: ClockFit::new(self.referee),
+        }
+    }
+}
+
+/// Reads the log at `path` and estimates the relation between its clocks
+pub fn analyze_clocks(path: impl AsRef<Path>) -> io::Result<ClockModel> {
+    run(path, ClockAnalyzer::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use crate::test_util::{message, referee};
+
+    const EPOCH: f64 = 1.7e9;
+
+    /// Samples every 10 ms for two minutes from a clock that is `offset` ahead and gains `drift` seconds per
+    /// second, received with a delay between 1 and 5 ms
+    fn samples(offset: f64, drift: f64) -> Vec<(f64, f64)> {
+        (0..12_000)
+            .map(|i| {
+                let receive_time = EPOCH + i as f64 * 0.01;
+                let delay = 0.001 + 0.004 * ((i * 7919) % 100) as f64 / 100.0;
+                (receive_time, receive_time + offset + drift * (receive_time - EPOCH) - delay)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fit_recovers_offset_and_drift() {
+        let fit = ClockFit::new(samples(2.5, 2.0e-5)).unwrap();
+        assert!((fit.drift - 2.0e-5).abs() < 1.0e-6, "{}", fit.drift);
+        let offset_at_start = fit.from_receive(EPOCH) - EPOCH;
+        assert!((offset_at_start - 2.499).abs() < 1.0e-4, "{}", offset_at_start);
+        assert!((fit.to_receive(fit.from_receive(EPOCH + 30.0)) - (EPOCH + 30.0)).abs() < 1.0e-6);
+        assert_eq!(fit.samples, 12_000);
+    }
+
+    #[test]
+    fn fit_ignores_unset_timestamps_and_receive_order() {
+        let mut samples = samples(0.5, 0.0);
+        samples.reverse();
+        samples.extend((0..100).map(|i| (EPOCH + i as f64, 0.0)));
+        let fit = ClockFit::new(samples).unwrap();
+        assert!((fit.offset - 0.499).abs() < 1.0e-4, "{}", fit.offset);
+        assert_eq!(fit.samples, 12_000);
+        assert!(ClockFit::new(vec![(EPOCH, 0.0)]).is_none());
+    }
+
+    #[test]
+    fn fit_leaves_out_implausible_windows() {
+        let mut samples = samples(0.5, 0.0);
+        samples.push((EPOCH + 60.0, EPOCH + 3600.0));
+        let fit = ClockFit::new(samples).unwrap();
+        assert!((fit.offset - 0.499).abs() < 1.0e-4, "{}", fit.offset);
+    }
+
+    #[test]
+    fn referee_messages_without_packet_timestamp_are_skipped() {
+        let mut analyzer = ClockAnalyzer::new();
+        for i in 0..20 {
+            let mut packet = referee(i as f64 * 0.1, Stage::NORMAL_FIRST_HALF, Command::STOP, 0);
+            if i % 2 == 0 {
+                packet.packet_timestamp = Some(0);
+            }
+            analyzer.push(&message(i as f64 * 0.1, MessageBody::Refbox2013(packet)));
+        }
+        let model = analyzer.finish();
+        let fit = model.referee.unwrap();
+        assert_eq!(fit.samples, 10);
+        assert!(fit.offset.abs() < 1.0e-5, "{}", fit.offset);
+        assert!(model.vision.is_none());
+    }
+}
@@ -0,0 +1,320 @@
+use crate::analysis::{Analyzer, run};
+use crate::protos::vision::messages_robocup_ssl_detection::SSL_DetectionFrame;
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+use crate::tracker::RobotKey;
+use crate::{LogMessage, MessageBody};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+use std::path::Path;
+
+/// Kind of suspicious robot detection
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IdentityIssue {
+    /// A robot moved further than it could have since it was last detected
+    Jump,
+    /// The same team and ID was detected at two distinct positions at the same time
+    DuplicateId,
+    /// More robots of a team were detected than the referee allows on the field
+    ExtraRobots,
+}
+
+/// A period in which one kind of issue was detected repeatedly
+#[derive(Clone, Debug)]
+pub struct SuspiciousSegment {
+    pub issue: IdentityIssue,
+    pub team: TeamColor,
+    /// Robot ID, or `None` for issues that concern the whole team
+    pub id: Option<u32>,
+    /// Receive time of the first detection frame with the issue
+    pub start: DateTime<Utc>,
+    /// Receive time of the last detection frame with the issue
+    pub end: DateTime<Utc>,
+    /// Number of detections with the issue
+    pub count: usize,
+    /// Largest jump or separation in meters, or the largest number of robots for extra robots
+    pub worst: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct IdentityOptions {
+    /// Highest plausible robot speed in meters per second
+    pub max_speed: f64,
+    /// Distance in meters a robot may move between detections regardless of time, to allow for detection noise
+    pub jump_tolerance: f64,
+    /// Distance in meters between two detections of the same robot above which they are counted as a duplicate
+    pub duplicate_distance: f64,
+    /// Detections from different cameras within this many seconds are treated as simultaneous
+    pub simultaneous_window: f64,
+    /// Issues of the same kind less than this many seconds apart are merged into one segment
+    pub segment_gap: f64,
+}
+
+impl Default for IdentityOptions {
+    fn default() -> Self {
+        IdentityOptions {
+            max_speed: 6.0,
+            jump_tolerance: 0.15,
+            duplicate_distance: 0.3,
+            simultaneous_window: 0.02,
+            segment_gap: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RobotDetection {
+    t_capture: f64,
+    pos: (f64, f64),
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// Finds robot detections that suggest misidentified or phantom robots
+pub struct IdentityAnalyzer {
+    options: IdentityOptions,
+    /// Latest detection of each robot from any camera
+    last_detection: BTreeMap<RobotKey, RobotDetection>,
+    /// Latest detection of each robot from each camera
+    camera_detections: BTreeMap<(RobotKey, u32), RobotDetection>,
+    /// Capture time and detected robots of the latest frame from each camera
+    camera_frames: BTreeMap<u32, (f64, Vec<RobotKey>)>,
+    max_allowed_bots: HashMap<TeamColor, u32>,
+    segments: Vec<SuspiciousSegment>,
+    /// Index into `segments` of the open segment for each issue, team, and ID
+    open_segments: HashMap<(IdentityIssue, TeamColor, Option<u32>), usize>,
+    has_current_vision: bool,
+}
+
+impl IdentityAnalyzer {
+    pub fn new(options: IdentityOptions) -> Self {
+        IdentityAnalyzer {
+            options,
+            last_detection: BTreeMap::new(),
+            camera_detections: BTreeMap::new(),
+            camera_frames: BTreeMap::new(),
+            max_allowed_bots: HashMap::new(),
+            segments: Vec::new(),
+            open_segments: HashMap::new(),
+            has_current_vision: false,
+        }
+    }
+
+    fn push_frame(&mut self, timestamp: DateTime<Utc>, frame: &SSL_DetectionFrame) {
+        let t_capture = frame.t_capture();
+        let camera_id = frame.camera_id();
+        let yellow = frame.robots_yellow.iter().map(|r| (TeamColor::TEAM_COLOR_YELLOW, r));
+        let blue = frame.robots_blue.iter().map(|r| (TeamColor::TEAM_COLOR_BLUE, r));
+
+        let mut frame_detections: BTreeMap<RobotKey, Vec<(f64, f64)>> = BTreeMap::new();
+        for (team, robot) in yellow.chain(blue) {
+            let Some(id) = robot.robot_id else {
+                continue;
+            };
+            let pos = (f64::from(robot.x()) / 1000.0, f64::from(robot.y()) / 1000.0);
+            frame_detections.entry(RobotKey { team, id }).or_default().push(pos);
+        }
+
+        for (robot, positions) in &frame_detections {
+            // Two detections of one ID in a single frame
+            let separation = positions
+                .iter()
+                .flat_map(|a| positions.iter().map(move |b| distance(*a, *b)))
+                .fold(0.0, f64::max);
+            if separation > self.options.duplicate_distance {
+                self.report(IdentityIssue::DuplicateId, robot.team, Some(robot.id), timestamp, separation);
+            }
+
+            // Detections of the same ID by another camera at the same time
+            let camera_separation = self
+                .camera_detections
+                .range((*robot, 0)..=(*robot, u32::MAX))
+                .filter(|((_, other_camera), other)| {
+                    *other_camera != camera_id
+                        && (other.t_capture - t_capture).abs() <= self.options.simultaneous_window
+                })
+                .map(|(_, other)| positions.iter().map(|p| distance(*p, other.pos)).fold(f64::INFINITY, f64::min))
+                .fold(0.0, f64::max);
+            if camera_separation > self.options.duplicate_distance {
+                self.report(IdentityIssue::DuplicateId, robot.team, Some(robot.id), timestamp, camera_separation);
+            }
+
+            // Movement since the last detection from any camera
+            if let Some(last) = self.last_detection.get(robot)
+                && t_capture > last.t_capture
+            {
+                let jump = positions.iter().map(|p| distance(*p, last.pos)).fold(f64::INFINITY, f64::min);
+                let allowed = self.options.jump_tolerance + self.options.max_speed * (t_capture - last.t_capture);
+                if jump > allowed {
+                    self.report(IdentityIssue::Jump, robot.team, Some(robot.id), timestamp, jump);
+                }
+            }
+
+            let detection = RobotDetection {
+                t_capture,
+                pos: positions[0],
+            };
+            let newer = self.last_detection.get(robot).is_none_or(|last| last.t_capture <= t_capture);
+            if newer {
+                self.last_detection.insert(*robot, detection);
+            }
+            self.camera_detections.insert((*robot, camera_id), detection);
+        }
+
+        // Robots seen by all cameras at about the same time, counted once per ID
+        self.camera_frames.insert(camera_id, (t_capture, frame_detections.into_keys().collect()));
+        let mut visible: BTreeSet<RobotKey> = BTreeSet::new();
+        for (camera_t_capture, robots) in self.camera_frames.values() {
+            if (camera_t_capture - t_capture).abs() <= self.options.simultaneous_window {
+                visible.extend(robots);
+            }
+        }
+        for (team, max_allowed_bots) in self.max_allowed_bots.clone() {
+            let count = visible.iter().filter(|r| r.team == team).count();
+            if count > max_allowed_bots as usize {
+                self.report(IdentityIssue::ExtraRobots, team, None, timestamp, count as f64);
+            }
+        }
+    }
+
+    fn report(&mut self, issue: IdentityIssue, team: TeamColor, id: Option<u32>, timestamp: DateTime<Utc>, value: f64) {
+        let key = (issue, team, id);
+        if let Some(&index) = self.open_segments.get(&key) {
+            let segment = &mut self.segments[index];
+            if (timestamp - segment.end).as_seconds_f64() <= self.options.segment_gap {
+                segment.end = timestamp;
+                segment.count += 1;
+                segment.worst = segment.worst.max(value);
+                return;
+            }
+        }
+        self.open_segments.insert(key, self.segments.len());
+        self.segments.push(SuspiciousSegment {
+            issue,
+            team,
+            id,
+            start: timestamp,
+            end: timestamp,
+            count: 1,
+            worst: value,
+        });
+    }
+}
+
+impl Analyzer for IdentityAnalyzer {
+    type Output = Vec<SuspiciousSegment>;
+
+    fn push(&mut self, message: &LogMessage) {
+        match &message.body {
+            MessageBody::Refbox2013(referee) => {
+                for (team, info) in [(TeamColor::TEAM_COLOR_YELLOW, &referee.yellow), (TeamColor::TEAM_COLOR_BLUE, &referee.blue)] {
+                    if let Some(max_allowed_bots) = info.max_allowed_bots {
+                        self.max_allowed_bots.insert(team, max_allowed_bots);
+                    }
+                }
+            }
+            MessageBody::Vision2014(packet) => {
+                self.has_current_vision = true;
+                if let Some(frame) = packet.detection.as_ref() {
+                    self.push_frame(message.timestamp, frame);
+                }
+            }
+            // Legacy frames usually duplicate Vision2014 frames, so they are only used in logs without any
+            MessageBody::Vision2010(packet) if !self.has_current_vision => {
+                if let Some(frame) = packet.detection.as_ref() {
+                    self.push_frame(message.timestamp, frame);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the suspicious segments in order of their start time
+    fn finish(self) -> Vec<SuspiciousSegment> {
+        self.segments
+    }
+}
+
+/// Reads the log at `path` and returns periods of suspicious robot detections
+pub fn analyze_identities(path: impl AsRef<Path>, options: IdentityOptions) -> io::Result<Vec<SuspiciousSegment>> {
+    run(path, IdentityAnalyzer::new(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use crate::test_util::{detection, message, referee};
+
+    const YELLOW: TeamColor = TeamColor::TEAM_COLOR_YELLOW;
+
+    fn analyze(messages: &[LogMessage]) -> Vec<SuspiciousSegment> {
+        let mut analyzer = IdentityAnalyzer::new(IdentityOptions::default());
+        for message in messages {
+            analyzer.push(message);
+        }
+        analyzer.finish()
+    }
+
+    /// A detection frame from `camera_id` at `time` seconds, received at the same time
+    fn frame(camera_id: u32, frame_number: u32, time: f64, robots: &[(u32, [f64; 2])]) -> LogMessage {
+        message(time, MessageBody::Vision2014(detection(camera_id, frame_number, time, &[], robots)))
+    }
+
+    #[test]
+    fn swapped_ids_are_reported_as_jumps() {
+        // Robots 1 and 2 stand 2 m apart, and vision swaps their IDs from frame 50 to 59
+        let messages: Vec<LogMessage> = (0..120u32)
+            .map(|i| {
+                let swapped = (50..60).contains(&i);
+                let (one, two) = if swapped { ([2.0, 0.0], [0.0, 0.0]) } else { ([0.0, 0.0], [2.0, 0.0]) };
+                frame(0, i, f64::from(i) / 60.0, &[(1, one), (2, two)])
+            })
+            .collect();
+        let segments = analyze(&messages);
+        assert_eq!(segments.len(), 2);
+        for (segment, id) in segments.iter().zip([1, 2]) {
+            assert_eq!((segment.issue, segment.team, segment.id), (IdentityIssue::Jump, YELLOW, Some(id)));
+            // The swap and the swap back are less than a segment gap apart
+            assert_eq!(segment.count, 2);
+            assert!((segment.worst - 2.0).abs() < 1.0e-6, "{}", segment.worst);
+            assert_eq!(segment.start, messages[50].timestamp);
+            assert_eq!(segment.end, messages[60].timestamp);
+        }
+    }
+
+    #[test]
+    fn slow_movement_is_not_a_jump() {
+        let messages: Vec<LogMessage> = (0..120u32)
+            .map(|i| frame(0, i, f64::from(i) / 60.0, &[(1, [f64::from(i) * 0.05, 0.0])]))
+            .collect();
+        assert!(analyze(&messages).is_empty());
+    }
+
+    #[test]
+    fn same_id_on_two_cameras_is_a_duplicate() {
+        let messages = [frame(0, 1, 0.0, &[(1, [0.0, 0.0])]), frame(1, 1, 0.001, &[(1, [1.0, 0.0])])];
+        let segments = analyze(&messages);
+        // The second detection is also further from the first than the robot could move in a millisecond
+        let duplicate = segments.iter().find(|s| s.issue == IdentityIssue::DuplicateId).unwrap();
+        assert_eq!((duplicate.id, duplicate.count), (Some(1), 1));
+        assert!((duplicate.worst - 1.0).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn robots_beyond_the_referee_limit_are_extra() {
+        let mut limit = referee(0.0, Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 1);
+        limit.yellow.mut_or_insert_default().max_allowed_bots = Some(2);
+        let messages = [
+            message(0.0, MessageBody::Refbox2013(limit)),
+            frame(0, 1, 0.1, &[(1, [0.0, 0.0]), (2, [1.0, 0.0])]),
+            frame(0, 2, 0.2, &[(1, [0.0, 0.0]), (2, [1.0, 0.0]), (3, [2.0, 0.0])]),
+        ];
+        let segments = analyze(&messages);
+        assert_eq!(segments.len(), 1);
+        assert_eq!((segments[0].issue, segments[0].id), (IdentityIssue::ExtraRobots, None));
+        assert_eq!(segments[0].worst, 3.0);
+    }
+}
@@ -0,0 +1,445 @@
+use crate::analysis::{Analyzer, run};
+use crate::protos::refbox::ssl_gc_common::Team;
+use crate::protos::refbox::ssl_gc_game_event::game_event::Event;
+use crate::protos::refbox::ssl_gc_referee_message::Referee;
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::{KickedBall, TeamColor, TrackedFrame};
+use crate::tracker::{RobotKey, is_from_source, source_label};
+use crate::{LogMessage, MessageBody};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+
+/// Highest ball speed allowed by the rules, in meters per second
+pub const MAX_BALL_SPEED: f64 = 6.5;
+
+/// A ball within this many meters of a robot center is touching the robot
+const POSSESSION_DISTANCE: f64 = 0.15;
+/// Seconds after a kick during which the ball touching the kicker is not counted
+const KICKER_GRACE: f64 = 0.3;
+/// Kicks whose vertical start velocity is above this, in meters per second, are chip kicks
+const CHIP_VELOCITY: f64 = 0.1;
+/// A BotKickedBallTooFast event first seen this long before or after a kick may belong to it
+const EVENT_BEFORE_KICK: TimeDelta = TimeDelta::seconds(1);
+const EVENT_AFTER_KICK: TimeDelta = TimeDelta::seconds(5);
+
+/// What happened to the ball after a kick
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KickOutcome {
+    /// The ball entered a goal. The scoring team is known if the referee reported which half each team defends.
+    Goal { scored_by: Option<TeamColor> },
+    /// The ball left the field outside of the goals
+    Out,
+    /// The ball touched a robot of the kicking team, which may be the kicker
+    Received(RobotKey),
+    /// The ball touched a robot of the other team
+    Intercepted(RobotKey),
+    /// The ball touched a robot and the kicker is unknown
+    Touched(RobotKey),
+    /// None of the above happened before the next kick or the end of the log
+    Unknown,
+}
+
+/// A kick reported by a tracker
+#[derive(Clone, Debug)]
+pub struct Kick {
+    /// Receive time of the first tracker frame that reported the kick
+    pub timestamp: DateTime<Utc>,
+    /// Time of the kick on the tracker clock, in seconds
+    pub start_timestamp: f64,
+    pub kicker: Option<RobotKey>,
+    /// Ball position at the kick in meters
+    pub pos: [f64; 2],
+    /// Ball velocity at the kick in meters per second
+    pub vel: [f64; 3],
+    /// Position where the tracker expects or saw the ball stop, in meters
+    pub stop_pos: Option<[f64; 2]>,
+    pub outcome: KickOutcome,
+    /// Ball position in meters when the outcome happened
+    pub outcome_pos: Option<[f64; 2]>,
+    /// Whether the kick is matched by a BotKickedBallTooFast game event
+    pub too_fast_event: bool,
+}
+
+impl Kick {
+    /// Initial ball speed in meters per second
+    pub fn speed(&self) -> f64 {
+        self.vel.iter().map(|v| v * v).sum::<f64>().sqrt()
+    }
+
+    pub fn is_chip(&self) -> bool {
+        self.vel[2] > CHIP_VELOCITY
+    }
+
+    /// Whether the kick is faster than the rules allow and no BotKickedBallTooFast event was issued for it
+    pub fn is_unpunished_too_fast(&self) -> bool {
+        self.speed() > MAX_BALL_SPEED && !self.too_fast_event
+    }
+}
+
+/// Kicks reported by one tracker source in a log
+#[derive(Clone, Debug, Default)]
+pub struct KickReport {
+    /// Name or UUID of the tracker source that was analyzed, if the log has tracker messages with kicks
+    pub source: Option<String>,
+    /// Kicks in the order they happened
+    pub kicks: Vec<Kick>,
+    /// Field length and width in meters
+    pub field_size: [f64; 2],
+}
+
+impl KickReport {
+    /// Returns up to `count` of the fastest kicks by `team`, fastest first
+    pub fn fastest(&self, team: TeamColor, count: usize) -> Vec<&Kick> {
+        let mut kicks: Vec<&Kick> = self.kicks.iter().filter(|k| k.kicker.is_some_and(|r| r.team == team)).collect();
+        kicks.sort_by(|a, b| b.speed().total_cmp(&a.speed()));
+        kicks.truncate(count);
+        kicks
+    }
+}
+
+/// Field dimensions in meters
+struct Field {
+    length: f64,
+    width: f64,
+    goal_width: f64,
+}
+
+impl Default for Field {
+    /// Division A field, used until the log provides geometry
+    fn default() -> Self {
+        Field {
+            length: 12.0,
+            width: 9.0,
+            goal_width: 1.8,
+        }
+    }
+}
+
+struct TooFastEvent {
+    timestamp: DateTime<Utc>,
+    team: Team,
+    bot: Option<u32>,
+}
+
+/// Extracts kicks from the `kicked_ball` field of one tracker source
+pub struct KickAnalyzer {
+    source: Option<String>,
+    field: Field,
+    blue_on_positive_half: Option<bool>,
+    kicks: Vec<Kick>,
+    /// Whether the last kick in `kicks` is still waiting for an outcome
+    open: bool,
+    too_fast_events: Vec<TooFastEvent>,
+    seen_events: HashSet<String>,
+}
+
+impl KickAnalyzer {
+    /// Creates an analyzer for the tracker with the given source name or UUID
+    ///
+    /// If `source` is `None`, the first tracker source in the log that reports a kick is analyzed.
+    pub fn new(source: Option<String>) -> Self {
+        KickAnalyzer {
+            source,
+            field: Field::default(),
+            blue_on_positive_half: None,
+            kicks: Vec::new(),
+            open: false,
+            too_fast_events: Vec::new(),
+            seen_events: HashSet::new(),
+        }
+    }
+
+    fn push_referee(&mut self, timestamp: DateTime<Utc>, referee: &Referee) {
+        if referee.blue_team_on_positive_half.is_some() {
+            self.blue_on_positive_half = referee.blue_team_on_positive_half;
+        }
+        // Game events are repeated in every referee message until they are cleared, so only the first is kept
+        for game_event in &referee.game_events {
+            let Some(Event::BotKickedBallTooFast(event)) = &game_event.event else {
+                continue;
+            };
+            let key = game_event
+                .id
+                .clone()
+                .unwrap_or_else(|| format!("{:?} {:?}", game_event.created_timestamp, event));
+            if self.seen_events.insert(key) {
+                self.too_fast_events.push(TooFastEvent {
+                    timestamp,
+                    team: event.by_team(),
+                    bot: event.by_bot,
+                });
+            }
+        }
+    }
+
+    fn push_frame(&mut self, timestamp: DateTime<Utc>, frame: &TrackedFrame) {
+        if let Some(kicked_ball) = frame.kicked_ball.as_ref() {
+            let same_kick = self.kicks.last().is_some_and(|kick| {
+                (kick.start_timestamp - kicked_ball.start_timestamp()).abs() < 1.0e-3
+                    && kick.kicker == kicked_ball.robot_id.as_ref().map(RobotKey::from)
+            });
+            if same_kick {
+                let kick = self.kicks.last_mut().expect("same kick was found in the list");
+                kick.stop_pos = kicked_ball.stop_pos.as_ref().map(|p| [f64::from(p.x()), f64::from(p.y())]);
+            } else {
+                self.start_kick(timestamp, kicked_ball);
+            }
+        }
+        if self.open {
+            self.update_outcome(frame);
+        }
+    }
+
+    fn start_kick(&mut self, timestamp: DateTime<Utc>, kicked_ball: &KickedBall) {
+        let kicker = kicked_ball.robot_id.as_ref().map(RobotKey::from);
+        let pos = [f64::from(kicked_ball.pos.x()), f64::from(kicked_ball.pos.y())];
+        // A new kick means the ball touched the new kicker
+        if self.open
+            && let Some(kicker) = kicker
+        {
+            self.set_touched(kicker, pos);
+        }
+        let vel = kicked_ball.vel.as_ref().map_or([0.0; 3], |v| [v.x(), v.y(), v.z()].map(f64::from));
+        self.kicks.push(Kick {
+            timestamp,
+            start_timestamp: kicked_ball.start_timestamp(),
+            kicker,
+            pos,
+            vel,
+            stop_pos: kicked_ball.stop_pos.as_ref().map(|p| [f64::from(p.x()), f64::from(p.y())]),
+            outcome: KickOutcome::Unknown,
+            outcome_pos: None,
+            too_fast_event: false,
+        });
+        self.open = true;
+    }
+
+    fn update_outcome(&mut self, frame: &TrackedFrame) {
+        let Some(kick) = self.kicks.last() else {
+            return;
+        };
+        let Some(ball) = frame.balls.first() else {
+            return;
+        };
+        let ball_pos = [f64::from(ball.pos.x()), f64::from(ball.pos.y())];
+
+        let half_length = self.field.length / 2.0;
+        if ball_pos[0].abs() > half_length && ball_pos[1].abs() < self.field.goal_width / 2.0 {
+            // The team defending the half the goal is on did not score
+            let scored_by = self.blue_on_positive_half.map(|blue_positive| {
+                if blue_positive == (ball_pos[0] > 0.0) {
+                    TeamColor::TEAM_COLOR_YELLOW
+                } else {
+                    TeamColor::TEAM_COLOR_BLUE
+                }
+            });
+            self.set_outcome(KickOutcome::Goal { scored_by }, ball_pos);
+            return;
+        }
+        if ball_pos[0].abs() > half_length || ball_pos[1].abs() > self.field.width / 2.0 {
+            self.set_outcome(KickOutcome::Out, ball_pos);
+            return;
+        }
+
+        let in_grace = frame.timestamp() < kick.start_timestamp + KICKER_GRACE;
+        let touching = frame
+            .robots
+            .iter()
+            .map(|r| {
+                let distance = (f64::from(r.pos.x()) - ball_pos[0]).hypot(f64::from(r.pos.y()) - ball_pos[1]);
+                (RobotKey::from(&*r.robot_id), distance)
+            })
+            .filter(|(robot, distance)| *distance < POSSESSION_DISTANCE && !(in_grace && Some(*robot) == kick.kicker))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((robot, _)) = touching {
+            self.set_touched(robot, ball_pos);
+        }
+    }
+
+    fn set_touched(&mut self, robot: RobotKey, ball_pos: [f64; 2]) {
+        let outcome = match self.kicks.last().and_then(|k| k.kicker) {
+            Some(kicker) if kicker.team == robot.team => KickOutcome::Received(robot),
+            Some(_) => KickOutcome::Intercepted(robot),
+            None => KickOutcome::Touched(robot),
+        };
+        self.set_outcome(outcome, ball_pos);
+    }
+
+    fn set_outcome(&mut self, outcome: KickOutcome, ball_pos: [f64; 2]) {
+        if let Some(kick) = self.kicks.last_mut() {
+            kick.outcome = outcome;
+            kick.outcome_pos = Some(ball_pos);
+        }
+        self.open = false;
+    }
+}
+
+impl Analyzer for KickAnalyzer {
+    type Output = KickReport;
+
+    fn push(&mut self, message: &LogMessage) {
+        match &message.body {
+            MessageBody::Refbox2013(referee) => self.push_referee(message.timestamp, referee),
+            MessageBody::Vision2014(packet) => {
+                if let Some(field) = packet.geometry.as_ref().and_then(|g| g.field.as_ref()) {
+                    self.field = Field {
+                        length: f64::from(field.field_length()) / 1000.0,
+                        width: f64::from(field.field_width()) / 1000.0,
+                        goal_width: f64::from(field.goal_width()) / 1000.0,
+                    };
+                }
+            }
+            MessageBody::VisionTracker2020(packet) => {
+                let Some(frame) = packet.tracked_frame.as_ref() else {
+                    return;
+                };
+                if self.source.is_none() && frame.kicked_ball.is_some() {
+                    self.source = Some(source_label(packet).to_string());
+                }
+                if self.source.as_deref().is_some_and(|source| is_from_source(packet, source)) {
+                    self.push_frame(message.timestamp, frame);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> KickReport {
+        for kick in &mut self.kicks {
+            let Some(kicker) = kick.kicker else {
+                continue;
+            };
+            let team = match kicker.team {
+                TeamColor::TEAM_COLOR_YELLOW => Team::YELLOW,
+                TeamColor::TEAM_COLOR_BLUE => Team::BLUE,
+                TeamColor::TEAM_COLOR_UNKNOWN => Team::UNKNOWN,
+            };
+            kick.too_fast_event = self.too_fast_events.iter().any(|event| {
+                event.team == team
+                    && event.bot.is_none_or(|bot| bot == kicker.id)
+                    && event.timestamp >= kick.timestamp - EVENT_BEFORE_KICK
+                    && event.timestamp <= kick.timestamp + EVENT_AFTER_KICK
+            });
+        }
+        KickReport {
+            source: self.source,
+            kicks: self.kicks,
+            field_size: [self.field.length, self.field.width],
+        }
+    }
+}
+
+/// Reads the log at `path` and returns the kicks reported by a tracker source
+///
+/// `source` is a tracker source name or UUID. If it is `None`, the first tracker source in the log that reports a
+/// kick is analyzed.
+pub fn analyze_kicks(path: impl AsRef<Path>, source: Option<String>) -> io::Result<KickReport> {
+    run(path, KickAnalyzer::new(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::refbox::ssl_gc_game_event::GameEvent;
+    use crate::protos::refbox::ssl_gc_game_event::game_event::BotKickedBallTooFast;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use crate::test_util::{message, referee, tracked, with_kick};
+
+    const YELLOW: TeamColor = TeamColor::TEAM_COLOR_YELLOW;
+    const BLUE: TeamColor = TeamColor::TEAM_COLOR_BLUE;
+
+    /// Two seconds of tracker frames at 100 Hz after yellow robot 1 kicks the ball from the center with `vel`
+    ///
+    /// The kicker stands next to the ball and `robots` stand still.
+    fn kick(vel: [f64; 3], robots: &[(TeamColor, u32, [f64; 2])]) -> Vec<LogMessage> {
+        let robots: Vec<_> = [(YELLOW, 1, [-0.1, 0.0])].into_iter().chain(robots.iter().copied()).collect();
+        (0..200u32)
+            .map(|i| {
+                let time = f64::from(i) * 0.01;
+                let frame = tracked("T", i, time, Some([vel[0] * time, vel[1] * time]), &robots);
+                let frame = with_kick(frame, (YELLOW, 1), 0.0, [0.0, 0.0], vel);
+                message(time, MessageBody::VisionTracker2020(frame))
+            })
+            .collect()
+    }
+
+    fn analyze(messages: &[LogMessage]) -> KickReport {
+        let mut analyzer = KickAnalyzer::new(None);
+        for message in messages {
+            analyzer.push(message);
+        }
+        analyzer.finish()
+    }
+
+    fn robot(team: TeamColor, id: u32) -> RobotKey {
+        RobotKey { team, id }
+    }
+
+    #[test]
+    fn ball_reaching_a_teammate_is_received() {
+        let report = analyze(&kick([2.0, 0.0, 0.0], &[(YELLOW, 2, [1.0, 0.0]), (BLUE, 1, [0.0, 2.0])]));
+        assert_eq!(report.source.as_deref(), Some("T"));
+        assert_eq!(report.kicks.len(), 1);
+        let kick = &report.kicks[0];
+        // The kicker touches the ball at the start, but only during the grace period
+        assert_eq!(kick.kicker, Some(robot(YELLOW, 1)));
+        assert_eq!(kick.outcome, KickOutcome::Received(robot(YELLOW, 2)));
+        assert!((kick.outcome_pos.unwrap()[0] - 0.86).abs() < 1.0e-3, "{:?}", kick.outcome_pos);
+        assert!((kick.speed() - 2.0).abs() < 1.0e-6);
+        assert!(!kick.is_chip());
+    }
+
+    #[test]
+    fn kicker_touching_a_slow_ball_after_the_grace_period_receives_it() {
+        let kick = &analyze(&kick([0.1, 0.0, 0.0], &[])).kicks[0];
+        assert_eq!(kick.outcome, KickOutcome::Received(robot(YELLOW, 1)));
+    }
+
+    #[test]
+    fn ball_reaching_an_opponent_is_intercepted() {
+        let report = analyze(&kick([0.0, 2.0, 0.5], &[(BLUE, 3, [0.0, 1.0])]));
+        assert_eq!(report.kicks[0].outcome, KickOutcome::Intercepted(robot(BLUE, 3)));
+        assert!(report.kicks[0].is_chip());
+    }
+
+    #[test]
+    fn ball_leaving_the_field_is_a_goal_or_out() {
+        let mut side = referee(0.0, Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 1);
+        side.blue_team_on_positive_half = Some(true);
+        let mut messages = vec![message(0.0, MessageBody::Refbox2013(side))];
+        messages.extend(kick([6.0, 0.0, 0.0], &[]));
+        let goal = &analyze(&messages).kicks[0];
+        // Blue defends the goal at positive x, so yellow scored
+        assert_eq!(goal.outcome, KickOutcome::Goal { scored_by: Some(YELLOW) });
+        assert!(goal.outcome_pos.unwrap()[0] > 6.0);
+
+        // Without geometry, the field is the division A size
+        let out = &analyze(&kick([0.0, 5.0, 0.0], &[])).kicks[0];
+        assert_eq!(out.outcome, KickOutcome::Out);
+        // A ball that never leaves the field or reaches another robot has no outcome
+        assert_eq!(analyze(&kick([0.0, -1.0, 0.0], &[])).kicks[0].outcome, KickOutcome::Unknown);
+    }
+
+    #[test]
+    fn too_fast_kicks_are_matched_to_game_events() {
+        let messages = kick([8.0, 0.0, 0.0], &[]);
+        assert!(analyze(&messages).kicks[0].is_unpunished_too_fast());
+
+        let mut event = referee(1.0, Stage::NORMAL_FIRST_HALF, Command::STOP, 2);
+        event.game_events.push(GameEvent {
+            id: Some("event".to_string()),
+            event: Some(Event::BotKickedBallTooFast(BotKickedBallTooFast {
+                by_team: Some(Team::YELLOW.into()),
+                by_bot: Some(1),
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+        let mut punished = messages.clone();
+        punished.push(message(1.0, MessageBody::Refbox2013(event)));
+        let kick = &analyze(&punished).kicks[0];
+        assert!(kick.too_fast_event);
+        assert!(!kick.is_unpunished_too_fast());
+    }
+}
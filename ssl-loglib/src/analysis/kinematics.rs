@@ -0,0 +1,336 @@
+use crate::analysis::{Analyzer, Distribution, run};
+use crate::protos::refbox::ssl_gc_referee_message::referee::Stage;
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::TrackedRobot;
+use crate::tracker::{RobotKey, is_from_source, source_label};
+use crate::{LogMessage, MessageBody};
+use protobuf::Enum;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::io;
+use std::path::Path;
+
+/// Seconds between the samples used to compute accelerations, and speeds for robots without a tracked velocity
+const SAMPLE_INTERVAL: f64 = 0.1;
+/// Frames further apart than this many seconds are not used together, such as when a robot leaves the field
+const MAX_GAP: f64 = 0.5;
+
+/// Movement statistics of one robot during one referee stage
+#[derive(Clone, Debug)]
+pub struct KinematicsStats {
+    pub robot: RobotKey,
+    /// Referee stage, or `None` for frames before the first referee message
+    pub stage: Option<Stage>,
+    /// Seconds the robot was tracked
+    pub tracked_time: f64,
+    /// Distance travelled in meters
+    pub distance: f64,
+    /// Speed in meters per second
+    pub speed: Distribution,
+    /// Magnitude of the acceleration in meters per second squared
+    pub acceleration: Distribution,
+    /// Magnitude of the angular velocity in radians per second
+    pub angular_speed: Distribution,
+}
+
+#[derive(Default)]
+struct StatsSums {
+    tracked_time: f64,
+    distance: f64,
+    speed: Vec<f64>,
+    acceleration: Vec<f64>,
+    angular_speed: Vec<f64>,
+}
+
+#[derive(Clone, Copy)]
+struct Sample {
+    timestamp: f64,
+    pos: [f64; 2],
+    orientation: f64,
+    /// Tracked velocity, or the velocity since the previous sample for robots without one
+    vel: Option<[f64; 2]>,
+}
+
+#[derive(Default)]
+struct RobotState {
+    last_frame: Option<Sample>,
+    last_sample: Option<Sample>,
+}
+
+fn norm(v: [f64; 2]) -> f64 {
+    v[0].hypot(v[1])
+}
+
+/// Smallest difference between two angles, in radians
+fn angle_difference(a: f64, b: f64) -> f64 {
+    (a - b + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Computes robot movement statistics from one tracker source
+pub struct KinematicsAnalyzer {
+    source: Option<String>,
+    stage: Option<Stage>,
+    robots: HashMap<RobotKey, RobotState>,
+    sums: HashMap<(RobotKey, Option<Stage>), StatsSums>,
+}
+
+impl KinematicsAnalyzer {
+    /// Creates an analyzer for the tracker with the given source name or UUID
+    ///
+    /// If `source` is `None`, the first tracker source in the log is analyzed.
+    pub fn new(source: Option<String>) -> Self {
+        KinematicsAnalyzer {
+            source,
+            stage: None,
+            robots: HashMap::new(),
+            sums: HashMap::new(),
+        }
+    }
+
+    fn push_robot(&mut self, timestamp: f64, robot: &TrackedRobot) {
+        let key = RobotKey::from(&*robot.robot_id);
+        let state = self.robots.entry(key).or_default();
+        let sums = self.sums.entry((key, self.stage)).or_default();
+        let mut sample = Sample {
+            timestamp,
+            pos: [f64::from(robot.pos.x()), f64::from(robot.pos.y())],
+            orientation: f64::from(robot.orientation()),
+            vel: robot.vel.as_ref().map(|v| [f64::from(v.x()), f64::from(v.y())]),
+        };
+
+        if let Some(last) = state.last_frame
+            && timestamp > last.timestamp
+            && timestamp - last.timestamp <= MAX_GAP
+        {
+            let dt = timestamp - last.timestamp;
+            sums.tracked_time += dt;
+            if let Some(vel) = sample.vel {
+                sums.speed.push(norm(vel));
+                sums.distance += norm(vel) * dt;
+            }
+            if let Some(vel_angular) = robot.vel_angular {
+                sums.angular_speed.push(f64::from(vel_angular).abs());
+            }
+        }
+        state.last_frame = Some(sample);
+
+        let Some(previous) = state.last_sample else {
+            state.last_sample = Some(sample);
+            return;
+        };
+        let dt = timestamp - previous.timestamp;
+        if dt < SAMPLE_INTERVAL {
+            return;
+        }
+        if dt <= MAX_GAP {
+            // Without tracked velocities, differentiate positions over the sample interval to limit noise
+            if sample.vel.is_none() {
+                let displacement = [sample.pos[0] - previous.pos[0], sample.pos[1] - previous.pos[1]];
+                sample.vel = Some(displacement.map(|d| d / dt));
+                sums.speed.push(norm(displacement) / dt);
+                sums.distance += norm(displacement);
+            }
+            if robot.vel_angular.is_none() {
+                sums.angular_speed.push(angle_difference(sample.orientation, previous.orientation).abs() / dt);
+            }
+            if let (Some(vel), Some(previous_vel)) = (sample.vel, previous.vel) {
+                sums.acceleration.push(norm([vel[0] - previous_vel[0], vel[1] - previous_vel[1]]) / dt);
+            }
+        }
+        state.last_sample = Some(sample);
+    }
+}
+
+impl Analyzer for KinematicsAnalyzer {
+    type Output = Vec<KinematicsStats>;
+
+    fn push(&mut self, message: &LogMessage) {
+        match &message.body {
+            MessageBody::Refbox2013(referee) => self.stage = Some(referee.stage()),
+            MessageBody::VisionTracker2020(packet) => {
+                let source = self.source.get_or_insert_with(|| source_label(packet).to_string());
+                if !is_from_source(packet, source) {
+                    return;
+                }
+                let Some(frame) = packet.tracked_frame.as_ref() else {
+                    return;
+                };
+                for robot in &frame.robots {
+                    self.push_robot(frame.timestamp(), robot);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns statistics for each robot and stage, ordered by stage, team, and ID
+    fn finish(self) -> Vec<KinematicsStats> {
+        let mut stats: Vec<KinematicsStats> = self
+            .sums
+            .into_iter()
+            .map(|((robot, stage), sums)| KinematicsStats {
+                robot,
+                stage,
+                tracked_time: sums.tracked_time,
+                distance: sums.distance,
+                speed: Distribution::new(sums.speed),
+                acceleration: Distribution::new(sums.acceleration),
+                angular_speed: Distribution::new(sums.angular_speed),
+            })
+            .collect();
+        stats.sort_by_key(|s| (s.stage.map(|stage| stage.value()), s.robot));
+        stats
+    }
+}
+
+/// Reads the log at `path` and computes movement statistics for every robot
+///
+/// `source` is a tracker source name or UUID. If it is `None`, the first tracker source in the log is analyzed.
+pub fn analyze_kinematics(path: impl AsRef<Path>, source: Option<String>) -> io::Result<Vec<KinematicsStats>> {
+    run(path, KinematicsAnalyzer::new(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::Command;
+    use crate::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+    use crate::test_util::{message, referee, tracked, with_robot_motion};
+
+    const YELLOW: TeamColor = TeamColor::TEAM_COLOR_YELLOW;
+    const BLUE: TeamColor = TeamColor::TEAM_COLOR_BLUE;
+    /// Seconds between tracker frames, chosen so that every fourth frame is a sample without rounding
+    const FRAME: f64 = 1.0 / 32.0;
+
+    fn analyze(messages: &[LogMessage]) -> Vec<KinematicsStats> {
+        let mut analyzer = KinematicsAnalyzer::new(None);
+        for message in messages {
+            analyzer.push(message);
+        }
+        analyzer.finish()
+    }
+
+    /// A tracker frame at `time` seconds with `robots` at the given positions, received at the same time
+    fn frame(frame_number: u32, time: f64, robots: &[(TeamColor, u32, [f64; 2])]) -> LogMessage {
+        message(time, MessageBody::VisionTracker2020(tracked("T", frame_number, time, None, robots)))
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1.0e-4, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn angle_difference_wraps_around() {
+        assert!((angle_difference(1.0, 0.5) - 0.5).abs() < 1.0e-12);
+        assert!((angle_difference(-3.0, 3.0) - (2.0 * PI - 6.0)).abs() < 1.0e-12);
+        assert!((angle_difference(3.0, -3.0) + (2.0 * PI - 6.0)).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn positions_are_differentiated_without_tracked_velocities() {
+        // Yellow robot 1 accelerates at 2 m/s² along x for two seconds and turns at 1 rad/s through ±π
+        let messages: Vec<LogMessage> = (0..=64u32)
+            .map(|i| {
+                let time = f64::from(i) * FRAME;
+                let packet = tracked("T", i, time, None, &[(YELLOW, 1, [time * time, 0.0])]);
+                let orientation = angle_difference(3.0 + time, 0.0);
+                message(time, MessageBody::VisionTracker2020(with_robot_motion(packet, orientation, None, None)))
+            })
+            .collect();
+        let stats = analyze(&messages);
+        assert_eq!(stats.len(), 1);
+        let robot = &stats[0];
+        assert_eq!((robot.robot, robot.stage), (RobotKey { team: YELLOW, id: 1 }, None));
+        assert_close(Some(robot.tracked_time), 2.0);
+        assert_close(Some(robot.distance), 4.0);
+        // Speeds are averaged over the 16 sample intervals of 0.125 s, from 0.125 to 3.875 m/s
+        assert_eq!(robot.speed.len(), 16);
+        assert_close(robot.speed.percentile(50.0), 2.0);
+        assert_close(robot.speed.max(), 3.875);
+        assert_eq!(robot.acceleration.len(), 15);
+        assert_close(robot.acceleration.min(), 2.0);
+        assert_close(robot.acceleration.max(), 2.0);
+        assert_close(robot.angular_speed.min(), 1.0);
+        assert_close(robot.angular_speed.max(), 1.0);
+    }
+
+    #[test]
+    fn tracked_velocities_are_used_when_available() {
+        // The tracked velocity grows by 2 m/s² while the tracked position stays put
+        let messages: Vec<LogMessage> = (0..=64u32)
+            .map(|i| {
+                let time = f64::from(i) * FRAME;
+                let packet = tracked("T", i, time, None, &[(BLUE, 4, [1.0, 1.0])]);
+                let packet = with_robot_motion(packet, 0.0, Some([0.0, 2.0 * time]), Some(-1.5));
+                message(time, MessageBody::VisionTracker2020(packet))
+            })
+            .collect();
+        let robot = &analyze(&messages)[0];
+        assert_close(Some(robot.tracked_time), 2.0);
+        // Speeds at the end of every frame interval, from 1/16 to 4 m/s, integrated over the frame intervals
+        assert_eq!(robot.speed.len(), 64);
+        assert_close(Some(robot.distance), 4.0625);
+        assert_close(robot.speed.percentile(50.0), 2.03125);
+        assert_close(robot.speed.max(), 4.0);
+        assert_eq!(robot.acceleration.len(), 16);
+        assert_close(robot.acceleration.percentile(50.0), 2.0);
+        assert_eq!(robot.angular_speed.len(), 64);
+        assert_close(robot.angular_speed.max(), 1.5);
+    }
+
+    #[test]
+    fn gaps_are_not_counted_as_movement() {
+        // The robot stands still for one second, is missing for one second, and reappears 3 m away
+        let messages: Vec<LogMessage> = (0..=96u32)
+            .filter(|i| !(33..64).contains(i))
+            .map(|i| {
+                let time = f64::from(i) * FRAME;
+                let pos = if i < 64 { [0.0, 0.0] } else { [3.0, 0.0] };
+                frame(i, time, &[(YELLOW, 1, pos)])
+            })
+            .collect();
+        let robot = &analyze(&messages)[0];
+        assert_close(Some(robot.tracked_time), 2.0);
+        assert_close(Some(robot.distance), 0.0);
+        assert_close(robot.speed.max(), 0.0);
+        assert_close(robot.acceleration.max(), 0.0);
+    }
+
+    #[test]
+    fn statistics_are_split_by_stage() {
+        // Two robots move at 1 m/s, with a referee message at 0.5 s and another at 1.5 s
+        let mut messages = Vec::new();
+        for i in 0..=64u32 {
+            let time = f64::from(i) * FRAME;
+            let stage = match i {
+                16 => Some(Stage::NORMAL_FIRST_HALF),
+                48 => Some(Stage::NORMAL_HALF_TIME),
+                _ => None,
+            };
+            if let Some(stage) = stage {
+                messages.push(message(time, MessageBody::Refbox2013(referee(time, stage, Command::STOP, 1))));
+            }
+            messages.push(frame(i, time, &[(BLUE, 1, [time, 0.0]), (YELLOW, 2, [0.0, time])]));
+        }
+        let stats = analyze(&messages);
+        let keys: Vec<_> = stats.iter().map(|s| (s.stage, s.robot.to_string())).collect();
+        assert_eq!(
+            keys,
+            [
+                (None, "Y2".to_string()),
+                (None, "B1".to_string()),
+                (Some(Stage::NORMAL_FIRST_HALF), "Y2".to_string()),
+                (Some(Stage::NORMAL_FIRST_HALF), "B1".to_string()),
+                (Some(Stage::NORMAL_HALF_TIME), "Y2".to_string()),
+                (Some(Stage::NORMAL_HALF_TIME), "B1".to_string()),
+            ]
+        );
+        // The interval between two frames belongs to the stage of the later frame
+        for (robot, expected) in stats.iter().zip([15.0, 15.0, 32.0, 32.0, 17.0, 17.0]) {
+            assert_close(Some(robot.tracked_time), expected * FRAME);
+            assert_close(robot.speed.percentile(50.0), 1.0);
+        }
+        let total: f64 = stats.iter().filter(|s| s.robot.team == BLUE).map(|s| s.distance).sum();
+        assert_close(Some(total), 2.0);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::analysis::{Analyzer, Distribution, PerCamera, run};
+use crate::LogMessage;
+use std::io;
+use std::path::Path;
+
+/// Timing of the vision pipeline for one camera. All values are in seconds.
+#[derive(Clone, Debug)]
+pub struct CameraLatency {
+    pub camera_id: u32,
+    /// Number of detection frames received from the camera
+    pub frames: usize,
+    /// `t_sent - t_capture`, the processing time on the vision PC
+    pub capture_to_send: Distribution,
+    /// Receive timestamp minus `t_sent`
+    ///
+    /// Both clocks are compared directly, so this is the network delay plus the offset between the vision PC clock
+    /// and the recorder clock. Shift by `-clock_offset` to get the delay relative to the fastest frame.
+    pub send_to_receive: Distribution,
+    /// Receive timestamp minus `t_capture`, the total delay from capture to recording (includes the clock offset)
+    pub capture_to_receive: Distribution,
+    /// `t_capture - t_capture_camera` for frames that report a camera timestamp
+    ///
+    /// Camera timestamps use the camera's own clock, so only the spread of this distribution is meaningful.
+    pub camera_to_capture: Distribution,
+    /// Estimated recorder clock minus vision PC clock
+    ///
+    /// This is the smallest send to receive difference, so it assumes the fastest frame had no network delay.
+    /// A large value means the clocks are not synchronized.
+    pub clock_offset: Option<f64>,
+}
+
+#[derive(Default)]
+struct CameraSamples {
+    capture_to_send: Vec<f64>,
+    send_to_receive: Vec<f64>,
+    capture_to_receive: Vec<f64>,
+    camera_to_capture: Vec<f64>,
+}
+
+/// Collects vision timing samples from log messages
+///
+/// Unlike the other analyses, every sample is kept rather than counted in a [`Histogram`](super::Histogram). Send to
+/// receive times include the clock offset and camera timestamps have an arbitrary epoch, so the range of the samples
+/// is not known in advance, and the report shifts them and bins them with a width chosen by the user. This takes
+/// 32 bytes per detection frame, about 70 MB for an hour of eight cameras at 75 Hz.
+#[derive(Default)]
+pub struct LatencyAnalyzer {
+    cameras: PerCamera<CameraSamples>,
+}
+
+impl LatencyAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Analyzer for LatencyAnalyzer {
+    type Output = Vec<CameraLatency>;
+
+    /// Adds the detection frame in `message`, if it has one
+    fn push(&mut self, message: &LogMessage) {
+        let Some((samples, detection)) = self.cameras.get(message) else {
+            return;
+        };
+        let receive_time = message.timestamp.timestamp_nanos_opt().unwrap_or_default() as f64 / 1.0e9;
+        samples.capture_to_send.push(detection.t_sent() - detection.t_capture());
+        samples.send_to_receive.push(receive_time - detection.t_sent());
+        samples.capture_to_receive.push(receive_time - detection.t_capture());
+        if let Some(t_capture_camera) = detection.t_capture_camera {
+            samples.camera_to_capture.push(detection.t_capture() - t_capture_camera);
+        }
+    }
+
+    /// Returns the timing of each camera, ordered by camera ID
+    fn finish(self) -> Vec<CameraLatency> {
+        self.cameras
+            .into_cameras()
+            .into_iter()
+            .map(|(camera_id, samples)| {
+                let send_to_receive = Distribution::new(samples.send_to_receive);
+                CameraLatency {
+                    camera_id,
+                    frames: samples.capture_to_send.len(),
+                    capture_to_send: Distribution::new(samples.capture_to_send),
+                    clock_offset: send_to_receive.min(),
+                    send_to_receive,
+                    capture_to_receive: Distribution::new(samples.capture_to_receive),
+                    camera_to_capture: Distribution::new(samples.camera_to_capture),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Reads the log at `path` and returns the vision timing of each camera
+pub fn analyze_latency(path: impl AsRef<Path>) -> io::Result<Vec<CameraLatency>> {
+    run(path, LatencyAnalyzer::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBody;
+    use crate::protos::vision::messages_robocup_ssl_wrapper_legacy::SSL_WrapperPacket as SSL_WrapperPacket_Legacy;
+    use crate::test_util::{START, detection, message};
+    use protobuf::Message;
+
+    /// A detection frame received `receive` seconds after [`START`], captured `delay` seconds earlier on a vision
+    /// clock that is `clock_offset` seconds behind the receive clock
+    ///
+    /// Frames are sent 5 ms after they are captured.
+    fn frame(camera_id: u32, frame_number: u32, receive: f64, delay: f64, clock_offset: f64) -> LogMessage {
+        let t_capture = START as f64 / 1.0e9 + receive - delay - clock_offset;
+        message(receive, MessageBody::Vision2014(detection(camera_id, frame_number, t_capture, &[], &[])))
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1.0e-6, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn latency_is_split_into_pipeline_stages() {
+        let mut analyzer = LatencyAnalyzer::new();
+        for i in 0..10u32 {
+            let receive = f64::from(i) * 0.02;
+            // Camera 0 frames take 1 ms longer on the network each time
+            analyzer.push(&frame(0, i, receive, 0.005 + f64::from(i) * 0.001, 0.2));
+            let mut camera_frame = frame(1, i, receive + 0.01, 0.006, -0.1);
+            if let MessageBody::Vision2014(packet) = &mut camera_frame.body {
+                let detection = packet.detection.as_mut().unwrap();
+                detection.t_capture_camera = Some(detection.t_capture() - 50.0);
+                // Legacy copies of the frames are ignored once Vision2014 frames are available
+                let legacy = SSL_WrapperPacket_Legacy::parse_from_bytes(&packet.write_to_bytes().unwrap()).unwrap();
+                analyzer.push(&message(receive, MessageBody::Vision2010(legacy)));
+            }
+            analyzer.push(&camera_frame);
+        }
+        let cameras = analyzer.finish();
+        assert_eq!(cameras.len(), 2);
+
+        let camera = &cameras[0];
+        assert_eq!((camera.camera_id, camera.frames), (0, 10));
+        assert_close(camera.clock_offset, 0.2);
+        assert_close(camera.capture_to_send.percentile(50.0), 0.005);
+        assert_close(camera.send_to_receive.percentile(0.0), 0.2);
+        assert_close(camera.send_to_receive.percentile(50.0), 0.2045);
+        assert_close(camera.send_to_receive.percentile(100.0), 0.209);
+        assert_close(camera.capture_to_receive.percentile(50.0), 0.2095);
+        assert!(camera.camera_to_capture.is_empty());
+
+        let camera = &cameras[1];
+        assert_eq!((camera.camera_id, camera.frames), (1, 10));
+        assert_close(camera.clock_offset, -0.099);
+        assert_close(camera.capture_to_receive.percentile(90.0), -0.094);
+        assert_close(camera.camera_to_capture.percentile(50.0), 50.0);
+    }
+}
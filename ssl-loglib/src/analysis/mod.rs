@@ -0,0 +1,310 @@
+//! Analyses computed from the messages in a log
+
+pub mod cameras;
+pub mod clock;
+pub mod identity;
+pub mod kicks;
+pub mod kinematics;
+pub mod latency;
+pub mod passes;
+pub mod trackers;
+pub mod tracking_quality;
+
+use crate::log_file_reader::LogFileReader;
+use crate::protos::vision::messages_robocup_ssl_detection::SSL_DetectionFrame;
+use crate::{LogMessage, MessageBody};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// An analysis that is computed one message at a time
+pub trait Analyzer {
+    type Output;
+
+    /// Adds a message to the analysis. Messages the analysis does not use are ignored.
+    fn push(&mut self, message: &LogMessage);
+
+    /// Returns the result once all messages have been pushed
+    fn finish(self) -> Self::Output;
+}
+
+/// Reads every message of the log at `path` into `analyzer` and returns its result
+pub fn run<A: Analyzer>(path: impl AsRef<Path>, mut analyzer: A) -> io::Result<A::Output> {
+    let mut reader = LogFileReader::new(path)?;
+    loop {
+        match reader.get_next_message() {
+            Ok(message) => analyzer.push(&message),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(analyzer.finish())
+}
+
+/// A set of samples with summary statistics
+#[derive(Clone, Debug, Default)]
+pub struct Distribution {
+    /// Samples in ascending order
+    samples: Vec<f64>,
+}
+
+impl Distribution {
+    /// Creates a distribution from samples in any order. NaN samples are dropped.
+    pub fn new(mut samples: Vec<f64>) -> Self {
+        samples.retain(|s| !s.is_nan());
+        samples.sort_by(f64::total_cmp);
+        Distribution { samples }
+    }
+
+    pub fn samples(&self) -> &[f64] {
+        &self.samples
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.samples.first().copied()
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.samples.last().copied()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+
+    /// Returns the value below which `percent` percent of the samples fall, interpolating between samples
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let position = (percent.clamp(0.0, 100.0) / 100.0) * (self.samples.len() - 1) as f64;
+        let lower = position.floor() as usize;
+        let upper = position.ceil() as usize;
+        let fraction = position - lower as f64;
+        Some(self.samples[lower] + (self.samples[upper] - self.samples[lower]) * fraction)
+    }
+
+    /// Returns a copy of the distribution with `offset` added to every sample
+    pub fn shifted(&self, offset: f64) -> Self {
+        Distribution {
+            samples: self.samples.iter().map(|s| s + offset).collect(),
+        }
+    }
+
+    /// Counts samples in bins of `bin_width`, returned as (bin start, count) from the lowest to the highest sample
+    pub fn histogram(&self, bin_width: f64) -> Vec<(f64, usize)> {
+        let (Some(min), Some(max)) = (self.min(), self.max()) else {
+            return Vec::new();
+        };
+        if bin_width <= 0.0 {
+            return vec![(min, self.samples.len())];
+        }
+        // Nudge values up slightly so samples on a bin edge are not pushed into the lower bin by rounding
+        let bin_of = |sample: f64| (sample / bin_width + 1.0e-9).floor();
+        let first_bin = bin_of(min);
+        let bin_count = (bin_of(max) - first_bin) as usize + 1;
+        let mut bins = vec![0; bin_count];
+        for sample in &self.samples {
+            let bin = (bin_of(*sample) - first_bin) as usize;
+            bins[bin.min(bin_count - 1)] += 1;
+        }
+        bins.into_iter()
+            .enumerate()
+            .map(|(i, count)| ((first_bin + i as f64) * bin_width, count))
+            .collect()
+    }
+}
+
+/// Counts samples in fixed bins, so percentiles can be computed without keeping every sample
+///
+/// Samples outside the range of the bins are counted in the outermost bins. Percentiles are interpolated within a
+/// bin and are clamped to the smallest and largest sample, which are kept exactly like the mean.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    start: f64,
+    bin_width: f64,
+    counts: Vec<u64>,
+    total: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram with bins of `bin_width` from `start` to `end`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bin_width` is not positive or the range is not finite.
+    pub fn new(start: f64, end: f64, bin_width: f64) -> Self {
+        assert!(bin_width > 0.0, "Histogram bin width must be positive, got {}", bin_width);
+        assert!((end - start).is_finite(), "Histogram range must be finite, got {} to {}", start, end);
+        let bin_count = ((end - start) / bin_width).ceil().max(1.0) as usize;
+        Histogram {
+            start,
+            bin_width,
+            counts: vec![0; bin_count],
+            total: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Adds a sample. NaN samples are dropped.
+    pub fn add(&mut self, sample: f64) {
+        if sample.is_nan() {
+            return;
+        }
+        let bin = ((sample - self.start) / self.bin_width).floor().max(0.0) as usize;
+        let last_bin = self.counts.len() - 1;
+        self.counts[bin.min(last_bin)] += 1;
+        self.total += 1;
+        self.sum += sample;
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.total as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (self.total > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.total > 0).then_some(self.max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.sum / self.total as f64)
+    }
+
+    /// Returns the value below which `percent` percent of the samples fall, interpolating within the bin
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+        let rank = percent.clamp(0.0, 100.0) / 100.0 * self.total as f64;
+        let mut below = 0;
+        for (bin, &count) in self.counts.iter().enumerate() {
+            if count > 0 && (below + count) as f64 >= rank {
+                let fraction = (rank - below as f64) / count as f64;
+                let value = self.start + (bin as f64 + fraction) * self.bin_width;
+                return Some(value.clamp(self.min, self.max));
+            }
+            below += count;
+        }
+        None
+    }
+}
+
+/// Per-camera state for analyses of vision detection frames
+///
+/// Logs usually contain every frame twice, as Vision2014 and as legacy Vision2010 messages. State is kept separately
+/// for each format and the legacy state is only used if the log has no Vision2014 frames.
+#[derive(Default)]
+struct PerCamera<T> {
+    current: BTreeMap<u32, T>,
+    legacy: BTreeMap<u32, T>,
+}
+
+impl<T: Default> PerCamera<T> {
+    /// Returns the detection frame in `message`, if it has one, and the state of the camera that sent it
+    fn get<'a>(&mut self, message: &'a LogMessage) -> Option<(&mut T, &'a SSL_DetectionFrame)> {
+        let (cameras, detection) = match &message.body {
+            MessageBody::Vision2014(packet) => (&mut self.current, packet.detection.as_ref()?),
+            MessageBody::Vision2010(packet) => (&mut self.legacy, packet.detection.as_ref()?),
+            _ => return None,
+        };
+        Some((cameras.entry(detection.camera_id()).or_default(), detection))
+    }
+
+    /// Returns the state of each camera, ordered by camera ID
+    fn into_cameras(self) -> BTreeMap<u32, T> {
+        if self.current.is_empty() { self.legacy } else { self.current }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution_is_sorted_without_nan() {
+        let distribution = Distribution::new(vec![3.0, f64::NAN, 1.0, 2.0]);
+        assert_eq!(distribution.samples(), [1.0, 2.0, 3.0]);
+        assert_eq!(distribution.min(), Some(1.0));
+        assert_eq!(distribution.max(), Some(3.0));
+        assert_eq!(distribution.mean(), Some(2.0));
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_samples() {
+        let distribution = Distribution::new(vec![40.0, 10.0, 30.0, 20.0, 50.0]);
+        assert_eq!(distribution.percentile(0.0), Some(10.0));
+        assert_eq!(distribution.percentile(50.0), Some(30.0));
+        assert_eq!(distribution.percentile(100.0), Some(50.0));
+        assert_eq!(distribution.percentile(62.5), Some(35.0));
+        // Percentiles outside 0 to 100 are clamped
+        assert_eq!(distribution.percentile(150.0), Some(50.0));
+        assert_eq!(Distribution::new(vec![7.0]).percentile(90.0), Some(7.0));
+        assert_eq!(Distribution::default().percentile(50.0), None);
+    }
+
+    #[test]
+    fn histogram_counts_samples_per_bin() {
+        let distribution = Distribution::new(vec![0.0, 0.5, 1.0, 1.2, 3.9]);
+        assert_eq!(distribution.histogram(1.0), [(0.0, 2), (1.0, 2), (2.0, 0), (3.0, 1)]);
+        assert_eq!(distribution.shifted(1.0).min(), Some(1.0));
+        assert!(Distribution::default().histogram(1.0).is_empty());
+    }
+
+    #[test]
+    fn histogram_percentiles_are_accurate_to_one_bin() {
+        let mut histogram = Histogram::new(0.0, 100.0, 1.0);
+        assert_eq!(histogram.percentile(50.0), None);
+        for i in 0..1000 {
+            histogram.add(f64::from(i) / 10.0);
+        }
+        histogram.add(f64::NAN);
+        assert_eq!(histogram.len(), 1000);
+        assert!((histogram.percentile(50.0).unwrap() - 50.0).abs() <= 1.0);
+        assert!((histogram.mean().unwrap() - 49.95).abs() < 1.0e-9);
+        // Samples outside the bins are counted in the outermost bins, but the extremes are exact
+        histogram.add(-5.0);
+        histogram.add(250.0);
+        assert_eq!(histogram.len(), 1002);
+        assert_eq!((histogram.min(), histogram.max()), (Some(-5.0), Some(250.0)));
+        assert_eq!(histogram.percentile(0.0), Some(0.0));
+        assert_eq!(histogram.percentile(100.0), Some(100.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "bin width must be positive")]
+    fn histogram_rejects_nan_bin_width() {
+        Histogram::new(0.0, 1.0, f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "bin width must be positive")]
+    fn histogram_rejects_zero_bin_width() {
+        Histogram::new(0.0, 1.0, 0.0);
+    }
+}
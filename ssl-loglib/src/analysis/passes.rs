@@ -0,0 +1,331 @@
+use crate::analysis::kicks::{Kick, KickOutcome, KickReport, analyze_kicks};
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+use crate::tracker::RobotKey;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// What happened to a pass
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassResult {
+    /// A teammate of the passer got the ball
+    Completed(RobotKey),
+    /// A robot of the other team got the ball
+    Intercepted(RobotKey),
+}
+
+/// A kick by one robot that reached another robot
+#[derive(Clone, Debug)]
+pub struct Pass {
+    /// Receive time of the first tracker frame that reported the kick
+    pub timestamp: DateTime<Utc>,
+    pub passer: RobotKey,
+    pub result: PassResult,
+    /// Ball position at the kick in meters
+    pub start_pos: [f64; 2],
+    /// Ball position in meters when the receiving robot touched it
+    pub end_pos: Option<[f64; 2]>,
+    /// Initial ball speed in meters per second
+    pub speed: f64,
+}
+
+impl Pass {
+    /// Turns a kick into a pass if it went from one robot to another
+    ///
+    /// Kicks that left the field or entered a goal may have been shots, so they are not passes. Kicks received by the
+    /// kicker are dribbles.
+    pub fn from_kick(kick: &Kick) -> Option<Pass> {
+        let passer = kick.kicker?;
+        let result = match kick.outcome {
+            KickOutcome::Received(robot) if robot != passer => PassResult::Completed(robot),
+            KickOutcome::Intercepted(robot) => PassResult::Intercepted(robot),
+            _ => return None,
+        };
+        Some(Pass {
+            timestamp: kick.timestamp,
+            passer,
+            result,
+            start_pos: kick.pos,
+            end_pos: kick.outcome_pos,
+            speed: kick.speed(),
+        })
+    }
+
+    /// The robot that got the ball, on either team
+    pub fn receiver(&self) -> RobotKey {
+        match self.result {
+            PassResult::Completed(robot) | PassResult::Intercepted(robot) => robot,
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        matches!(self.result, PassResult::Completed(_))
+    }
+}
+
+/// Pass statistics of one robot
+#[derive(Clone, Debug)]
+pub struct PassNode {
+    pub robot: RobotKey,
+    /// Passes kicked by the robot
+    pub passes: usize,
+    /// Passes kicked by the robot that reached a teammate
+    pub completed: usize,
+    /// Passes from teammates received by the robot
+    pub received: usize,
+    /// Passes of the other team intercepted by the robot
+    pub intercepts: usize,
+    /// Average position in meters where the robot kicked, received or intercepted a pass
+    pub pos: [f64; 2],
+}
+
+impl PassNode {
+    fn new(robot: RobotKey) -> Self {
+        PassNode {
+            robot,
+            passes: 0,
+            completed: 0,
+            received: 0,
+            intercepts: 0,
+            pos: [0.0; 2],
+        }
+    }
+
+    /// Fraction of the robot's passes that reached a teammate
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.passes > 0).then(|| self.completed as f64 / self.passes as f64)
+    }
+}
+
+/// Completed passes from one robot to a teammate
+#[derive(Clone, Debug)]
+pub struct PassEdge {
+    pub from: RobotKey,
+    pub to: RobotKey,
+    pub count: usize,
+}
+
+/// Who passes to whom in one team
+#[derive(Clone, Debug)]
+pub struct PassNetwork {
+    pub team: TeamColor,
+    /// Robots that kicked, received or intercepted a pass, ordered by ID
+    pub nodes: Vec<PassNode>,
+    /// Completed passes between robots, ordered by passer and receiver
+    pub edges: Vec<PassEdge>,
+    /// Passes kicked by the team
+    pub passes: usize,
+    /// Passes that reached a teammate
+    pub completed: usize,
+    /// Passes intercepted by the other team
+    pub intercepted: usize,
+    /// Passes of the other team intercepted by this team
+    pub intercepts: usize,
+}
+
+impl PassNetwork {
+    /// Fraction of the team's passes that reached a teammate
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.passes > 0).then(|| self.completed as f64 / self.passes as f64)
+    }
+}
+
+/// Passes reported by one tracker source in a log
+#[derive(Clone, Debug, Default)]
+pub struct PassReport {
+    /// Name or UUID of the tracker source that was analyzed, if the log has tracker messages with kicks
+    pub source: Option<String>,
+    /// Passes in the order they happened
+    pub passes: Vec<Pass>,
+    /// Field length and width in meters
+    pub field_size: [f64; 2],
+}
+
+impl From<&KickReport> for PassReport {
+    fn from(kicks: &KickReport) -> Self {
+        PassReport {
+            source: kicks.source.clone(),
+            passes: kicks.kicks.iter().filter_map(Pass::from_kick).collect(),
+            field_size: kicks.field_size,
+        }
+    }
+}
+
+/// Sums the positions of a node, which are averaged once all passes are counted
+#[derive(Default)]
+struct PositionSum {
+    sum: [f64; 2],
+    count: usize,
+}
+
+impl PositionSum {
+    fn add(&mut self, pos: Option<[f64; 2]>) {
+        if let Some(pos) = pos {
+            self.sum[0] += pos[0];
+            self.sum[1] += pos[1];
+            self.count += 1;
+        }
+    }
+
+    fn mean(&self) -> [f64; 2] {
+        if self.count == 0 {
+            return [0.0; 2];
+        }
+        self.sum.map(|sum| sum / self.count as f64)
+    }
+}
+
+fn node_entry(
+    nodes: &mut BTreeMap<RobotKey, (PassNode, PositionSum)>,
+    robot: RobotKey,
+) -> &mut (PassNode, PositionSum) {
+    nodes.entry(robot).or_insert_with(|| (PassNode::new(robot), PositionSum::default()))
+}
+
+impl PassReport {
+    /// Builds the pass network of `team`
+    pub fn network(&self, team: TeamColor) -> PassNetwork {
+        let mut nodes: BTreeMap<RobotKey, (PassNode, PositionSum)> = BTreeMap::new();
+        let mut edges: BTreeMap<(RobotKey, RobotKey), usize> = BTreeMap::new();
+        let mut network = PassNetwork {
+            team,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            passes: 0,
+            completed: 0,
+            intercepted: 0,
+            intercepts: 0,
+        };
+        for pass in &self.passes {
+            let receiver = pass.receiver();
+            if pass.passer.team == team {
+                network.passes += 1;
+                let (passer, positions) = node_entry(&mut nodes, pass.passer);
+                passer.passes += 1;
+                positions.add(Some(pass.start_pos));
+                if pass.is_completed() {
+                    passer.completed += 1;
+                    network.completed += 1;
+                    let (receiver_node, positions) = node_entry(&mut nodes, receiver);
+                    receiver_node.received += 1;
+                    positions.add(pass.end_pos);
+                    *edges.entry((pass.passer, receiver)).or_default() += 1;
+                } else {
+                    network.intercepted += 1;
+                }
+            } else if receiver.team == team && !pass.is_completed() {
+                network.intercepts += 1;
+                let (interceptor, positions) = node_entry(&mut nodes, receiver);
+                interceptor.intercepts += 1;
+                positions.add(pass.end_pos);
+            }
+        }
+        network.nodes = nodes
+            .into_values()
+            .map(|(node, positions)| PassNode {
+                pos: positions.mean(),
+                ..node
+            })
+            .collect();
+        network.edges = edges.into_iter().map(|((from, to), count)| PassEdge { from, to, count }).collect();
+        network
+    }
+}
+
+/// Reads the log at `path` and returns the passes reported by a tracker source
+///
+/// Passes are kicks from [`analyze_kicks`] that reached another robot. `source` is a tracker source name or UUID. If
+/// it is `None`, the first tracker source in the log that reports a kick is analyzed.
+pub fn analyze_passes(path: impl AsRef<Path>, source: Option<String>) -> io::Result<PassReport> {
+    Ok(PassReport::from(&analyze_kicks(path, source)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::START;
+    use chrono::TimeZone;
+
+    const YELLOW: TeamColor = TeamColor::TEAM_COLOR_YELLOW;
+    const BLUE: TeamColor = TeamColor::TEAM_COLOR_BLUE;
+
+    fn robot(team: TeamColor, id: u32) -> RobotKey {
+        RobotKey { team, id }
+    }
+
+    fn kick(kicker: Option<RobotKey>, pos: [f64; 2], outcome: KickOutcome, outcome_pos: [f64; 2]) -> Kick {
+        Kick {
+            timestamp: Utc.timestamp_nanos(START),
+            start_timestamp: 0.0,
+            kicker,
+            pos,
+            vel: [3.0, 4.0, 0.0],
+            stop_pos: None,
+            outcome,
+            outcome_pos: Some(outcome_pos),
+            too_fast_event: false,
+        }
+    }
+
+    #[test]
+    fn only_kicks_between_two_robots_are_passes() {
+        let y1 = Some(robot(YELLOW, 1));
+        let completed = kick(y1, [0.0, 0.0], KickOutcome::Received(robot(YELLOW, 2)), [1.0, 0.0]);
+        let pass = Pass::from_kick(&completed).unwrap();
+        assert_eq!(pass.result, PassResult::Completed(robot(YELLOW, 2)));
+        assert_eq!((pass.start_pos, pass.end_pos, pass.speed), ([0.0, 0.0], Some([1.0, 0.0]), 5.0));
+        let intercepted = kick(y1, [0.0, 0.0], KickOutcome::Intercepted(robot(BLUE, 3)), [1.0, 0.0]);
+        let pass = Pass::from_kick(&intercepted).unwrap();
+        assert_eq!(pass.receiver(), robot(BLUE, 3));
+        assert!(!pass.is_completed());
+
+        // Dribbles, shots, and kicks by an unknown robot are not passes
+        let not_passes = [
+            kick(y1, [0.0, 0.0], KickOutcome::Received(robot(YELLOW, 1)), [0.5, 0.0]),
+            kick(y1, [0.0, 0.0], KickOutcome::Goal { scored_by: Some(YELLOW) }, [6.1, 0.0]),
+            kick(y1, [0.0, 0.0], KickOutcome::Out, [0.0, 4.6]),
+            kick(y1, [0.0, 0.0], KickOutcome::Unknown, [0.0, 0.0]),
+            kick(None, [0.0, 0.0], KickOutcome::Touched(robot(YELLOW, 2)), [1.0, 0.0]),
+        ];
+        for kick in &not_passes {
+            assert!(Pass::from_kick(kick).is_none(), "{:?}", kick.outcome);
+        }
+    }
+
+    #[test]
+    fn networks_count_passes_and_intercepts_per_team() {
+        let (y1, y2, b1, b2) = (robot(YELLOW, 1), robot(YELLOW, 2), robot(BLUE, 1), robot(BLUE, 2));
+        let kicks = KickReport {
+            source: Some("T".to_string()),
+            kicks: vec![
+                kick(Some(y1), [0.0, 0.0], KickOutcome::Received(y2), [2.0, 0.0]),
+                kick(Some(y1), [1.0, 0.0], KickOutcome::Received(y2), [3.0, 0.0]),
+                kick(Some(y2), [4.0, 0.0], KickOutcome::Intercepted(b1), [5.0, 0.0]),
+                kick(Some(b1), [5.0, 0.0], KickOutcome::Received(b2), [5.0, 2.0]),
+                kick(Some(b2), [5.0, 2.0], KickOutcome::Out, [5.0, 4.6]),
+            ],
+            field_size: [12.0, 9.0],
+        };
+        let report = PassReport::from(&kicks);
+        assert_eq!(report.passes.len(), 4);
+
+        let yellow = report.network(YELLOW);
+        assert_eq!((yellow.passes, yellow.completed, yellow.intercepted, yellow.intercepts), (3, 2, 1, 0));
+        assert_eq!(yellow.success_rate(), Some(2.0 / 3.0));
+        let nodes: Vec<_> = yellow.nodes.iter().map(|n| (n.robot, n.passes, n.completed, n.received)).collect();
+        assert_eq!(nodes, [(y1, 2, 2, 0), (y2, 1, 0, 2)]);
+        // Nodes are placed at the average position where the robot kicked or received
+        assert_eq!(yellow.nodes[0].pos, [0.5, 0.0]);
+        assert_eq!(yellow.nodes[1].pos, [3.0, 0.0]);
+        assert_eq!(yellow.edges.len(), 1);
+        assert_eq!((yellow.edges[0].from, yellow.edges[0].to, yellow.edges[0].count), (y1, y2, 2));
+
+        let blue = report.network(BLUE);
+        assert_eq!((blue.passes, blue.completed, blue.intercepted, blue.intercepts), (1, 1, 0, 1));
+        let b1_node = &blue.nodes[0];
+        assert_eq!((b1_node.robot, b1_node.intercepts, b1_node.passes), (b1, 1, 1));
+        assert_eq!(b1_node.pos, [5.0, 0.0]);
+        assert_eq!(report.network(TeamColor::TEAM_COLOR_UNKNOWN).nodes.len(), 0);
+    }
+}
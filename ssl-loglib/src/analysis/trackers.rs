@@ -0,0 +1,377 @@
+use crate::analysis::{Analyzer, run};
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::TrackedFrame;
+use crate::tracker::{RobotKey, is_from_source};
+use crate::{LogMessage, MessageBody};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// Longest time in seconds between two frames of the second source that a frame of the first source is interpolated
+/// between
+const MAX_INTERPOLATION_GAP: f64 = 0.1;
+
+/// Longest time in seconds that frames of two sources with the same tracker time are expected to be apart in the log
+///
+/// Frames of the second source are kept this long to be matched to late frames of the first source, and frames of the
+/// first source wait this long for the second source to catch up.
+pub(super) const MATCH_WINDOW: f64 = 1.0;
+
+/// A robot or the ball
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TrackedObject {
+    Ball,
+    Robot(RobotKey),
+}
+
+impl fmt::Display for TrackedObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackedObject::Ball => write!(f, "Ball"),
+            TrackedObject::Robot(robot) => robot.fmt(f),
+        }
+    }
+}
+
+/// Position and velocity of an object in one tracker frame, in meters and meters per second
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ObjectState {
+    pub(super) pos: [f64; 3],
+    pub(super) vel: Option<[f64; 3]>,
+}
+
+impl ObjectState {
+    fn interpolate(&self, other: &ObjectState, fraction: f64) -> ObjectState {
+        let lerp = |a: [f64; 3], b: [f64; 3]| std::array::from_fn(|i| a[i] + (b[i] - a[i]) * fraction);
+        ObjectState {
+            pos: lerp(self.pos, other.pos),
+            vel: self.vel.zip(other.vel).map(|(a, b)| lerp(a, b)),
+        }
+    }
+}
+
+pub(super) fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt()
+}
+
+pub(super) struct FrameState {
+    pub(super) timestamp: f64,
+    pub(super) objects: BTreeMap<TrackedObject, ObjectState>,
+}
+
+impl From<&TrackedFrame> for FrameState {
+    fn from(frame: &TrackedFrame) -> Self {
+        let mut objects = BTreeMap::new();
+        // Trackers that report several balls list the most likely one first
+        if let Some(ball) = frame.balls.first() {
+            let state = ObjectState {
+                pos: [ball.pos.x(), ball.pos.y(), ball.pos.z()].map(f64::from),
+                vel: ball.vel.as_ref().map(|v| [v.x(), v.y(), v.z()].map(f64::from)),
+            };
+            objects.insert(TrackedObject::Ball, state);
+        }
+        for robot in &frame.robots {
+            let state = ObjectState {
+                pos: [robot.pos.x(), robot.pos.y(), 0.0].map(f64::from),
+                vel: robot.vel.as_ref().map(|v| [v.x(), v.y(), 0.0].map(f64::from)),
+            };
+            objects.insert(TrackedObject::Robot(RobotKey::from(&*robot.robot_id)), state);
+        }
+        FrameState {
+            timestamp: frame.timestamp(),
+            objects,
+        }
+    }
+}
+
+/// Differences between two trackers for one object
+#[derive(Clone, Debug, Default)]
+pub struct ObjectDifference {
+    /// Number of frames in which both trackers report the object
+    pub frames: usize,
+    /// RMS position difference in meters
+    pub position_rms: f64,
+    /// Largest position difference in meters
+    pub position_max: f64,
+    /// RMS velocity difference in meters per second, over frames where both trackers report a velocity
+    pub velocity_rms: Option<f64>,
+    /// Frames in which only the first tracker reports the object
+    pub only_in_a: usize,
+    /// Frames in which only the second tracker reports the object
+    pub only_in_b: usize,
+}
+
+/// Result of comparing two tracker sources frame by frame
+#[derive(Clone, Debug)]
+pub struct TrackerComparison {
+    pub source_a: String,
+    pub source_b: String,
+    pub frames_a: usize,
+    pub frames_b: usize,
+    /// Number of frames of the first source that could be matched to the second source
+    pub matched_frames: usize,
+    pub objects: BTreeMap<TrackedObject, ObjectDifference>,
+}
+
+impl TrackerComparison {
+    /// RMS position difference in meters over all robots
+    pub fn robot_position_rms(&self) -> Option<f64> {
+        let robots = self.objects.iter().filter(|(object, _)| matches!(object, TrackedObject::Robot(_)));
+        let (sum, count) = robots.fold((0.0, 0), |(sum, count), (_, d)| {
+            (sum + d.position_rms.powi(2) * d.frames as f64, count + d.frames)
+        });
+        (count > 0).then(|| (sum / count as f64).sqrt())
+    }
+}
+
+#[derive(Default)]
+struct DifferenceSums {
+    frames: usize,
+    position_squares: f64,
+    position_max: f64,
+    velocity_frames: usize,
+    velocity_squares: f64,
+    only_in_a: usize,
+    only_in_b: usize,
+}
+
+/// Inserts `frame` into `frames`, which is ordered by timestamp. Frames usually arrive in order, so this is cheap.
+pub(super) fn insert_frame(frames: &mut VecDeque<FrameState>, frame: FrameState) {
+    let index = frames.partition_point(|f| f.timestamp <= frame.timestamp);
+    frames.insert(index, frame);
+}
+
+/// Compares the frames of two tracker sources in a log
+///
+/// Frames are matched by their tracker timestamp. Each frame of the first source is compared to the second source
+/// interpolated to the same time. Frames are matched as soon as the second source has reached their time, so only
+/// about [`MATCH_WINDOW`] seconds of frames are kept in memory.
+pub struct TrackerComparisonAnalyzer {
+    source_a: String,
+    source_b: String,
+    frames_a: usize,
+    frames_b: usize,
+    matched_frames: usize,
+    /// Frames of the first source waiting for the second source to reach their time
+    pending_a: VecDeque<FrameState>,
+    /// Recent frames of the second source, ordered by timestamp
+    recent_b: VecDeque<FrameState>,
+    latest_a: f64,
+    sums: BTreeMap<TrackedObject, DifferenceSums>,
+}
+
+impl TrackerComparisonAnalyzer {
+    /// Creates an analyzer for two sources, each given as a source name or UUID
+    pub fn new(source_a: impl Into<String>, source_b: impl Into<String>) -> Self {
+        TrackerComparisonAnalyzer {
+            source_a: source_a.into(),
+            source_b: source_b.into(),
+            frames_a: 0,
+            frames_b: 0,
+            matched_frames: 0,
+            pending_a: VecDeque::new(),
+            recent_b: VecDeque::new(),
+            latest_a: f64::NEG_INFINITY,
+            sums: BTreeMap::new(),
+        }
+    }
+
+    /// Matches the pending frames of the first source that the second source has caught up with
+    fn match_pending(&mut self) {
+        let latest_b = self.recent_b.back().map_or(f64::NEG_INFINITY, |f| f.timestamp);
+        while let Some(frame_a) = self.pending_a.front() {
+            if frame_a.timestamp > latest_b && self.latest_a - frame_a.timestamp <= MATCH_WINDOW {
+                break;
+            }
+            let frame_a = self.pending_a.pop_front().unwrap();
+            self.compare(&frame_a);
+        }
+        // Keep the frames of the second source that a pending or late frame of the first source could still use
+        let oldest_needed = self.pending_a.front().map_or(self.latest_a, |f| f.timestamp).min(self.latest_a);
+        while self.recent_b.len() > 1 && self.recent_b[1].timestamp < oldest_needed - MATCH_WINDOW {
+            self.recent_b.pop_front();
+        }
+    }
+
+    fn compare(&mut self, frame_a: &FrameState) {
+        let Some(objects_b) = interpolate_frame(self.recent_b.make_contiguous(), frame_a.timestamp) else {
+            return;
+        };
+        self.matched_frames += 1;
+        for (object, state_a) in &frame_a.objects {
+            let sums = self.sums.entry(*object).or_default();
+            let Some(state_b) = objects_b.get(object) else {
+                sums.only_in_a += 1;
+                continue;
+            };
+            let position_difference = distance(state_a.pos, state_b.pos);
+            sums.frames += 1;
+            sums.position_squares += position_difference.powi(2);
+            sums.position_max = sums.position_max.max(position_difference);
+            if let (Some(vel_a), Some(vel_b)) = (state_a.vel, state_b.vel) {
+                sums.velocity_frames += 1;
+                sums.velocity_squares += distance(vel_a, vel_b).powi(2);
+            }
+        }
+        for object in objects_b.keys().filter(|object| !frame_a.objects.contains_key(object)) {
+            self.sums.entry(*object).or_default().only_in_b += 1;
+        }
+    }
+}
+
+impl Analyzer for TrackerComparisonAnalyzer {
+    type Output = TrackerComparison;
+
+    fn push(&mut self, message: &LogMessage) {
+        let MessageBody::VisionTracker2020(packet) = &message.body else {
+            return;
+        };
+        let Some(frame) = packet.tracked_frame.as_ref() else {
+            return;
+        };
+        if is_from_source(packet, &self.source_a) {
+            self.frames_a += 1;
+            self.latest_a = self.latest_a.max(frame.timestamp());
+            insert_frame(&mut self.pending_a, FrameState::from(frame));
+        } else if is_from_source(packet, &self.source_b) {
+            self.frames_b += 1;
+            insert_frame(&mut self.recent_b, FrameState::from(frame));
+        } else {
+            return;
+        }
+        self.match_pending();
+    }
+
+    fn finish(mut self) -> TrackerComparison {
+        while let Some(frame_a) = self.pending_a.pop_front() {
+            self.compare(&frame_a);
+        }
+
+        let objects = self
+            .sums
+            .into_iter()
+            .map(|(object, sums)| {
+                let difference = ObjectDifference {
+                    frames: sums.frames,
+                    position_rms: (sums.position_squares / sums.frames.max(1) as f64).sqrt(),
+                    position_max: sums.position_max,
+                    velocity_rms: (sums.velocity_frames > 0)
+                        .then(|| (sums.velocity_squares / sums.velocity_frames as f64).sqrt()),
+                    only_in_a: sums.only_in_a,
+                    only_in_b: sums.only_in_b,
+                };
+                (object, difference)
+            })
+            .collect();
+
+        TrackerComparison {
+            source_a: self.source_a,
+            source_b: self.source_b,
+            frames_a: self.frames_a,
+            frames_b: self.frames_b,
+            matched_frames: self.matched_frames,
+            objects,
+        }
+    }
+}
+
+/// Interpolates the objects in `frames` to `timestamp`, returning `None` if there are no frames close enough
+///
+/// Objects are only included if they are in the frames before and after `timestamp`.
+pub(super) fn interpolate_frame(frames: &[FrameState], timestamp: f64) -> Option<BTreeMap<TrackedObject, ObjectState>> {
+    let after_index = frames.partition_point(|f| f.timestamp < timestamp);
+    let after = frames.get(after_index)?;
+    if after.timestamp == timestamp {
+        return Some(after.objects.clone());
+    }
+    let before = frames.get(after_index.checked_sub(1)?)?;
+    if after.timestamp - before.timestamp > MAX_INTERPOLATION_GAP {
+        return None;
+    }
+    let fraction = (timestamp - before.timestamp) / (after.timestamp - before.timestamp);
+    Some(
+        before
+            .objects
+            .iter()
+            .filter_map(|(object, state)| {
+                let next = after.objects.get(object)?;
+                Some((*object, state.interpolate(next, fraction)))
+            })
+            .collect(),
+    )
+}
+
+/// Reads the log at `path` and compares two tracker sources, each given as a source name or UUID
+pub fn compare_trackers(
+    path: impl AsRef<Path>,
+    source_a: impl Into<String>,
+    source_b: impl Into<String>,
+) -> io::Result<TrackerComparison> {
+    run(path, TrackerComparisonAnalyzer::new(source_a, source_b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+    use crate::test_util::{message, tracked};
+
+    const YELLOW: TeamColor = TeamColor::TEAM_COLOR_YELLOW;
+
+    /// Pushes 10 seconds of frames from both sources at 100 Hz, with the second source `offset` meters further along
+    /// x and its messages recorded `delay` seconds after the first source's
+    fn compare(offset: f64, delay: f64) -> TrackerComparisonAnalyzer {
+        let mut analyzer = TrackerComparisonAnalyzer::new("A", "B");
+        let mut messages = Vec::new();
+        for i in 0..1000u32 {
+            let time = f64::from(i) * 0.01;
+            let x = time * 0.5;
+            let a = tracked("A", i, time, Some([x, 0.0]), &[(YELLOW, 1, [x, 1.0])]);
+            // The second source runs at half the rate, so its frames have to be interpolated
+            messages.push(message(time, MessageBody::VisionTracker2020(a)));
+            if i % 2 == 0 {
+                let b = tracked("B", i, time, Some([x + offset, 0.0]), &[(YELLOW, 1, [x + offset, 1.0])]);
+                messages.push(message(time + delay, MessageBody::VisionTracker2020(b)));
+            }
+        }
+        messages.sort_by_key(|m| m.timestamp);
+        for message in &messages {
+            analyzer.push(message);
+        }
+        analyzer
+    }
+
+    #[test]
+    fn frames_are_matched_by_interpolation() {
+        let comparison = compare(0.1, 0.0).finish();
+        assert_eq!((comparison.frames_a, comparison.frames_b), (1000, 500));
+        // The last frame of the first source is after the last frame of the second source
+        assert_eq!(comparison.matched_frames, 999);
+        let ball = &comparison.objects[&TrackedObject::Ball];
+        assert_eq!(ball.frames, 999);
+        assert!((ball.position_rms - 0.1).abs() < 1.0e-4, "{}", ball.position_rms);
+        assert!((comparison.robot_position_rms().unwrap() - 0.1).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn late_source_is_matched_with_bounded_buffers() {
+        let analyzer = compare(0.0, 0.5);
+        assert!(analyzer.pending_a.len() <= 60, "{}", analyzer.pending_a.len());
+        assert!(analyzer.recent_b.len() <= 120, "{}", analyzer.recent_b.len());
+        let comparison = analyzer.finish();
+        assert_eq!(comparison.matched_frames, 999);
+        assert!(comparison.objects[&TrackedObject::Ball].position_max < 1.0e-6);
+    }
+
+    #[test]
+    fn missing_source_does_not_grow_buffers() {
+        let mut analyzer = TrackerComparisonAnalyzer::new("A", "Missing");
+        for i in 0..1000u32 {
+            let time = f64::from(i) * 0.01;
+            let frame = tracked("A", i, time, Some([0.0, 0.0]), &[]);
+            analyzer.push(&message(time, MessageBody::VisionTracker2020(frame)));
+        }
+        assert!(analyzer.pending_a.len() <= 101, "{}", analyzer.pending_a.len());
+        assert_eq!(analyzer.finish().matched_frames, 0);
+    }
+}
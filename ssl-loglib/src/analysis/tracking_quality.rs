@@ -0,0 +1,410 @@
+use crate::analysis::trackers::{FrameState, MATCH_WINDOW, TrackedObject, distance, insert_frame, interpolate_frame};
+use crate::analysis::{Analyzer, Histogram, run};
+use crate::protos::vision::messages_robocup_ssl_detection::SSL_DetectionFrame;
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+use crate::tracker::{RobotKey, is_from_source, source_label};
+use crate::{LogMessage, MessageBody};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::path::Path;
+
+/// Ball detections further than this from the tracked ball, in meters, are not counted as observations of it
+const BALL_ASSOCIATION_DISTANCE: f64 = 0.5;
+/// Robot detections further than this from the tracked robot, in meters, are not counted as observations of it
+///
+/// This is less than the distance between two robots, so a detection with another robot's ID is not associated.
+const ROBOT_ASSOCIATION_DISTANCE: f64 = 0.3;
+/// Residual histogram bin width in meters, which limits the precision of the residual percentiles
+const RESIDUAL_BIN_WIDTH: f64 = 0.001;
+/// A tracked object with no detection within this many seconds is counted as a ghost
+const GHOST_TIMEOUT: f64 = 0.5;
+
+/// How well the tracker follows the raw detections of one object
+#[derive(Clone, Debug)]
+pub struct ObjectQuality {
+    /// Raw detections of the object from all cameras while the tracker was running
+    pub detections: usize,
+    /// Distance in meters between each associated detection and the tracked position at the same time
+    pub residuals: Histogram,
+    /// Detections too far from the tracked position to be associated with it, such as false detections or
+    /// detections of another robot with the wrong ID
+    pub unassociated_detections: usize,
+    /// Detections made while the tracker did not report the object
+    pub lost_detections: usize,
+    /// Number of periods in which the object was detected but not tracked
+    pub track_losses: usize,
+    /// Tracker frames that report the object
+    pub tracked_frames: usize,
+    /// Tracker frames that report the object with no detection of it nearby in time
+    pub ghost_frames: usize,
+    /// Number of periods in which the object was tracked but not detected
+    pub ghost_tracks: usize,
+}
+
+/// Comparison of one tracker source with the raw detections in a log
+#[derive(Clone, Debug, Default)]
+pub struct TrackingQuality {
+    /// Name or UUID of the tracker source that was analyzed, if the log has tracker messages
+    pub source: Option<String>,
+    pub tracker_frames: usize,
+    pub objects: BTreeMap<TrackedObject, ObjectQuality>,
+}
+
+struct Detection {
+    /// `t_capture` of the detection frame
+    timestamp: f64,
+    object: TrackedObject,
+    pos: [f64; 3],
+    /// Whether the detection is from a legacy Vision2010 frame
+    legacy: bool,
+}
+
+struct QualitySums {
+    quality: ObjectQuality,
+    association_distance: f64,
+    lost: bool,
+    ghost: bool,
+}
+
+impl QualitySums {
+    fn new(object: TrackedObject) -> Self {
+        let association_distance = match object {
+            TrackedObject::Ball => BALL_ASSOCIATION_DISTANCE,
+            TrackedObject::Robot(_) => ROBOT_ASSOCIATION_DISTANCE,
+        };
+        QualitySums {
+            quality: ObjectQuality {
+                detections: 0,
+                residuals: Histogram::new(0.0, association_distance, RESIDUAL_BIN_WIDTH),
+                unassociated_detections: 0,
+                lost_detections: 0,
+                track_losses: 0,
+                tracked_frames: 0,
+                ghost_frames: 0,
+                ghost_tracks: 0,
+            },
+            association_distance,
+            lost: false,
+            ghost: false,
+        }
+    }
+}
+
+/// Compares a tracker source with the raw detections from all cameras
+///
+/// Tracker frames are matched to detections by comparing the tracker timestamp to `t_capture`, so both must be on
+/// the vision clock. Detections are compared as soon as the tracker has reached their capture time, and tracker
+/// frames are checked for ghosts once the detections have moved [`GHOST_TIMEOUT`] past them, so only about
+/// [`MATCH_WINDOW`] seconds of frames and detections are kept in memory.
+///
+/// Logs usually contain every detection frame twice, as Vision2014 and as legacy Vision2010 messages. Legacy frames
+/// are only used until the first Vision2014 frame.
+pub struct TrackingQualityAnalyzer {
+    source: Option<String>,
+    tracker_frames: usize,
+    /// Whether a Vision2014 frame has been seen, after which legacy frames are ignored
+    vision2014: bool,
+    /// Recent tracker frames ordered by timestamp, kept while a pending or late detection could use them
+    frames: VecDeque<FrameState>,
+    /// Timestamps and objects of the tracker frames that have not been checked for ghosts yet, ordered by timestamp
+    unchecked_frames: VecDeque<(f64, Vec<TrackedObject>)>,
+    /// Detections waiting for the tracker to reach their capture time, ordered by capture time
+    pending: VecDeque<Detection>,
+    /// Capture times of the recent detections of each object, ordered, used to find ghost tracks
+    detection_times: BTreeMap<TrackedObject, VecDeque<f64>>,
+    latest_frame: f64,
+    latest_detection: f64,
+    sums: BTreeMap<TrackedObject, QualitySums>,
+}
+
+impl TrackingQualityAnalyzer {
+    /// Creates an analyzer for the tracker with the given source name or UUID
+    ///
+    /// If `source` is `None`, the first tracker source in the log is analyzed.
+    pub fn new(source: Option<String>) -> Self {
+        TrackingQualityAnalyzer {
+            source,
+            tracker_frames: 0,
+            vision2014: false,
+            frames: VecDeque::new(),
+            unchecked_frames: VecDeque::new(),
+            pending: VecDeque::new(),
+            detection_times: BTreeMap::new(),
+            latest_frame: f64::NEG_INFINITY,
+            latest_detection: f64::NEG_INFINITY,
+            sums: BTreeMap::new(),
+        }
+    }
+
+    fn add_detections(&mut self, frame: &SSL_DetectionFrame, legacy: bool) {
+        let timestamp = frame.t_capture();
+        self.latest_detection = self.latest_detection.max(timestamp);
+        let balls = frame.balls.iter().map(|ball| (TrackedObject::Ball, ball.x(), ball.y()));
+        let yellow = frame.robots_yellow.iter().map(|r| (TeamColor::TEAM_COLOR_YELLOW, r));
+        let blue = frame.robots_blue.iter().map(|r| (TeamColor::TEAM_COLOR_BLUE, r));
+        // Detections without an ID are unidentified patterns that cannot be matched to a track
+        let robots = yellow.chain(blue).filter_map(|(team, robot)| {
+            let object = TrackedObject::Robot(RobotKey { team, id: robot.robot_id? });
+            Some((object, robot.x(), robot.y()))
+        });
+        for (object, x, y) in balls.chain(robots) {
+            let times = self.detection_times.entry(object).or_default();
+            times.insert(times.partition_point(|t| *t <= timestamp), timestamp);
+            let detection = Detection {
+                timestamp,
+                object,
+                pos: [f64::from(x) / 1000.0, f64::from(y) / 1000.0, 0.0],
+                legacy,
+            };
+            let index = self.pending.partition_point(|d| d.timestamp <= timestamp);
+            self.pending.insert(index, detection);
+        }
+    }
+
+    /// Compares the pending detections that the tracker has caught up with
+    fn compare_pending(&mut self) {
+        while let Some(detection) = self.pending.front() {
+            if detection.timestamp > self.latest_frame && self.latest_detection - detection.timestamp <= MATCH_WINDOW {
+                break;
+            }
+            let detection = self.pending.pop_front().unwrap();
+            self.compare(&detection);
+        }
+        // Keep the frames that a pending or late detection could still use. Without detections, frames are only kept
+        // for a while in case the detections are recorded after the tracker frames.
+        let oldest_needed = self
+            .pending
+            .front()
+            .map_or(f64::INFINITY, |d| d.timestamp)
+            .min(self.latest_detection.max(self.latest_frame - MATCH_WINDOW));
+        while self.frames.len() > 1 && self.frames[1].timestamp < oldest_needed - MATCH_WINDOW {
+            self.frames.pop_front();
+        }
+    }
+
+    fn compare(&mut self, detection: &Detection) {
+        // Skip detections made while the tracker was not running
+        let Some(tracked) = interpolate_frame(self.frames.make_contiguous(), detection.timestamp) else {
+            return;
+        };
+        let sums = self.sums.entry(detection.object).or_insert_with(|| QualitySums::new(detection.object));
+        sums.quality.detections += 1;
+        let Some(state) = tracked.get(&detection.object) else {
+            sums.quality.lost_detections += 1;
+            if !sums.lost {
+                sums.quality.track_losses += 1;
+                sums.lost = true;
+            }
+            return;
+        };
+        sums.lost = false;
+        // Tracked positions are 3D for the ball, but detections are compared on the ground plane
+        let residual = distance(detection.pos, [state.pos[0], state.pos[1], 0.0]);
+        if residual > sums.association_distance {
+            sums.quality.unassociated_detections += 1;
+        } else {
+            sums.quality.residuals.add(residual);
+        }
+    }
+
+    /// Checks the tracker frames for ghosts once no more detections near them are expected, or all of them if
+    /// `all` is set
+    fn check_ghosts(&mut self, all: bool) {
+        while let Some((timestamp, _)) = self.unchecked_frames.front() {
+            let detections_passed = self.latest_detection - timestamp > GHOST_TIMEOUT;
+            let tracker_passed = self.latest_frame - timestamp > MATCH_WINDOW;
+            if !all && !detections_passed && !tracker_passed {
+                break;
+            }
+            let (timestamp, objects) = self.unchecked_frames.pop_front().unwrap();
+            for object in objects {
+                let sums = self.sums.entry(object).or_insert_with(|| QualitySums::new(object));
+                sums.quality.tracked_frames += 1;
+                let times = self.detection_times.get(&object);
+                let next = times.map_or(0, |times| times.partition_point(|t| *t < timestamp));
+                let nearest = [next.checked_sub(1), Some(next)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|i| times?.get(i))
+                    .map(|t| (t - timestamp).abs())
+                    .min_by(f64::total_cmp);
+                if nearest.is_some_and(|dt| dt <= GHOST_TIMEOUT) {
+                    sums.ghost = false;
+                    continue;
+                }
+                sums.quality.ghost_frames += 1;
+                if !sums.ghost {
+                    sums.quality.ghost_tracks += 1;
+                    sums.ghost = true;
+                }
+            }
+        }
+        // Forget detections too old to be near an unchecked or future tracker frame
+        let oldest_needed = self
+            .unchecked_frames
+            .front()
+            .map_or(self.latest_frame.max(self.latest_detection - MATCH_WINDOW), |(t, _)| *t);
+        for times in self.detection_times.values_mut() {
+            while times.front().is_some_and(|t| *t < oldest_needed - GHOST_TIMEOUT) {
+                times.pop_front();
+            }
+        }
+    }
+}
+
+impl Analyzer for TrackingQualityAnalyzer {
+    type Output = TrackingQuality;
+
+    fn push(&mut self, message: &LogMessage) {
+        match &message.body {
+            MessageBody::VisionTracker2020(packet) => {
+                let source = self.source.get_or_insert_with(|| source_label(packet).to_string());
+                let Some(frame) = packet.tracked_frame.as_ref().filter(|_| is_from_source(packet, source)) else {
+                    return;
+                };
+                let frame = FrameState::from(frame);
+                self.tracker_frames += 1;
+                self.latest_frame = self.latest_frame.max(frame.timestamp);
+                let index = self.unchecked_frames.partition_point(|(t, _)| *t <= frame.timestamp);
+                self.unchecked_frames.insert(index, (frame.timestamp, frame.objects.keys().copied().collect()));
+                insert_frame(&mut self.frames, frame);
+            }
+            MessageBody::Vision2014(packet) => {
+                let Some(frame) = packet.detection.as_ref() else {
+                    return;
+                };
+                if !self.vision2014 {
+                    self.vision2014 = true;
+                    self.pending.retain(|d| !d.legacy);
+                }
+                self.add_detections(frame, false);
+            }
+            MessageBody::Vision2010(packet) if !self.vision2014 => {
+                let Some(frame) = packet.detection.as_ref() else {
+                    return;
+                };
+                self.add_detections(frame, true);
+            }
+            _ => return,
+        }
+        self.compare_pending();
+        self.check_ghosts(false);
+    }
+
+    fn finish(mut self) -> TrackingQuality {
+        while let Some(detection) = self.pending.pop_front() {
+            self.compare(&detection);
+        }
+        self.check_ghosts(true);
+
+        TrackingQuality {
+            source: self.source,
+            tracker_frames: self.tracker_frames,
+            objects: self.sums.into_iter().map(|(object, sums)| (object, sums.quality)).collect(),
+        }
+    }
+}
+
+/// Reads the log at `path` and compares a tracker source with the raw detections
+///
+/// `source` is a source name or UUID. If it is `None`, the first tracker source in the log is analyzed.
+pub fn analyze_tracking_quality(path: impl AsRef<Path>, source: Option<String>) -> io::Result<TrackingQuality> {
+    run(path, TrackingQualityAnalyzer::new(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{detection, message, tracked};
+
+    const YELLOW: TeamColor = TeamColor::TEAM_COLOR_YELLOW;
+
+    fn robot(id: u32) -> TrackedObject {
+        TrackedObject::Robot(RobotKey { team: YELLOW, id })
+    }
+
+    /// Pushes 3 seconds of detections and tracker frames at 100 Hz, with each tracker frame recorded 5 ms after the
+    /// detection frame with the same capture time
+    ///
+    /// Robot 1 is tracked 1 cm away from its detections, except for one detection 1 m away. Robot 2 is not tracked
+    /// from 1 to 1.5 seconds, and robot 3 is not detected after 1 second.
+    fn analyze() -> TrackingQualityAnalyzer {
+        let mut messages = Vec::new();
+        for i in 0..300u32 {
+            let time = f64::from(i) * 0.01;
+            let x = time * 0.5;
+            let robot_1 = if i == 200 { x + 1.0 } else { x + 0.01 };
+            let mut detected = vec![(1, [robot_1, 0.0]), (2, [x, 1.0])];
+            if time < 1.0 {
+                detected.push((3, [x, 2.0]));
+            }
+            let packet = detection(0, i, time, &[[x, -1.0]], &detected);
+            messages.push(message(time, MessageBody::Vision2014(packet)));
+
+            let mut robots = vec![(YELLOW, 1, [x, 0.0]), (YELLOW, 3, [x, 2.0])];
+            if !(1.0..1.5).contains(&time) {
+                robots.push((YELLOW, 2, [x, 1.0]));
+            }
+            let frame = tracked("T", i, time, Some([x, -1.0]), &robots);
+            messages.push(message(time + 0.005, MessageBody::VisionTracker2020(frame)));
+        }
+        let mut analyzer = TrackingQualityAnalyzer::new(None);
+        for message in &messages {
+            analyzer.push(message);
+        }
+        analyzer
+    }
+
+    #[test]
+    fn residuals_exclude_unassociated_detections() {
+        let quality = analyze().finish();
+        assert_eq!(quality.source.as_deref(), Some("T"));
+        assert_eq!(quality.tracker_frames, 300);
+        let robot_1 = &quality.objects[&robot(1)];
+        assert_eq!(robot_1.detections, 300);
+        assert_eq!(robot_1.unassociated_detections, 1);
+        assert_eq!(robot_1.residuals.len(), 299);
+        let median = robot_1.residuals.percentile(50.0).unwrap();
+        assert!((median - 0.01).abs() <= RESIDUAL_BIN_WIDTH, "{}", median);
+        assert!(robot_1.residuals.max().unwrap() < 0.011);
+        let ball = &quality.objects[&TrackedObject::Ball];
+        assert_eq!((ball.unassociated_detections, ball.residuals.len()), (0, 300));
+    }
+
+    #[test]
+    fn lost_and_ghost_tracks_are_counted() {
+        let quality = analyze().finish();
+        let robot_2 = &quality.objects[&robot(2)];
+        assert_eq!((robot_2.lost_detections, robot_2.track_losses), (50, 1));
+        assert_eq!(robot_2.ghost_frames, 0);
+        let robot_3 = &quality.objects[&robot(3)];
+        assert_eq!(robot_3.tracked_frames, 300);
+        // Frames more than GHOST_TIMEOUT after the last detection at 0.99 seconds are ghosts
+        assert_eq!((robot_3.ghost_frames, robot_3.ghost_tracks), (150, 1));
+        assert_eq!(robot_3.lost_detections, 0);
+    }
+
+    #[test]
+    fn buffers_stay_bounded() {
+        let analyzer = analyze();
+        assert!(analyzer.frames.len() <= 210, "{}", analyzer.frames.len());
+        assert!(analyzer.unchecked_frames.len() <= 60, "{}", analyzer.unchecked_frames.len());
+        assert!(analyzer.pending.len() <= 4, "{}", analyzer.pending.len());
+        let times = &analyzer.detection_times[&robot(1)];
+        assert!(times.len() <= 160, "{}", times.len());
+    }
+
+    #[test]
+    fn detections_without_tracker_are_skipped() {
+        let mut analyzer = TrackingQualityAnalyzer::new(None);
+        for i in 0..1000u32 {
+            let time = f64::from(i) * 0.01;
+            let packet = detection(0, i, time, &[[0.0, 0.0]], &[(1, [1.0, 0.0])]);
+            analyzer.push(&message(time, MessageBody::Vision2014(packet)));
+        }
+        assert!(analyzer.pending.len() <= 2 * 101, "{}", analyzer.pending.len());
+        let quality = analyzer.finish();
+        assert_eq!(quality.source, None);
+        assert!(quality.objects.is_empty());
+    }
+}
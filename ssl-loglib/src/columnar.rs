@@ -0,0 +1,593 @@
+use crate::{LogMessage, MessageBody};
+use crate::protos::refbox::ssl_gc_referee_message::Referee;
+use crate::protos::vision::messages_robocup_ssl_detection::{SSL_DetectionBall, SSL_DetectionFrame, SSL_DetectionRobot};
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::{TeamColor, TrackedBall, TrackedRobot};
+use crate::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use crate::tracker::{source_label, team_name};
+use arrow_array::builder::{
+    ArrayBuilder, Float32Builder, Float64Builder, Int64Builder, StringBuilder, TimestampNanosecondBuilder,
+    UInt32Builder, UInt64Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use std::sync::Arc;
+
+/// The tables produced from a log by [`ColumnarConverter`]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Table {
+    /// One row per robot per raw vision detection frame
+    DetectionRobots,
+    /// One row per ball per raw vision detection frame
+    DetectionBalls,
+    /// One row per robot per tracker frame
+    TrackedRobots,
+    /// One row per ball per tracker frame
+    TrackedBalls,
+    /// One row per referee message
+    Referee,
+}
+
+impl Table {
+    pub const ALL: [Table; 5] = [
+        Table::DetectionRobots,
+        Table::DetectionBalls,
+        Table::TrackedRobots,
+        Table::TrackedBalls,
+        Table::Referee,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Table::DetectionRobots => "detection_robots",
+            Table::DetectionBalls => "detection_balls",
+            Table::TrackedRobots => "tracked_robots",
+            Table::TrackedBalls => "tracked_balls",
+            Table::Referee => "referee",
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        let timestamp = Field::new("timestamp", DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())), false);
+        let fields = match self {
+            Table::DetectionRobots => vec![
+                timestamp,
+                Field::new("camera_id", DataType::UInt32, false),
+                Field::new("frame_number", DataType::UInt32, false),
+                Field::new("t_capture", DataType::Float64, false),
+                Field::new("t_sent", DataType::Float64, false),
+                Field::new("team", DataType::Utf8, false),
+                Field::new("robot_id", DataType::UInt32, true),
+                Field::new("confidence", DataType::Float32, false),
+                Field::new("x", DataType::Float32, false),
+                Field::new("y", DataType::Float32, false),
+                Field::new("orientation", DataType::Float32, true),
+                Field::new("pixel_x", DataType::Float32, false),
+                Field::new("pixel_y", DataType::Float32, false),
+                Field::new("height", DataType::Float32, true),
+            ],
+            Table::DetectionBalls => vec![
+                timestamp,
+                Field::new("camera_id", DataType::UInt32, false),
+                Field::new("frame_number", DataType::UInt32, false),
+                Field::new("t_capture", DataType::Float64, false),
+                Field::new("t_sent", DataType::Float64, false),
+                Field::new("confidence", DataType::Float32, false),
+                Field::new("area", DataType::UInt32, true),
+                Field::new("x", DataType::Float32, false),
+                Field::new("y", DataType::Float32, false),
+                Field::new("z", DataType::Float32, true),
+                Field::new("pixel_x", DataType::Float32, false),
+                Field::new("pixel_y", DataType::Float32, false),
+            ],
+            Table::TrackedRobots => vec![
+                timestamp,
+                Field::new("source", DataType::Utf8, false),
+                Field::new("frame_number", DataType::UInt32, false),
+                Field::new("frame_timestamp", DataType::Float64, false),
+                Field::new("team", DataType::Utf8, false),
+                Field::new("robot_id", DataType::UInt32, false),
+                Field::new("pos_x", DataType::Float32, false),
+                Field::new("pos_y", DataType::Float32, false),
+                Field::new("orientation", DataType::Float32, false),
+                Field::new("vel_x", DataType::Float32, true),
+                Field::new("vel_y", DataType::Float32, true),
+                Field::new("vel_angular", DataType::Float32, true),
+                Field::new("visibility", DataType::Float32, true),
+            ],
+            Table::TrackedBalls => vec![
+                timestamp,
+                Field::new("source", DataType::Utf8, false),
+                Field::new("frame_number", DataType::UInt32, false),
+                Field::new("frame_timestamp", DataType::Float64, false),
+                Field::new("ball_index", DataType::UInt32, false),
+                Field::new("pos_x", DataType::Float32, false),
+                Field::new("pos_y", DataType::Float32, false),
+                Field::new("pos_z", DataType::Float32, false),
+                Field::new("vel_x", DataType::Float32, true),
+                Field::new("vel_y", DataType::Float32, true),
+                Field::new("vel_z", DataType::Float32, true),
+                Field::new("visibility", DataType::Float32, true),
+            ],
+            Table::Referee => vec![
+                timestamp,
+                Field::new("packet_timestamp", DataType::UInt64, false),
+                Field::new("stage", DataType::Utf8, false),
+                Field::new("stage_time_left", DataType::Int64, true),
+                Field::new("command", DataType::Utf8, false),
+                Field::new("command_counter", DataType::UInt32, false),
+                Field::new("command_timestamp", DataType::UInt64, false),
+                Field::new("yellow_name", DataType::Utf8, false),
+                Field::new("yellow_score", DataType::UInt32, false),
+                Field::new("blue_name", DataType::Utf8, false),
+                Field::new("blue_score", DataType::UInt32, false),
+                Field::new("game_event_count", DataType::UInt32, false),
+            ],
+        };
+        Arc::new(Schema::new(fields))
+    }
+}
+
+fn timestamp_builder() -> TimestampNanosecondBuilder {
+    TimestampNanosecondBuilder::new().with_timezone("UTC")
+}
+
+struct DetectionRobotColumns {
+    timestamp: TimestampNanosecondBuilder,
+    camera_id: UInt32Builder,
+    frame_number: UInt32Builder,
+    t_capture: Float64Builder,
+    t_sent: Float64Builder,
+    team: StringBuilder,
+    robot_id: UInt32Builder,
+    confidence: Float32Builder,
+    x: Float32Builder,
+    y: Float32Builder,
+    orientation: Float32Builder,
+    pixel_x: Float32Builder,
+    pixel_y: Float32Builder,
+    height: Float32Builder,
+}
+
+impl DetectionRobotColumns {
+    fn new() -> Self {
+        DetectionRobotColumns {
+            timestamp: timestamp_builder(),
+            camera_id: UInt32Builder::new(),
+            frame_number: UInt32Builder::new(),
+            t_capture: Float64Builder::new(),
+            t_sent: Float64Builder::new(),
+            team: StringBuilder::new(),
+            robot_id: UInt32Builder::new(),
+            confidence: Float32Builder::new(),
+            x: Float32Builder::new(),
+            y: Float32Builder::new(),
+            orientation: Float32Builder::new(),
+            pixel_x: Float32Builder::new(),
+            pixel_y: Float32Builder::new(),
+            height: Float32Builder::new(),
+        }
+    }
+
+    fn append(&mut self, timestamp: i64, frame: &SSL_DetectionFrame, team: TeamColor, robot: &SSL_DetectionRobot) {
+        self.timestamp.append_value(timestamp);
+        self.camera_id.append_value(frame.camera_id());
+        self.frame_number.append_value(frame.frame_number());
+        self.t_capture.append_value(frame.t_capture());
+        self.t_sent.append_value(frame.t_sent());
+        self.team.append_value(team_name(team));
+        self.robot_id.append_option(robot.robot_id);
+        self.confidence.append_value(robot.confidence());
+        self.x.append_value(robot.x());
+        self.y.append_value(robot.y());
+        self.orientation.append_option(robot.orientation);
+        self.pixel_x.append_value(robot.pixel_x());
+        self.pixel_y.append_value(robot.pixel_y());
+        self.height.append_option(robot.height);
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.camera_id.finish()),
+            Arc::new(self.frame_number.finish()),
+            Arc::new(self.t_capture.finish()),
+            Arc::new(self.t_sent.finish()),
+            Arc::new(self.team.finish()),
+            Arc::new(self.robot_id.finish()),
+            Arc::new(self.confidence.finish()),
+            Arc::new(self.x.finish()),
+            Arc::new(self.y.finish()),
+            Arc::new(self.orientation.finish()),
+            Arc::new(self.pixel_x.finish()),
+            Arc::new(self.pixel_y.finish()),
+            Arc::new(self.height.finish()),
+        ]
+    }
+}
+
+struct DetectionBallColumns {
+    timestamp: TimestampNanosecondBuilder,
+    camera_id: UInt32Builder,
+    frame_number: UInt32Builder,
+    t_capture: Float64Builder,
+    t_sent: Float64Builder,
+    confidence: Float32Builder,
+    area: UInt32Builder,
+    x: Float32Builder,
+    y: Float32Builder,
+    z: Float32Builder,
+    pixel_x: Float32Builder,
+    pixel_y: Float32Builder,
+}
+
+impl DetectionBallColumns {
+    fn new() -> Self {
+        DetectionBallColumns {
+            timestamp: timestamp_builder(),
+            camera_id: UInt32Builder::new(),
+            frame_number: UInt32Builder::new(),
+            t_capture: Float64Builder::new(),
+            t_sent: Float64Builder::new(),
+            confidence: Float32Builder::new(),
+            area: UInt32Builder::new(),
+            x: Float32Builder::new(),
+            y: Float32Builder::new(),
+            z: Float32Builder::new(),
+            pixel_x: Float32Builder::new(),
+            pixel_y: Float32Builder::new(),
+        }
+    }
+
+    fn append(&mut self, timestamp: i64, frame: &SSL_DetectionFrame, ball: &SSL_DetectionBall) {
+        self.timestamp.append_value(timestamp);
+        self.camera_id.append_value(frame.camera_id());
+        self.frame_number.append_value(frame.frame_number());
+        self.t_capture.append_value(frame.t_capture());
+        self.t_sent.append_value(frame.t_sent());
+        self.confidence.append_value(ball.confidence());
+        self.area.append_option(ball.area);
+        self.x.append_value(ball.x());
+        self.y.append_value(ball.y());
+        self.z.append_option(ball.z);
+        self.pixel_x.append_value(ball.pixel_x());
+        self.pixel_y.append_value(ball.pixel_y());
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.camera_id.finish()),
+            Arc::new(self.frame_number.finish()),
+            Arc::new(self.t_capture.finish()),
+            Arc::new(self.t_sent.finish()),
+            Arc::new(self.confidence.finish()),
+            Arc::new(self.area.finish()),
+            Arc::new(self.x.finish()),
+            Arc::new(self.y.finish()),
+            Arc::new(self.z.finish()),
+            Arc::new(self.pixel_x.finish()),
+            Arc::new(self.pixel_y.finish()),
+        ]
+    }
+}
+
+struct TrackedRobotColumns {
+    timestamp: TimestampNanosecondBuilder,
+    source: StringBuilder,
+    frame_number: UInt32Builder,
+    frame_timestamp: Float64Builder,
+    team: StringBuilder,
+    robot_id: UInt32Builder,
+    pos_x: Float32Builder,
+    pos_y: Float32Builder,
+    orientation: Float32Builder,
+    vel_x: Float32Builder,
+    vel_y: Float32Builder,
+    vel_angular: Float32Builder,
+    visibility: Float32Builder,
+}
+
+impl TrackedRobotColumns {
+    fn new() -> Self {
+        TrackedRobotColumns {
+            timestamp: timestamp_builder(),
+            source: StringBuilder::new(),
+            frame_number: UInt32Builder::new(),
+            frame_timestamp: Float64Builder::new(),
+            team: StringBuilder::new(),
+            robot_id: UInt32Builder::new(),
+            pos_x: Float32Builder::new(),
+            pos_y: Float32Builder::new(),
+            orientation: Float32Builder::new(),
+            vel_x: Float32Builder::new(),
+            vel_y: Float32Builder::new(),
+            vel_angular: Float32Builder::new(),
+            visibility: Float32Builder::new(),
+        }
+    }
+
+    fn append(&mut self, timestamp: i64, packet: &TrackerWrapperPacket, robot: &TrackedRobot) {
+        self.timestamp.append_value(timestamp);
+        self.source.append_value(source_label(packet));
+        self.frame_number.append_value(packet.tracked_frame.frame_number());
+        self.frame_timestamp.append_value(packet.tracked_frame.timestamp());
+        self.team.append_value(team_name(robot.robot_id.team_color()));
+        self.robot_id.append_value(robot.robot_id.id());
+        self.pos_x.append_value(robot.pos.x());
+        self.pos_y.append_value(robot.pos.y());
+        self.orientation.append_value(robot.orientation());
+        self.vel_x.append_option(robot.vel.as_ref().map(|v| v.x()));
+        self.vel_y.append_option(robot.vel.as_ref().map(|v| v.y()));
+        self.vel_angular.append_option(robot.vel_angular);
+        self.visibility.append_option(robot.visibility);
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.source.finish()),
+            Arc::new(self.frame_number.finish()),
+            Arc::new(self.frame_timestamp.finish()),
+            Arc::new(self.team.finish()),
+            Arc::new(self.robot_id.finish()),
+            Arc::new(self.pos_x.finish()),
+            Arc::new(self.pos_y.finish()),
+            Arc::new(self.orientation.finish()),
+            Arc::new(self.vel_x.finish()),
+            Arc::new(self.vel_y.finish()),
+            Arc::new(self.vel_angular.finish()),
+            Arc::new(self.visibility.finish()),
+        ]
+    }
+}
+
+struct TrackedBallColumns {
+    timestamp: TimestampNanosecondBuilder,
+    source: StringBuilder,
+    frame_number: UInt32Builder,
+    frame_timestamp: Float64Builder,
+    ball_index: UInt32Builder,
+    pos_x: Float32Builder,
+    pos_y: Float32Builder,
+    pos_z: Float32Builder,
+    vel_x: Float32Builder,
+    vel_y: Float32Builder,
+    vel_z: Float32Builder,
+    visibility: Float32Builder,
+}
+
+impl TrackedBallColumns {
+    fn new() -> Self {
+        TrackedBallColumns {
+            timestamp: timestamp_builder(),
+            source: StringBuilder::new(),
+            frame_number: UInt32Builder::new(),
+            frame_timestamp: Float64Builder::new(),
+            ball_index: UInt32Builder::new(),
+            pos_x: Float32Builder::new(),
+            pos_y: Float32Builder::new(),
+            pos_z: Float32Builder::new(),
+            vel_x: Float32Builder::new(),
+            vel_y: Float32Builder::new(),
+            vel_z: Float32Builder::new(),
+            visibility: Float32Builder::new(),
+        }
+    }
+
+    fn append(&mut self, timestamp: i64, packet: &TrackerWrapperPacket, index: usize, ball: &TrackedBall) {
+        self.timestamp.append_value(timestamp);
+        self.source.append_value(source_label(packet));
+        self.frame_number.append_value(packet.tracked_frame.frame_number());
+        self.frame_timestamp.append_value(packet.tracked_frame.timestamp());
+        self.ball_index.append_value(index as u32);
+        self.pos_x.append_value(ball.pos.x());
+        self.pos_y.append_value(ball.pos.y());
+        self.pos_z.append_value(ball.pos.z());
+        self.vel_x.append_option(ball.vel.as_ref().map(|v| v.x()));
+        self.vel_y.append_option(ball.vel.as_ref().map(|v| v.y()));
+        self.vel_z.append_option(ball.vel.as_ref().map(|v| v.z()));
+        self.visibility.append_option(ball.visibility);
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.source.finish()),
+            Arc::new(self.frame_number.finish()),
+            Arc::new(self.frame_timestamp.finish()),
+            Arc::new(self.ball_index.finish()),
+            Arc::new(self.pos_x.finish()),
+            Arc::new(self.pos_y.finish()),
+            Arc::new(self.pos_z.finish()),
+            Arc::new(self.vel_x.finish()),
+            Arc::new(self.vel_y.finish()),
+            Arc::new(self.vel_z.finish()),
+            Arc::new(self.visibility.finish()),
+        ]
+    }
+}
+
+struct RefereeColumns {
+    timestamp: TimestampNanosecondBuilder,
+    packet_timestamp: UInt64Builder,
+    stage: StringBuilder,
+    stage_time_left: Int64Builder,
+    command: StringBuilder,
+    command_counter: UInt32Builder,
+    command_timestamp: UInt64Builder,
+    yellow_name: StringBuilder,
+    yellow_score: UInt32Builder,
+    blue_name: StringBuilder,
+    blue_score: UInt32Builder,
+    game_event_count: UInt32Builder,
+}
+
+impl RefereeColumns {
+    fn new() -> Self {
+        RefereeColumns {
+            timestamp: timestamp_builder(),
+            packet_timestamp: UInt64Builder::new(),
+            stage: StringBuilder::new(),
+            stage_time_left: Int64Builder::new(),
+            command: StringBuilder::new(),
+            command_counter: UInt32Builder::new(),
+            command_timestamp: UInt64Builder::new(),
+            yellow_name: StringBuilder::new(),
+            yellow_score: UInt32Builder::new(),
+            blue_name: StringBuilder::new(),
+            blue_score: UInt32Builder::new(),
+            game_event_count: UInt32Builder::new(),
+        }
+    }
+
+    fn append(&mut self, timestamp: i64, referee: &Referee) {
+        self.timestamp.append_value(timestamp);
+        self.packet_timestamp.append_value(referee.packet_timestamp());
+        self.stage.append_value(format!("{:?}", referee.stage()));
+        self.stage_time_left.append_option(referee.stage_time_left);
+        self.command.append_value(format!("{:?}", referee.command()));
+        self.command_counter.append_value(referee.command_counter());
+        self.command_timestamp.append_value(referee.command_timestamp());
+        self.yellow_name.append_value(referee.yellow.name());
+        self.yellow_score.append_value(referee.yellow.score());
+        self.blue_name.append_value(referee.blue.name());
+        self.blue_score.append_value(referee.blue.score());
+        self.game_event_count.append_value(referee.game_events.len() as u32);
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.packet_timestamp.finish()),
+            Arc::new(self.stage.finish()),
+            Arc::new(self.stage_time_left.finish()),
+            Arc::new(self.command.finish()),
+            Arc::new(self.command_counter.finish()),
+            Arc::new(self.command_timestamp.finish()),
+            Arc::new(self.yellow_name.finish()),
+            Arc::new(self.yellow_score.finish()),
+            Arc::new(self.blue_name.finish()),
+            Arc::new(self.blue_score.finish()),
+            Arc::new(self.game_event_count.finish()),
+        ]
+    }
+}
+
+/// Converts log messages into Arrow record batches, one table per [`Table`] variant
+pub struct ColumnarConverter {
+    detection_robots: DetectionRobotColumns,
+    detection_balls: DetectionBallColumns,
+    tracked_robots: TrackedRobotColumns,
+    tracked_balls: TrackedBallColumns,
+    referee: RefereeColumns,
+}
+
+impl Default for ColumnarConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColumnarConverter {
+    pub fn new() -> Self {
+        ColumnarConverter {
+            detection_robots: DetectionRobotColumns::new(),
+            detection_balls: DetectionBallColumns::new(),
+            tracked_robots: TrackedRobotColumns::new(),
+            tracked_balls: TrackedBallColumns::new(),
+            referee: RefereeColumns::new(),
+        }
+    }
+
+    /// Adds the rows from one message. Messages without detection, tracker, or referee data are ignored.
+    pub fn push(&mut self, message: &LogMessage) {
+        let Some(timestamp) = message.timestamp.timestamp_nanos_opt() else {
+            return;
+        };
+        match &message.body {
+            MessageBody::Vision2014(packet) => {
+                if let Some(frame) = packet.detection.as_ref() {
+                    for robot in &frame.robots_yellow {
+                        self.detection_robots.append(timestamp, frame, TeamColor::TEAM_COLOR_YELLOW, robot);
+                    }
+                    for robot in &frame.robots_blue {
+                        self.detection_robots.append(timestamp, frame, TeamColor::TEAM_COLOR_BLUE, robot);
+                    }
+                    for ball in &frame.balls {
+                        self.detection_balls.append(timestamp, frame, ball);
+                    }
+                }
+            }
+            MessageBody::VisionTracker2020(packet) => {
+                let Some(frame) = packet.tracked_frame.as_ref() else {
+                    return;
+                };
+                for robot in &frame.robots {
+                    self.tracked_robots.append(timestamp, packet, robot);
+                }
+                for (index, ball) in frame.balls.iter().enumerate() {
+                    self.tracked_balls.append(timestamp, packet, index, ball);
+                }
+            }
+            MessageBody::Refbox2013(referee) => self.referee.append(timestamp, referee),
+            _ => {}
+        }
+    }
+
+    /// Number of rows buffered for `table` since it was last finished
+    pub fn len(&self, table: Table) -> usize {
+        match table {
+            Table::DetectionRobots => self.detection_robots.timestamp.len(),
+            Table::DetectionBalls => self.detection_balls.timestamp.len(),
+            Table::TrackedRobots => self.tracked_robots.timestamp.len(),
+            Table::TrackedBalls => self.tracked_balls.timestamp.len(),
+            Table::Referee => self.referee.timestamp.len(),
+        }
+    }
+
+    pub fn is_empty(&self, table: Table) -> bool {
+        self.len(table) == 0
+    }
+
+    /// Builds a record batch from the rows buffered for `table` and clears them
+    pub fn finish(&mut self, table: Table) -> Result<RecordBatch, ArrowError> {
+        let columns = match table {
+            Table::DetectionRobots => self.detection_robots.finish(),
+            Table::DetectionBalls => self.detection_balls.finish(),
+            Table::TrackedRobots => self.tracked_robots.finish(),
+            Table::TrackedBalls => self.tracked_balls.finish(),
+            Table::Referee => self.referee.finish(),
+        };
+        RecordBatch::try_new(table.schema(), columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use crate::test_util::{detection, message, referee, tracked};
+
+    #[test]
+    fn batches_have_the_table_schema() {
+        let robots = [(TeamColor::TEAM_COLOR_YELLOW, 1, [0.0, 0.0]), (TeamColor::TEAM_COLOR_BLUE, 2, [1.0, 0.0])];
+        let messages = [
+            message(0.0, MessageBody::Refbox2013(referee(0.0, Stage::NORMAL_FIRST_HALF, Command::STOP, 1))),
+            message(0.1, MessageBody::Vision2014(detection(0, 1, 0.1, &[[0.5, 0.5]], &[(1, [0.0, 0.0])]))),
+            message(0.2, MessageBody::VisionTracker2020(tracked("T", 1, 0.2, Some([0.5, 0.5]), &robots))),
+        ];
+        let mut converter = ColumnarConverter::new();
+        for message in &messages {
+            converter.push(message);
+        }
+        for (table, rows) in Table::ALL.into_iter().zip([1, 1, 2, 1, 1]) {
+            assert_eq!(converter.len(table), rows, "{}", table.name());
+            let batch = converter.finish(table).unwrap();
+            assert_eq!(batch.schema(), table.schema(), "{}", table.name());
+            assert_eq!(batch.num_rows(), rows);
+            assert!(converter.is_empty(table));
+            // Tables without rows have the same schema
+            assert_eq!(converter.finish(table).unwrap().schema(), table.schema());
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use crate::log_file_reader::LogFileReader;
+use crate::log_file_writer::LogFileWriter;
+use crate::protos::refbox::ssl_gc_referee_message::Referee;
+use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+use crate::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
+use crate::protos::vision::messages_robocup_ssl_wrapper_legacy::SSL_WrapperPacket as SSL_WrapperPacket_Legacy;
+use crate::raw::{MessageType, RawMessage};
+use crate::seconds_to_nanos;
+use protobuf::Message;
+use std::io;
+use std::path::Path;
+
+/// Selects which part of a log is kept by [`cut_log`]
+#[derive(Clone, Debug)]
+pub enum CutRange {
+    /// Time range in seconds from the first message in the log. Open ends extend to the start / end of the log.
+    Time { start: Option<f64>, end: Option<f64> },
+    /// All messages received while the referee reports the given stage
+    Stage(Stage),
+    /// Window around each time the referee issues the given command, in seconds before and after the command
+    Command { command: Command, before: f64, after: f64 },
+}
+
+/// Latest state messages needed for a cut log to stand on its own
+#[derive(Default)]
+struct StreamState {
+    vision2010_geometry: Option<RawMessage>,
+    vision2014_geometry: Option<RawMessage>,
+    referee: Option<RawMessage>,
+}
+
+impl StreamState {
+    fn update(&mut self, message: &RawMessage) -> io::Result<()> {
+        match message.message_type {
+            MessageType::Vision2010 => {
+                let packet = SSL_WrapperPacket_Legacy::parse_from_bytes(&message.data)?;
+                if packet.geometry.is_some() {
+                    let geometry_packet = SSL_WrapperPacket_Legacy {
+                        geometry: packet.geometry,
+                        ..Default::default()
+                    };
+                    self.vision2010_geometry = Some(RawMessage {
+                        timestamp: message.timestamp,
+                        message_type: message.message_type,
+                        data: geometry_packet.write_to_bytes()?,
+                    });
+                }
+            }
+            MessageType::Vision2014 => {
+                let packet = SSL_WrapperPacket::parse_from_bytes(&message.data)?;
+                if packet.geometry.is_some() {
+                    let geometry_packet = SSL_WrapperPacket {
+                        geometry: packet.geometry,
+                        ..Default::default()
+                    };
+                    self.vision2014_geometry = Some(RawMessage {
+                        timestamp: message.timestamp,
+                        message_type: message.message_type,
+                        data: geometry_packet.write_to_bytes()?,
+                    });
+                }
+            }
+            MessageType::Refbox2013 => {
+                self.referee = Some(message.clone());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn write_to(&self, writer: &mut LogFileWriter, timestamp: i64) -> io::Result<usize> {
+        let mut count = 0;
+        for message in [&self.vision2010_geometry, &self.vision2014_geometry, &self.referee].into_iter().flatten() {
+            writer.write_raw_message(&RawMessage {
+                timestamp,
+                ..message.clone()
+            })?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Writes the part of the log at `input_path` selected by `range` to a new log at `output_path`.
+///
+/// At the start of each kept window, the most recent geometry and referee messages are re-emitted so the output
+/// can be used on its own. Index messages are not copied. Returns the number of messages written.
+pub fn cut_log(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    range: &CutRange,
+) -> io::Result<usize> {
+    let windows = find_windows(input_path.as_ref(), range)?;
+
+    let mut reader = LogFileReader::new(input_path)?;
+    let mut writer = LogFileWriter::new(output_path)?;
+    let mut state = StreamState::default();
+    let mut window_index = 0;
+    let mut in_window = false;
+    let mut written = 0;
+
+    loop {
+        let message = match reader.get_next_raw_message() {
+            Ok(m) => m,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        while window_index < windows.len() && windows[window_index].1 <= message.timestamp {
+            window_index += 1;
+            in_window = false;
+        }
+        let inside = window_index < windows.len() && windows[window_index].0 <= message.timestamp;
+        if inside {
+            if !in_window {
+                written += state.write_to(&mut writer, message.timestamp)?;
+                in_window = true;
+            }
+            if message.message_type != MessageType::Index2021 {
+                writer.write_raw_message(&message)?;
+                written += 1;
+            }
+        }
+        state.update(&message)?;
+    }
+
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Finds the sorted, non-overlapping `[start, end)` timestamp windows selected by `range`
+fn find_windows(path: &Path, range: &CutRange) -> io::Result<Vec<(i64, i64)>> {
+    let mut reader = LogFileReader::new(path)?;
+    let mut windows = Vec::new();
+
+    match range {
+        CutRange::Time { start, end } => {
+            let first_timestamp = match reader.get_next_raw_message() {
+                Ok(m) => m.timestamp,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(windows),
+                Err(e) => return Err(e),
+            };
+            let window_start = start.map_or(i64::MIN, |s| first_timestamp + seconds_to_nanos(s));
+            let window_end = end.map_or(i64::MAX, |e| first_timestamp + seconds_to_nanos(e));
+            windows.push((window_start, window_end));
+        }
+        CutRange::Stage(stage) => {
+            let mut window_start: Option<i64> = None;
+            for_each_referee_message(&mut reader, |timestamp, referee| {
+                match (referee.stage() == *stage, window_start) {
+                    (true, None) => window_start = Some(timestamp),
+                    (false, Some(start)) => {
+                        windows.push((start, timestamp));
+                        window_start = None;
+                    }
+                    _ => {}
+                }
+            })?;
+            if let Some(start) = window_start {
+                windows.push((start, i64::MAX));
+            }
+        }
+        CutRange::Command { command, before, after } => {
+            let mut last_counter: Option<u32> = None;
+            for_each_referee_message(&mut reader, |timestamp, referee| {
+                if last_counter != Some(referee.command_counter()) && referee.command() == *command {
+                    windows.push((
+                        timestamp.saturating_sub(seconds_to_nanos(*before)),
+                        timestamp.saturating_add(seconds_to_nanos(*after)),
+                    ));
+                }
+                last_counter = Some(referee.command_counter());
+            })?;
+        }
+    }
+
+    windows.sort();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(windows.len());
+    for window in windows {
+        match merged.last_mut() {
+            Some(last) if window.0 <= last.1 => last.1 = last.1.max(window.1),
+            _ => merged.push(window),
+        }
+    }
+    Ok(merged)
+}
+
+fn for_each_referee_message(
+    reader: &mut LogFileReader,
+    mut f: impl FnMut(i64, &Referee),
+) -> io::Result<()> {
+    loop {
+        let message = match reader.get_next_raw_message() {
+            Ok(m) => m,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if message.message_type == MessageType::Refbox2013 {
+            let referee = Referee::parse_from_bytes(&message.data)?;
+            f(message.timestamp, &referee);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBody;
+    use crate::test_util::{at, geometry, message, read_raw_log, referee, write_log};
+    use crate::LogMessage;
+
+    fn referee_message(seconds: f64, stage: Stage, command: Command, counter: u32) -> LogMessage {
+        message(seconds, MessageBody::Refbox2013(referee(seconds, stage, command, counter)))
+    }
+
+    /// A referee message every 100 ms with the given stage and command for each second of the log
+    fn referee_log(seconds: &[(Stage, Command, u32)]) -> Vec<LogMessage> {
+        let mut messages = Vec::new();
+        for (second, (stage, command, counter)) in seconds.iter().enumerate() {
+            for step in 0..10 {
+                let time = second as f64 + step as f64 * 0.1;
+                messages.push(referee_message(time, *stage, *command, *counter));
+            }
+        }
+        messages
+    }
+
+    #[test]
+    fn time_range_keeps_messages_inside_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages: Vec<LogMessage> = (0..30)
+            .map(|i| message(i as f64 * 0.1, MessageBody::Blank(vec![i as u8])))
+            .collect();
+        let input = write_log(dir.path(), "input.log", &messages);
+        let output = dir.path().join("output.log");
+
+        let range = CutRange::Time { start: Some(1.0), end: Some(2.0) };
+        let written = cut_log(&input, &output, &range).unwrap();
+
+        let cut = read_raw_log(&output);
+        assert_eq!(written, 10);
+        assert_eq!(cut.len(), 10);
+        assert_eq!(cut.first().unwrap().timestamp, at(1.0));
+        assert_eq!(cut.last().unwrap().timestamp, at(1.9));
+    }
+
+    #[test]
+    fn state_is_reemitted_at_window_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = vec![
+            message(0.0, MessageBody::Vision2014(geometry())),
+            referee_message(0.5, Stage::NORMAL_FIRST_HALF, Command::STOP, 1),
+            message(1.0, MessageBody::Blank(vec![1])),
+            message(2.0, MessageBody::Blank(vec![2])),
+        ];
+        let input = write_log(dir.path(), "input.log", &messages);
+        let output = dir.path().join("output.log");
+
+        let range = CutRange::Time { start: Some(0.8), end: None };
+        cut_log(&input, &output, &range).unwrap();
+
+        let cut = read_raw_log(&output);
+        let types: Vec<MessageType> = cut.iter().map(|m| m.message_type).collect();
+        assert_eq!(
+            types,
+            [MessageType::Vision2014, MessageType::Refbox2013, MessageType::Blank, MessageType::Blank]
+        );
+        // Re-emitted state is stamped with the time of the first message in the window
+        assert!(cut[..3].iter().all(|m| m.timestamp == at(1.0)));
+        let original_referee = RawMessage::try_from(&messages[1]).unwrap();
+        assert_eq!(cut[1].data, original_referee.data);
+    }
+
+    #[test]
+    fn stage_windows_cover_the_stage() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = referee_log(&[
+            (Stage::NORMAL_FIRST_HALF_PRE, Command::HALT, 1),
+            (Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 2),
+            (Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 2),
+            (Stage::NORMAL_HALF_TIME, Command::HALT, 3),
+        ]);
+        let input = write_log(dir.path(), "input.log", &messages);
+
+        let windows = find_windows(&input, &CutRange::Stage(Stage::NORMAL_FIRST_HALF)).unwrap();
+        assert_eq!(windows, [(at(1.0), at(3.0))]);
+
+        let windows = find_windows(&input, &CutRange::Stage(Stage::NORMAL_HALF_TIME)).unwrap();
+        assert_eq!(windows, [(at(3.0), i64::MAX)]);
+    }
+
+    #[test]
+    fn command_windows_start_at_new_commands_and_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = referee_log(&[
+            (Stage::NORMAL_FIRST_HALF, Command::STOP, 1),
+            (Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 2),
+            (Stage::NORMAL_FIRST_HALF, Command::STOP, 3),
+            (Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 4),
+            (Stage::NORMAL_FIRST_HALF, Command::STOP, 5),
+            (Stage::NORMAL_FIRST_HALF, Command::STOP, 5),
+            (Stage::NORMAL_FIRST_HALF, Command::FORCE_START, 6),
+        ]);
+        let input = write_log(dir.path(), "input.log", &messages);
+
+        let range = CutRange::Command { command: Command::FORCE_START, before: 0.5, after: 0.25 };
+        let windows = find_windows(&input, &range).unwrap();
+        assert_eq!(windows, [(at(0.5), at(1.25)), (at(2.5), at(3.25)), (at(5.5), at(6.25))]);
+
+        // Windows that overlap are merged
+        let range = CutRange::Command { command: Command::FORCE_START, before: 0.5, after: 1.5 };
+        let windows = find_windows(&input, &range).unwrap();
+        assert_eq!(windows, [(at(0.5), at(4.5)), (at(5.5), at(7.5))]);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::raw::{CustomTypeId, MessageType, RawMessage};
+use crate::{LogMessage, MessageBody};
+use chrono::{TimeZone, Utc};
+use protobuf::MessageFull;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+type DecodeFn = dyn Fn(&[u8]) -> io::Result<Arc<dyn Any + Send + Sync>> + Send + Sync;
+
+/// A message with a custom type ID, decoded by a function registered in a [`DecoderRegistry`]
+#[derive(Clone)]
+pub struct CustomMessage {
+    pub type_id: CustomTypeId,
+    /// Original payload, kept so the message can be written back to a log unchanged
+    pub data: Vec<u8>,
+    pub value: Arc<dyn Any + Send + Sync>,
+}
+
+impl CustomMessage {
+    /// Returns the decoded value if it has type `T`
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref::<T>()
+    }
+}
+
+impl fmt::Debug for CustomMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomMessage")
+            .field("type_id", &self.type_id)
+            .field("data", &self.data)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decode functions for message type IDs outside the standard set
+///
+/// Messages with a custom type ID that has no registered decoder are returned as
+/// [`MessageBody::Undecoded`] with their raw payload.
+#[derive(Clone, Default)]
+pub struct DecoderRegistry {
+    decoders: HashMap<CustomTypeId, Arc<DecodeFn>>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a function that decodes payloads with the given type ID
+    ///
+    /// Returns an error if `type_id` is one of the standard message types, which are always decoded by this library.
+    pub fn register<T, F>(&mut self, type_id: i32, decode: F) -> io::Result<()>
+    where
+        T: Any + Send + Sync,
+        F: Fn(&[u8]) -> io::Result<T> + Send + Sync + 'static,
+    {
+        self.decoders.insert(
+            CustomTypeId::try_from(type_id)?,
+            Arc::new(move |data| Ok(Arc::new(decode(data)?) as Arc<dyn Any + Send + Sync>)),
+        );
+        Ok(())
+    }
+
+    /// Registers a protobuf message type to decode payloads with the given type ID
+    pub fn register_protobuf<M: MessageFull>(&mut self, type_id: i32) -> io::Result<()> {
+        self.register(type_id, |data| Ok(M::parse_from_bytes(data)?))
+    }
+
+    pub fn is_registered(&self, type_id: i32) -> bool {
+        CustomTypeId::new(type_id).is_some_and(|type_id| self.decoders.contains_key(&type_id))
+    }
+
+    /// Decodes a raw message, using the registered decoders for custom type IDs
+    pub fn decode(&self, raw_message: &RawMessage) -> io::Result<LogMessage> {
+        if let MessageType::Custom(type_id) = raw_message.message_type
+            && let Some(decode) = self.decoders.get(&type_id)
+        {
+            return Ok(LogMessage {
+                timestamp: Utc.timestamp_nanos(raw_message.timestamp),
+                body: MessageBody::Custom(CustomMessage {
+                    type_id,
+                    data: raw_message.data.clone(),
+                    value: decode(&raw_message.data)?,
+                }),
+            });
+        }
+        LogMessage::try_from(raw_message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::refbox::ssl_gc_referee_message::Referee;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use crate::test_util::{at, referee};
+    use protobuf::Message;
+
+    fn raw(type_id: i32, data: Vec<u8>) -> RawMessage {
+        RawMessage {
+            timestamp: at(1.0),
+            message_type: MessageType::try_from(type_id).unwrap(),
+            data,
+        }
+    }
+
+    fn registry() -> DecoderRegistry {
+        let mut decoders = DecoderRegistry::new();
+        decoders.register_protobuf::<Referee>(100).unwrap();
+        decoders.register(101, |data| Ok(data.len())).unwrap();
+        decoders
+    }
+
+    #[test]
+    fn registered_types_are_decoded() {
+        let data = referee(1.0, Stage::NORMAL_FIRST_HALF, Command::STOP, 3).write_to_bytes().unwrap();
+        let message = registry().decode(&raw(100, data.clone())).unwrap();
+        assert_eq!(message.timestamp.timestamp_nanos_opt(), Some(at(1.0)));
+        let MessageBody::Custom(custom) = &message.body else {
+            panic!("Message was not decoded");
+        };
+        assert_eq!(custom.type_id.get(), 100);
+        assert_eq!(custom.downcast_ref::<Referee>().unwrap().command_counter(), 3);
+        assert!(custom.downcast_ref::<usize>().is_none());
+        // The original payload is written back unchanged
+        assert_eq!(RawMessage::try_from(&message).unwrap(), raw(100, data));
+
+        let message = registry().decode(&raw(101, vec![1, 2, 3])).unwrap();
+        let MessageBody::Custom(custom) = &message.body else {
+            panic!("Message was not decoded");
+        };
+        assert_eq!(custom.downcast_ref::<usize>(), Some(&3));
+    }
+
+    #[test]
+    fn unregistered_types_keep_their_payload() {
+        let message = registry().decode(&raw(7, b"team data".to_vec())).unwrap();
+        let MessageBody::Undecoded(type_id, data) = &message.body else {
+            panic!("Message was decoded without a registered decoder");
+        };
+        assert_eq!((type_id.get(), data.as_slice()), (7, b"team data".as_slice()));
+        // Standard types are decoded as usual
+        let data = referee(1.0, Stage::NORMAL_FIRST_HALF, Command::HALT, 1).write_to_bytes().unwrap();
+        assert!(matches!(registry().decode(&raw(3, data)).unwrap().body, MessageBody::Refbox2013(_)));
+    }
+
+    #[test]
+    fn registering_standard_types_and_decode_failures_are_errors() {
+        let mut decoders = registry();
+        let error = decoders.register(3, |data| Ok(data.to_vec())).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(!decoders.is_registered(3));
+        assert!(decoders.is_registered(100));
+        assert!(decoders.decode(&raw(100, vec![0xff])).is_err());
+    }
+}
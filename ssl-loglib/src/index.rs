@@ -1,6 +1,10 @@
 use std::io;
 
+/// Marker at the end of every index message, and so at the end of every indexed log file
+pub const INDEX_MARKER: &[u8] = b"INDEXED";
+
 #[derive(Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndexMessage {
     /// Array with byte-aligned offsets, starting at the beginning of the file
     pub offsets: Vec<i64>,
@@ -18,11 +22,11 @@ impl IndexMessage {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "Not enough bytes in data for valid Index message."));
         }
 
-        if (data.len() - 15) % 8 != 0 {
+        if !(data.len() - 15).is_multiple_of(8) {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid number of bytes for Index message."));
         }
 
-        if &data[data.len() - 7..] != "INDEXED".as_bytes() {
+        if &data[data.len() - 7..] != INDEX_MARKER {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "'INDEXED' marker not found at end of data"));
         }
 
@@ -34,13 +38,23 @@ impl IndexMessage {
         };
 
         for i in 0..num_offsets {
-            let offset_bytes: [u8; 8] = data[i..i+8].try_into().or(Err(io::Error::new(io::ErrorKind::InvalidInput, "Could not pull expected number of bytes for offset.")))?;
+            let offset_bytes: [u8; 8] = data[i*8..i*8+8].try_into().or(Err(io::Error::new(io::ErrorKind::InvalidInput, "Could not pull expected number of bytes for offset.")))?;
             message.offsets.push(i64::from_be_bytes(offset_bytes));
         }
 
-        let index_offset_bytes: [u8; 8] = data[num_offsets..num_offsets+8].try_into().or(Err(io::Error::new(io::ErrorKind::InvalidInput, "Could not pull expected number of bytes for index offset.")))?;
+        let index_offset_bytes: [u8; 8] = data[num_offsets*8..num_offsets*8+8].try_into().or(Err(io::Error::new(io::ErrorKind::InvalidInput, "Could not pull expected number of bytes for index offset.")))?;
         message.index_offset = i64::from_be_bytes(index_offset_bytes);
 
         Ok(message)
     }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.offsets.len() * 8 + 15);
+        for offset in &self.offsets {
+            data.extend_from_slice(&offset.to_be_bytes());
+        }
+        data.extend_from_slice(&self.index_offset.to_be_bytes());
+        data.extend_from_slice(INDEX_MARKER);
+        data
+    }
 }
@@ -0,0 +1,185 @@
+use crate::index::IndexMessage;
+use crate::protos::refbox::ssl_gc_referee_message::Referee;
+use crate::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
+use crate::protos::vision::messages_robocup_ssl_wrapper_legacy::SSL_WrapperPacket as SSL_WrapperPacket_Legacy;
+use crate::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use crate::raw::MessageType;
+use crate::{LogMessage, MessageBody};
+use chrono::{TimeZone, Utc};
+use protobuf::{MessageDyn, MessageFull};
+use protobuf_json_mapping::PrintOptions;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de, ser};
+use serde_json::Value;
+use std::io;
+
+/// JSON representation of a log message
+#[derive(Serialize, Deserialize)]
+struct JsonLogMessage {
+    /// Receive timestamp in nanoseconds since the Unix epoch
+    timestamp: i64,
+    #[serde(rename = "type")]
+    message_type: MessageType,
+    body: Value,
+}
+
+fn invalid_data(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+fn protobuf_to_json(message: &dyn MessageDyn) -> io::Result<Value> {
+    let options = PrintOptions {
+        proto_field_name: true,
+        ..Default::default()
+    };
+    let json = protobuf_json_mapping::print_to_string_with_options(message, &options).map_err(invalid_data)?;
+    serde_json::from_str(&json).map_err(invalid_data)
+}
+
+fn protobuf_from_json<M: MessageFull>(value: &Value) -> io::Result<M> {
+    protobuf_json_mapping::parse_from_str(&value.to_string()).map_err(invalid_data)
+}
+
+/// Reads a raw payload stored as an array of bytes, treating null as an empty payload
+fn bytes_from_json(value: &Value) -> io::Result<Vec<u8>> {
+    if value.is_null() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_value(value.clone()).map_err(invalid_data)
+}
+
+impl MessageBody {
+    /// Converts the message body to JSON, using the protobuf JSON mapping for protobuf messages
+    pub fn to_json(&self) -> io::Result<Value> {
+        match self {
+            MessageBody::Blank(data) | MessageBody::Unkown(data) | MessageBody::Undecoded(_, data) => {
+                serde_json::to_value(data).map_err(invalid_data)
+            }
+            MessageBody::Vision2010(packet) => protobuf_to_json(packet),
+            MessageBody::Refbox2013(referee) => protobuf_to_json(referee),
+            MessageBody::Vision2014(packet) => protobuf_to_json(packet),
+            MessageBody::VisionTracker2020(packet) => protobuf_to_json(packet),
+            MessageBody::Index2021(index) => serde_json::to_value(index).map_err(invalid_data),
+            MessageBody::Custom(message) => serde_json::to_value(&message.data).map_err(invalid_data),
+        }
+    }
+
+    /// Parses a message body of the given type from JSON, as written by [`MessageBody::to_json`]
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the JSON does not match the message type.
+    pub fn from_json(message_type: MessageType, value: &Value) -> io::Result<MessageBody> {
+        Ok(match message_type {
+            MessageType::Blank => MessageBody::Blank(bytes_from_json(value)?),
+            MessageType::Unkown => MessageBody::Unkown(bytes_from_json(value)?),
+            MessageType::Vision2010 => {
+                MessageBody::Vision2010(protobuf_from_json::<SSL_WrapperPacket_Legacy>(value)?)
+            }
+            MessageType::Refbox2013 => MessageBody::Refbox2013(protobuf_from_json::<Referee>(value)?),
+            MessageType::Vision2014 => MessageBody::Vision2014(protobuf_from_json::<SSL_WrapperPacket>(value)?),
+            MessageType::VisionTracker2020 => {
+                MessageBody::VisionTracker2020(protobuf_from_json::<TrackerWrapperPacket>(value)?)
+            }
+            MessageType::Index2021 => MessageBody::Index2021(
+                serde_json::from_value::<IndexMessage>(value.clone()).map_err(invalid_data)?,
+            ),
+            MessageType::Custom(type_id) => MessageBody::Undecoded(type_id, bytes_from_json(value)?),
+        })
+    }
+}
+
+impl Serialize for MessageBody {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().map_err(ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+impl Serialize for LogMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let timestamp = self
+            .timestamp
+            .timestamp_nanos_opt()
+            .ok_or(ser::Error::custom("Message timestamp out of range"))?;
+        JsonLogMessage {
+            timestamp,
+            message_type: self.body.message_type(),
+            body: self.body.to_json().map_err(ser::Error::custom)?,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LogMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let message = JsonLogMessage::deserialize(deserializer)?;
+        Ok(LogMessage {
+            timestamp: Utc.timestamp_nanos(message.timestamp),
+            body: MessageBody::from_json(message.message_type, &message.body).map_err(de::Error::custom)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::CustomMessage;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use crate::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+    use crate::raw::{CustomTypeId, RawMessage};
+    use crate::test_util::{detection, geometry, message, referee, tracked, with_kick};
+    use protobuf::Message;
+    use std::sync::Arc;
+
+    fn round_trip(message: &LogMessage) -> LogMessage {
+        serde_json::from_str(&serde_json::to_string(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn every_message_type_round_trips() {
+        let vision = detection(1, 42, 1.25, &[[0.5, -0.25]], &[(3, [1.0, 2.0])]);
+        let legacy = SSL_WrapperPacket_Legacy::parse_from_bytes(&vision.write_to_bytes().unwrap()).unwrap();
+        let tracker = tracked("T", 7, 3.5, Some([0.1, 0.2]), &[(TeamColor::TEAM_COLOR_BLUE, 4, [-1.0, 0.5])]);
+        let tracker = with_kick(tracker, (TeamColor::TEAM_COLOR_BLUE, 4), 3.4, [0.0, 0.1], [4.0, 1.0, 0.5]);
+        let type_id = CustomTypeId::new(100).unwrap();
+        let custom = CustomMessage {
+            type_id,
+            data: b"team data".to_vec(),
+            value: Arc::new(()),
+        };
+        let index = IndexMessage {
+            offsets: vec![16, 40, 96],
+            index_offset: 55,
+        };
+        let messages = [
+            message(0.0, MessageBody::Blank(vec![1, 2, 3])),
+            message(0.1, MessageBody::Unkown(vec![255, 0])),
+            message(0.2, MessageBody::Vision2010(legacy)),
+            message(0.3, MessageBody::Refbox2013(referee(0.3, Stage::NORMAL_FIRST_HALF, Command::STOP, 3))),
+            message(0.4, MessageBody::Vision2014(vision)),
+            message(0.5, MessageBody::Vision2014(geometry())),
+            message(0.6, MessageBody::VisionTracker2020(tracker)),
+            message(0.7, MessageBody::Index2021(index)),
+            message(0.8, MessageBody::Custom(custom)),
+            message(0.9, MessageBody::Undecoded(type_id, vec![9, 8, 7])),
+        ];
+        for original in &messages {
+            let decoded = round_trip(original);
+            assert_eq!(decoded.timestamp, original.timestamp);
+            assert_eq!(
+                RawMessage::try_from(&decoded).unwrap(),
+                RawMessage::try_from(original).unwrap(),
+                "{:?}",
+                original.body.message_type()
+            );
+        }
+        // Decoded values are not written to JSON, so custom messages are read back with only their payload
+        assert!(matches!(round_trip(&messages[8]).body, MessageBody::Undecoded(id, _) if id == type_id));
+    }
+
+    #[test]
+    fn mismatched_body_is_invalid_data() {
+        let error = MessageBody::from_json(MessageType::Index2021, &Value::from("not an index")).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        // A null payload is an empty one
+        let empty = MessageBody::from_json(MessageType::Blank, &Value::Null).unwrap();
+        assert!(empty.to_bytes().unwrap().is_empty());
+    }
+}
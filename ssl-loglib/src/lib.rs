@@ -1,7 +1,19 @@
 pub mod protos;
+pub mod decoder;
 pub mod index;
 pub mod log_file_reader;
+pub mod log_file_writer;
 pub mod raw;
+pub mod cut;
+pub mod merge;
+pub mod tracker;
+pub mod analysis;
+#[cfg(feature = "arrow")]
+pub mod columnar;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 
 use std::io;
 use std::io::Read;
@@ -14,18 +26,57 @@ use crate::protos::refbox::ssl_gc_referee_message::Referee;
 use crate::protos::vision::messages_robocup_ssl_wrapper_legacy::SSL_WrapperPacket as SSL_WrapperPacket_Legacy;
 use crate::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
 use crate::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
-use crate::raw::{MessageType, extract_next_raw_message};
+use crate::raw::{CustomTypeId, MessageType, RawMessage, extract_next_raw_message};
 use crate::index::IndexMessage;
+use crate::decoder::CustomMessage;
 
 #[derive(Clone)]
 pub enum MessageBody {
-    Blank(()),
-    Unkown(()),
+    Blank(Vec<u8>),
+    Unkown(Vec<u8>),
     Vision2010(SSL_WrapperPacket_Legacy),
     Refbox2013(Referee),
     Vision2014(SSL_WrapperPacket),
     VisionTracker2020(TrackerWrapperPacket),
     Index2021(IndexMessage),
+    /// A message with a custom type ID that was decoded by a registered decoder
+    Custom(CustomMessage),
+    /// A message with a custom type ID that has no registered decoder
+    Undecoded(CustomTypeId, Vec<u8>),
+}
+
+impl MessageBody {
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            MessageBody::Blank(_) => MessageType::Blank,
+            MessageBody::Unkown(_) => MessageType::Unkown,
+            MessageBody::Vision2010(_) => MessageType::Vision2010,
+            MessageBody::Refbox2013(_) => MessageType::Refbox2013,
+            MessageBody::Vision2014(_) => MessageType::Vision2014,
+            MessageBody::VisionTracker2020(_) => MessageType::VisionTracker2020,
+            MessageBody::Index2021(_) => MessageType::Index2021,
+            MessageBody::Custom(message) => MessageType::Custom(message.type_id),
+            MessageBody::Undecoded(type_id, _) => MessageType::Custom(*type_id),
+        }
+    }
+
+    /// Encodes the body into the payload format used in log files
+    ///
+    /// Raw and index payloads are reproduced exactly. Protobuf messages are serialized again, which keeps their
+    /// unknown fields but may order or pack fields differently than the recorded payload, so the bytes are only
+    /// guaranteed to decode to the same message.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        Ok(match self {
+            MessageBody::Blank(data) | MessageBody::Unkown(data) => data.clone(),
+            MessageBody::Vision2010(packet) => packet.write_to_bytes()?,
+            MessageBody::Refbox2013(referee) => referee.write_to_bytes()?,
+            MessageBody::Vision2014(packet) => packet.write_to_bytes()?,
+            MessageBody::VisionTracker2020(packet) => packet.write_to_bytes()?,
+            MessageBody::Index2021(index) => index.to_bytes(),
+            MessageBody::Custom(message) => message.data.clone(),
+            MessageBody::Undecoded(_, data) => data.clone(),
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -34,19 +85,50 @@ pub struct LogMessage {
     pub body: MessageBody,
 }
 
+impl TryFrom<&RawMessage> for LogMessage {
+    type Error = io::Error;
+
+    fn try_from(raw_message: &RawMessage) -> Result<Self, Self::Error> {
+        let timestamp = Utc.timestamp_nanos(raw_message.timestamp);
+        let body = match raw_message.message_type {
+            MessageType::Blank => MessageBody::Blank(raw_message.data.clone()),
+            MessageType::Unkown => MessageBody::Unkown(raw_message.data.clone()),
+            MessageType::Vision2010 => MessageBody::Vision2010(SSL_WrapperPacket_Legacy::parse_from_bytes(&raw_message.data)?),
+            MessageType::Refbox2013 => MessageBody::Refbox2013(Referee::parse_from_bytes(&raw_message.data)?),
+            MessageType::Vision2014 => MessageBody::Vision2014(SSL_WrapperPacket::parse_from_bytes(&raw_message.data)?),
+            MessageType::VisionTracker2020 => MessageBody::VisionTracker2020(TrackerWrapperPacket::parse_from_bytes(&raw_message.data)?),
+            MessageType::Index2021 => MessageBody::Index2021(IndexMessage::from_bytes(&raw_message.data)?),
+            MessageType::Custom(type_id) => MessageBody::Undecoded(type_id, raw_message.data.clone()),
+        };
+        Ok(LogMessage { timestamp, body })
+    }
+}
+
+/// Encodes a message for writing to a log file, see [`MessageBody::to_bytes`] for how closely the payload matches the
+/// one that was read
+impl TryFrom<&LogMessage> for RawMessage {
+    type Error = io::Error;
+
+    fn try_from(message: &LogMessage) -> Result<Self, Self::Error> {
+        let timestamp = message.timestamp.timestamp_nanos_opt().ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Message timestamp out of range",
+        ))?;
+        Ok(RawMessage {
+            timestamp,
+            message_type: message.body.message_type(),
+            data: message.body.to_bytes()?,
+        })
+    }
+}
+
+/// Converts a duration in seconds to nanoseconds, the unit of log timestamps
+pub fn seconds_to_nanos(seconds: f64) -> i64 {
+    (seconds * 1.0e9) as i64
+}
+
 pub fn extract_next_message<R: Read>(reader: &mut R) -> io::Result<LogMessage> {
-    let raw_message = extract_next_raw_message(reader)?;
-    let timestamp = Utc.timestamp_nanos(raw_message.timestamp);
-    let body = match raw_message.message_type {
-        MessageType::Blank => MessageBody::Blank(()),
-        MessageType::Unkown => MessageBody::Unkown(()),
-        MessageType::Vision2010 => MessageBody::Vision2010(SSL_WrapperPacket_Legacy::parse_from_bytes(&raw_message.data)?),
-        MessageType::Refbox2013 => MessageBody::Refbox2013(Referee::parse_from_bytes(&raw_message.data)?),
-        MessageType::Vision2014 => MessageBody::Vision2014(SSL_WrapperPacket::parse_from_bytes(&raw_message.data)?),
-        MessageType::VisionTracker2020 => MessageBody::VisionTracker2020(TrackerWrapperPacket::parse_from_bytes(&raw_message.data)?),
-        MessageType::Index2021 => MessageBody::Index2021(IndexMessage::from_bytes(&raw_message.data)?),
-    };
-    Ok(LogMessage { timestamp, body })
+    LogMessage::try_from(&extract_next_raw_message(reader)?)
 }
 
 pub fn get_all_referee_messages(path: impl AsRef<Path>) -> io::Result<Vec<LogMessage>> {
@@ -70,3 +152,57 @@ pub fn get_all_referee_messages(path: impl AsRef<Path>) -> io::Result<Vec<LogMes
     }
     Ok(ref_messages)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use crate::test_util::{at, referee};
+    use std::str::FromStr;
+
+    fn raw(seconds: f64, message_type: MessageType, data: Vec<u8>) -> RawMessage {
+        RawMessage {
+            timestamp: at(seconds),
+            message_type,
+            data,
+        }
+    }
+
+    fn round_trip(message: &RawMessage) -> RawMessage {
+        RawMessage::try_from(&LogMessage::try_from(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn raw_messages_round_trip() {
+        let index = IndexMessage {
+            offsets: vec![16, 40, 96],
+            index_offset: 55,
+        };
+        let mut referee_data = referee(1.0, Stage::NORMAL_FIRST_HALF, Command::STOP, 3).write_to_bytes().unwrap();
+        // Field 1000 is not in the referee message and has to be kept as an unknown field
+        referee_data.extend_from_slice(&[0xc0, 0x3e, 0x2a]);
+        let messages = [
+            raw(0.0, MessageType::Blank, vec![1, 2, 3]),
+            raw(0.5, MessageType::Unkown, Vec::new()),
+            raw(1.0, MessageType::Refbox2013, referee_data),
+            raw(1.5, MessageType::try_from(100).unwrap(), b"team data".to_vec()),
+            raw(2.0, MessageType::Index2021, index.to_bytes()),
+        ];
+        for message in &messages {
+            assert_eq!(&round_trip(message), message, "{:?}", message.message_type);
+        }
+    }
+
+    #[test]
+    fn type_ids_have_one_representation() {
+        for id in [-1, 0, 3, 6, 7, 100] {
+            let message_type = MessageType::try_from(id).unwrap();
+            assert_eq!(i32::from(message_type), id);
+            assert_eq!(MessageType::from_str(&id.to_string()).unwrap(), message_type);
+        }
+        assert_eq!(MessageType::try_from(3).unwrap(), MessageType::Refbox2013);
+        assert!(CustomTypeId::new(3).is_none());
+        assert!(CustomTypeId::try_from(6).is_err());
+        assert_eq!(MessageType::try_from(7).unwrap(), MessageType::Custom(CustomTypeId::new(7).unwrap()));
+    }
+}
@@ -1,63 +1,174 @@
-use crate::{LogMessage, extract_next_message};
+use crate::index::{INDEX_MARKER, IndexMessage};
+use crate::LogMessage;
+use crate::decoder::DecoderRegistry;
+use crate::raw::{MessageType, RawMessage, extract_next_raw_message};
 use std::fs::File;
+use std::io::Cursor;
 use std::io::Read;
 use std::io::Seek;
 use std::io;
 use std::path::Path;
 
-pub struct LogFileReader {
-    file: File,
+/// Size of the preamble and version at the start of every log file
+pub const HEADER_SIZE: u64 = 16;
+
+/// Reads the file preamble and version from the start of a log, returning an error if either is invalid
+pub fn verify_log_preamble<R: Read>(reader: &mut R) -> io::Result<()> {
+    const EXPECTED_PREAMBLE: &[u8] = b"SSL_LOG_FILE";
+    let mut preamble_buffer = [0; EXPECTED_PREAMBLE.len()];
+    reader.read_exact(&mut preamble_buffer)?;
+    if preamble_buffer != EXPECTED_PREAMBLE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Log file preamble does not match expected value",
+        ));
+    }
+    const EXPECTED_VERSION: i32 = 1;
+    let mut version_buffer = [0; 4];
+    reader.read_exact(&mut version_buffer)?;
+    let version = i32::from_be_bytes(version_buffer);
+    if version != EXPECTED_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unsupported log format version: {}", version),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads messages from a log file, or from any other seekable source of log data
+pub struct LogFileReader<R = File> {
+    file: R,
+    decoders: DecoderRegistry,
 }
 
-impl LogFileReader {
+impl LogFileReader<File> {
     pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
         let file = File::open(path)?;
+        LogFileReader::from_reader(file)
+    }
+}
+
+impl<'a> LogFileReader<Cursor<&'a [u8]>> {
+    /// Reads log data that is already in memory
+    pub fn from_bytes(data: &'a [u8]) -> io::Result<Self> {
+        LogFileReader::from_reader(Cursor::new(data))
+    }
+}
+
+impl<R: Read + Seek> LogFileReader<R> {
+    pub fn from_reader(file: R) -> io::Result<Self> {
         let mut reader = LogFileReader {
             file,
+            decoders: DecoderRegistry::new(),
         };
         reader.verify_log_preamble()?;
         Ok(reader)
     }
 
+    /// Sets the decoders used for messages with custom type IDs
+    pub fn set_decoders(&mut self, decoders: DecoderRegistry) {
+        self.decoders = decoders;
+    }
+
     pub fn get_next_message(&mut self) -> io::Result<LogMessage> {
-        extract_next_message(&mut self.file)
+        let raw_message = extract_next_raw_message(&mut self.file)?;
+        self.decoders.decode(&raw_message)
+    }
+
+    pub fn get_next_raw_message(&mut self) -> io::Result<RawMessage> {
+        extract_next_raw_message(&mut self.file)
     }
 
     pub fn is_indexed(&mut self) -> io::Result<bool> {
-        const EXPECTED_MARKER: &[u8] = b"INDEXED";
         let position_cache = self.file.stream_position()?;
-        self.file.seek(io::SeekFrom::End(-(EXPECTED_MARKER.len() as i64)))?;
-        let mut marker_buffer = [0; EXPECTED_MARKER.len()];
+        self.file.seek(io::SeekFrom::End(-(INDEX_MARKER.len() as i64)))?;
+        let mut marker_buffer = [0; INDEX_MARKER.len()];
         self.file.read_exact(&mut marker_buffer)?;
         self.file.seek(io::SeekFrom::Start(position_cache))?;
-        Ok(marker_buffer == EXPECTED_MARKER)
+        Ok(marker_buffer == INDEX_MARKER)
     }
 
-    fn verify_log_preamble(&mut self) -> io::Result<()> {
-        const EXPECTED_PREAMBLE: &[u8] = b"SSL_LOG_FILE";
-        let mut preamble_buffer = [0; EXPECTED_PREAMBLE.len()];
-        self.file.read_exact(&mut preamble_buffer)?;
-        if preamble_buffer != EXPECTED_PREAMBLE {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Log file preamble does not match expected value",
-            ));
+    /// Reads the index message at the end of an indexed log file without changing the read position
+    pub fn read_index(&mut self) -> io::Result<IndexMessage> {
+        const INDEX_TRAILER_SIZE: i64 = 15;
+        let position_cache = self.file.stream_position()?;
+        let result = (|| {
+            self.file.seek(io::SeekFrom::End(-INDEX_TRAILER_SIZE))?;
+            let mut index_offset_buffer = [0; 8];
+            self.file.read_exact(&mut index_offset_buffer)?;
+            let index_offset = i64::from_be_bytes(index_offset_buffer);
+            self.file.seek(io::SeekFrom::End(-index_offset))?;
+            let raw_message = extract_next_raw_message(&mut self.file)?;
+            if raw_message.message_type != MessageType::Index2021 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Index offset does not point to an index message",
+                ));
+            }
+            IndexMessage::from_bytes(&raw_message.data)
+        })();
+        self.file.seek(io::SeekFrom::Start(position_cache))?;
+        result
+    }
+
+    /// Moves the read position back to the first message in the log
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.file.seek(io::SeekFrom::Start(HEADER_SIZE))?;
+        Ok(())
+    }
+
+    /// Offset in bytes of the next message from the beginning of the file, for returning to it with [`seek_to_offset`]
+    ///
+    /// [`seek_to_offset`]: LogFileReader::seek_to_offset
+    pub fn position(&mut self) -> io::Result<i64> {
+        let position = self.file.stream_position()?;
+        i64::try_from(position).map_err(|_| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Read position out of range: {}", position),
+        ))
+    }
+
+    /// Moves the read position to the message starting at `offset` bytes from the beginning of the file
+    pub fn seek_to_offset(&mut self, offset: i64) -> io::Result<()> {
+        let offset = u64::try_from(offset).map_err(|_| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid message offset: {}", offset),
+        ))?;
+        self.file.seek(io::SeekFrom::Start(offset.max(HEADER_SIZE)))?;
+        Ok(())
+    }
+
+    /// Uses `index` to move the read position to the first message received at or after `timestamp` (in nanoseconds)
+    pub fn seek_to_timestamp(&mut self, index: &IndexMessage, timestamp: i64) -> io::Result<()> {
+        let mut low = 0;
+        let mut high = index.offsets.len();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            self.seek_to_offset(index.offsets[middle])?;
+            let mut timestamp_buffer = [0; 8];
+            self.file.read_exact(&mut timestamp_buffer)?;
+            if i64::from_be_bytes(timestamp_buffer) < timestamp {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
         }
-        const EXPECTED_VERSION: i32 = 1;
-        let mut version_buffer = [0; 4];
-        self.file.read_exact(&mut version_buffer)?;
-        let version = i32::from_be_bytes(version_buffer);
-        if version != EXPECTED_VERSION {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Unsupported log format version: {}", version),
-            ));
+        match index.offsets.get(low) {
+            Some(offset) => self.seek_to_offset(*offset),
+            None => {
+                self.file.seek(io::SeekFrom::End(0))?;
+                Ok(())
+            }
         }
-        Ok(())
+    }
+
+    fn verify_log_preamble(&mut self) -> io::Result<()> {
+        verify_log_preamble(&mut self.file)
     }
 }
 
-impl Iterator for LogFileReader {
+impl<R: Read + Seek> Iterator for LogFileReader<R> {
     type Item = LogMessage;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -0,0 +1,41 @@
+use crate::raw::{RawMessage, write_raw_message};
+use crate::LogMessage;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::io;
+use std::path::Path;
+
+pub struct LogFileWriter {
+    file: BufWriter<File>,
+}
+
+impl LogFileWriter {
+    pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let mut writer = LogFileWriter {
+            file: BufWriter::new(file),
+        };
+        writer.write_log_preamble()?;
+        Ok(writer)
+    }
+
+    pub fn write_raw_message(&mut self, message: &RawMessage) -> io::Result<()> {
+        write_raw_message(&mut self.file, message)
+    }
+
+    pub fn write_message(&mut self, message: &LogMessage) -> io::Result<()> {
+        self.write_raw_message(&RawMessage::try_from(message)?)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    fn write_log_preamble(&mut self) -> io::Result<()> {
+        const PREAMBLE: &[u8] = b"SSL_LOG_FILE";
+        const VERSION: i32 = 1;
+        self.file.write_all(PREAMBLE)?;
+        self.file.write_all(&VERSION.to_be_bytes())?;
+        Ok(())
+    }
+}
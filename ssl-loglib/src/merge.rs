@@ -0,0 +1,233 @@
+use crate::log_file_reader::LogFileReader;
+use crate::log_file_writer::LogFileWriter;
+use crate::raw::{MessageType, RawMessage};
+use crate::seconds_to_nanos;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Time window in seconds used by `log_merge --deduplicate` unless another one is given
+pub const DEFAULT_DEDUPLICATE_WINDOW: f64 = 0.05;
+
+/// A log to be merged by [`merge_logs`]
+#[derive(Clone, Debug)]
+pub struct MergeInput {
+    pub path: PathBuf,
+    /// Seconds added to every timestamp from this log before merging
+    pub time_offset: f64,
+}
+
+impl MergeInput {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        MergeInput {
+            path: path.into(),
+            time_offset: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MergeSummary {
+    /// Number of messages written to the output log
+    pub written: usize,
+    /// Number of duplicate messages dropped
+    pub duplicates: usize,
+}
+
+struct MergeSource {
+    reader: LogFileReader,
+    time_offset: i64,
+    pending: Option<RawMessage>,
+}
+
+impl MergeSource {
+    fn advance(&mut self) -> io::Result<()> {
+        self.pending = loop {
+            match self.reader.get_next_raw_message() {
+                Ok(m) if m.message_type == MessageType::Index2021 => continue,
+                Ok(m) => {
+                    break Some(RawMessage {
+                        timestamp: m.timestamp.saturating_add(self.time_offset),
+                        ..m
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break None,
+                Err(e) => return Err(e),
+            }
+        };
+        Ok(())
+    }
+}
+
+/// Interleaves the messages of all `inputs` in timestamp order and writes them to a new log at `output_path`.
+///
+/// Messages with equal timestamps are written in the order of `inputs`. If `deduplicate_window` is set, a message with
+/// the same type and payload as a message written less than that many seconds earlier is dropped. The same packet
+/// captured by two recorders has slightly different receive timestamps, so the window should cover the difference
+/// between the recorders' clocks after applying the time offsets. Index messages are not copied.
+pub fn merge_logs(
+    inputs: &[MergeInput],
+    output_path: impl AsRef<Path>,
+    deduplicate_window: Option<f64>,
+) -> io::Result<MergeSummary> {
+    let mut sources = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let mut source = MergeSource {
+            reader: LogFileReader::new(&input.path)?,
+            time_offset: seconds_to_nanos(input.time_offset),
+            pending: None,
+        };
+        source.advance()?;
+        sources.push(source);
+    }
+
+    let mut heap = BinaryHeap::new();
+    for (index, source) in sources.iter().enumerate() {
+        if let Some(message) = &source.pending {
+            heap.push(Reverse((message.timestamp, index)));
+        }
+    }
+
+    let mut writer = LogFileWriter::new(output_path)?;
+    let mut summary = MergeSummary::default();
+    let deduplicate_window = deduplicate_window.map(seconds_to_nanos);
+    // Messages written within the deduplication window before the current message, oldest first
+    let mut recent_messages: VecDeque<RawMessage> = VecDeque::new();
+
+    while let Some(Reverse((_, index))) = heap.pop() {
+        let source = &mut sources[index];
+        let message = match source.pending.take() {
+            Some(m) => m,
+            None => continue,
+        };
+        source.advance()?;
+        if let Some(next) = &source.pending {
+            heap.push(Reverse((next.timestamp, index)));
+        }
+
+        if let Some(window) = deduplicate_window {
+            let window_start = message.timestamp.saturating_sub(window);
+            while recent_messages.front().is_some_and(|m| m.timestamp < window_start) {
+                recent_messages.pop_front();
+            }
+            let is_duplicate = recent_messages
+                .iter()
+                .any(|m| m.message_type == message.message_type && m.data == message.data);
+            if is_duplicate {
+                summary.duplicates += 1;
+                continue;
+            }
+        }
+
+        writer.write_raw_message(&message)?;
+        summary.written += 1;
+
+        if deduplicate_window.is_some() {
+            recent_messages.push_back(message);
+        }
+    }
+
+    writer.flush()?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{at, message, read_raw_log, write_log};
+    use crate::{LogMessage, MessageBody};
+
+    fn blank_log(times: &[f64], payload_offset: u8) -> Vec<LogMessage> {
+        times
+            .iter()
+            .enumerate()
+            .map(|(i, time)| message(*time, MessageBody::Blank(vec![i as u8 + payload_offset])))
+            .collect()
+    }
+
+    #[test]
+    fn messages_are_interleaved_in_timestamp_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_log(dir.path(), "a.log", &blank_log(&[0.0, 0.2, 0.4], 0));
+        let b = write_log(dir.path(), "b.log", &blank_log(&[0.1, 0.2, 0.5], 10));
+        let output = dir.path().join("merged.log");
+
+        let summary = merge_logs(&[MergeInput::new(&a), MergeInput::new(&b)], &output, None).unwrap();
+
+        let merged = read_raw_log(&output);
+        assert_eq!(summary.written, 6);
+        let timestamps: Vec<i64> = merged.iter().map(|m| m.timestamp).collect();
+        assert_eq!(timestamps, [at(0.0), at(0.1), at(0.2), at(0.2), at(0.4), at(0.5)]);
+        // Equal timestamps keep the order of the inputs
+        assert_eq!(merged[2].data, [1]);
+        assert_eq!(merged[3].data, [11]);
+    }
+
+    #[test]
+    fn time_offsets_shift_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_log(dir.path(), "a.log", &blank_log(&[0.0, 1.0], 0));
+        let b = write_log(dir.path(), "b.log", &blank_log(&[0.0, 1.0], 10));
+        let output = dir.path().join("merged.log");
+        let inputs = [
+            MergeInput::new(&a),
+            MergeInput {
+                path: b,
+                time_offset: 0.5,
+            },
+        ];
+
+        merge_logs(&inputs, &output, None).unwrap();
+
+        let payloads: Vec<u8> = read_raw_log(&output).iter().map(|m| m.data[0]).collect();
+        assert_eq!(payloads, [0, 10, 1, 11]);
+    }
+
+    #[test]
+    fn duplicates_with_shifted_timestamps_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let times: Vec<f64> = (0..20).map(|i| i as f64 * 0.01).collect();
+        let shifted: Vec<f64> = times.iter().map(|t| t + 0.003).collect();
+        let a = write_log(dir.path(), "a.log", &blank_log(&times, 0));
+        // The second recorder received the same packets 3 ms later, plus one packet the first one missed
+        let mut b_messages = blank_log(&shifted, 0);
+        b_messages.push(message(0.5, MessageBody::Blank(vec![200])));
+        let b = write_log(dir.path(), "b.log", &b_messages);
+        let inputs = [MergeInput::new(&a), MergeInput::new(&b)];
+
+        let output = dir.path().join("merged.log");
+        let summary = merge_logs(&inputs, &output, Some(DEFAULT_DEDUPLICATE_WINDOW)).unwrap();
+        assert_eq!(summary.written, 21);
+        assert_eq!(summary.duplicates, 20);
+        let merged = read_raw_log(&output);
+        assert!(merged[..20].iter().zip(&times).all(|(m, t)| m.timestamp == at(*t)));
+
+        let summary = merge_logs(&inputs, &output, None).unwrap();
+        assert_eq!(summary.written, 41);
+        assert_eq!(summary.duplicates, 0);
+    }
+
+    #[test]
+    fn identical_payloads_outside_the_window_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write_log(dir.path(), "a.log", &[message(0.0, MessageBody::Blank(vec![1]))]);
+        let b = write_log(
+            dir.path(),
+            "b.log",
+            &[
+                message(0.04, MessageBody::Blank(vec![1])),
+                message(0.06, MessageBody::Blank(vec![1])),
+                message(0.2, MessageBody::Unkown(vec![1])),
+            ],
+        );
+        let output = dir.path().join("merged.log");
+
+        let summary = merge_logs(&[MergeInput::new(&a), MergeInput::new(&b)], &output, Some(0.05)).unwrap();
+
+        // The message at 0.06 s is more than 50 ms after the last kept copy, and the one at 0.2 s has another type
+        assert_eq!(summary.duplicates, 1);
+        let timestamps: Vec<i64> = read_raw_log(&output).iter().map(|m| m.timestamp).collect();
+        assert_eq!(timestamps, [at(0.0), at(0.06), at(0.2)]);
+    }
+}
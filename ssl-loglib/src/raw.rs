@@ -1,15 +1,85 @@
-use std::io::{Error, ErrorKind, Read};
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
 use std::mem::size_of;
+use std::str::FromStr;
 
+/// Type IDs 0 to 6 belong to the standard message types
+const STANDARD_TYPE_IDS: std::ops::RangeInclusive<i32> = 0..=6;
+
+/// A message type ID outside the standard set, such as a private message stream recorded by a team
+///
+/// Standard type IDs are rejected, so every [`MessageType`] has exactly one representation and is read back from a
+/// log as the same value it was written as.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "i32", into = "i32"))]
+pub struct CustomTypeId(i32);
+
+impl CustomTypeId {
+    /// Returns `None` if `id` is the type ID of a standard message type
+    pub fn new(id: i32) -> Option<Self> {
+        (!STANDARD_TYPE_IDS.contains(&id)).then_some(CustomTypeId(id))
+    }
+
+    pub fn get(self) -> i32 {
+        self.0
+    }
+}
+
+impl From<CustomTypeId> for i32 {
+    fn from(id: CustomTypeId) -> Self {
+        id.0
+    }
+}
+
+impl TryFrom<i32> for CustomTypeId {
+    type Error = Error;
+
+    fn try_from(id: i32) -> Result<Self, Self::Error> {
+        CustomTypeId::new(id).ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Type ID {} is a standard message type", id),
+        ))
+    }
+}
+
+impl fmt::Display for CustomTypeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The type of a log message
+///
+/// The variants have no discriminants because [`MessageType::Custom`] carries its own ID, so `as i32` casts do not
+/// compile. Use `i32::from` to get the type ID written to log files.
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub enum MessageType {
-    Blank = 0,
-    Unkown = 1,
-    Vision2010 = 2,
-    Refbox2013 = 3,
-    Vision2014 = 4,
-    VisionTracker2020 = 5,
-    Index2021 = 6,
+    Blank,
+    Unkown,
+    Vision2010,
+    Refbox2013,
+    Vision2014,
+    VisionTracker2020,
+    Index2021,
+    /// Any other type ID, such as a private message stream recorded by a team
+    Custom(CustomTypeId),
+}
+
+impl From<MessageType> for i32 {
+    fn from(message_type: MessageType) -> Self {
+        match message_type {
+            MessageType::Blank => 0,
+            MessageType::Unkown => 1,
+            MessageType::Vision2010 => 2,
+            MessageType::Refbox2013 => 3,
+            MessageType::Vision2014 => 4,
+            MessageType::VisionTracker2020 => 5,
+            MessageType::Index2021 => 6,
+            MessageType::Custom(id) => id.get(),
+        }
+    }
 }
 
 impl TryFrom<i32> for MessageType {
@@ -24,21 +94,44 @@ impl TryFrom<i32> for MessageType {
             4 => Ok(MessageType::Vision2014),
             5 => Ok(MessageType::VisionTracker2020),
             6 => Ok(MessageType::Index2021),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("Unsupported message type: {}", value),
-            )),
+            _ => Ok(MessageType::Custom(CustomTypeId(value))),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl FromStr for MessageType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Blank" => Ok(MessageType::Blank),
+            "Unkown" | "Unknown" => Ok(MessageType::Unkown),
+            "Vision2010" => Ok(MessageType::Vision2010),
+            "Refbox2013" => Ok(MessageType::Refbox2013),
+            "Vision2014" => Ok(MessageType::Vision2014),
+            "VisionTracker2020" => Ok(MessageType::VisionTracker2020),
+            "Index2021" => Ok(MessageType::Index2021),
+            _ => match s.parse::<i32>() {
+                Ok(id) => MessageType::try_from(id),
+                Err(_) => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown message type: {}", s),
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RawMessage {
     pub timestamp: i64,
     pub message_type: MessageType,
     pub data: Vec<u8>,
 }
 
+/// Size of the timestamp, type, and size fields before each message payload
+pub const RECORD_HEADER_SIZE: u64 = 16;
+
 pub fn extract_next_raw_message<R: Read>(reader: &mut R) -> Result<RawMessage, std::io::Error> {
     let mut timestamp_buffer = [0; size_of::<i64>()];
     reader.read_exact(&mut timestamp_buffer)?;
@@ -49,7 +142,11 @@ pub fn extract_next_raw_message<R: Read>(reader: &mut R) -> Result<RawMessage, s
     let mut message_size_buffer = [0; size_of::<i32>()];
     reader.read_exact(&mut message_size_buffer)?;
     let message_size = i32::from_be_bytes(message_size_buffer);
-    let mut data_buffer = vec![0; message_size as usize];
+    let message_size = usize::try_from(message_size).map_err(|_| Error::new(
+        ErrorKind::InvalidData,
+        format!("Invalid message size: {}", message_size),
+    ))?;
+    let mut data_buffer = vec![0; message_size];
     reader.read_exact(&mut data_buffer)?;
     Ok(RawMessage {
         timestamp,
@@ -57,3 +154,15 @@ pub fn extract_next_raw_message<R: Read>(reader: &mut R) -> Result<RawMessage, s
         data: data_buffer,
     })
 }
+
+pub fn write_raw_message<W: Write>(writer: &mut W, message: &RawMessage) -> Result<(), std::io::Error> {
+    let message_size = i32::try_from(message.data.len()).map_err(|_| Error::new(
+        ErrorKind::InvalidInput,
+        format!("Message too large to write: {} bytes", message.data.len()),
+    ))?;
+    writer.write_all(&message.timestamp.to_be_bytes())?;
+    writer.write_all(&i32::from(message.message_type).to_be_bytes())?;
+    writer.write_all(&message_size.to_be_bytes())?;
+    writer.write_all(&message.data)?;
+    Ok(())
+}
@@ -0,0 +1,278 @@
+//! Builders for small synthetic logs used by the unit tests
+//!
+//! Other crates in the workspace use them through the `test-util` feature.
+
+use crate::index::IndexMessage;
+use crate::log_file_reader::{HEADER_SIZE, LogFileReader};
+use crate::log_file_writer::LogFileWriter;
+use crate::protos::refbox::ssl_gc_referee_message::Referee;
+use crate::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage, TeamInfo};
+use crate::protos::vision::messages_robocup_ssl_detection::{SSL_DetectionBall, SSL_DetectionFrame, SSL_DetectionRobot};
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::{
+    KickedBall, RobotId, TeamColor, TrackedBall, TrackedFrame, TrackedRobot, Vector2, Vector3,
+};
+use crate::protos::vision::messages_robocup_ssl_geometry::{SSL_GeometryData, SSL_GeometryFieldSize};
+use crate::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
+use crate::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use crate::raw::{MessageType, RECORD_HEADER_SIZE, RawMessage};
+use crate::{LogMessage, MessageBody};
+use chrono::{TimeZone, Utc};
+use protobuf::{EnumOrUnknown, MessageField};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Receive time of the first message in test logs, in nanoseconds
+pub const START: i64 = 1_700_000_000_000_000_000;
+
+/// Receive timestamp in nanoseconds of a message `seconds` after [`START`]
+pub fn at(seconds: f64) -> i64 {
+    START + (seconds * 1.0e9).round() as i64
+}
+
+pub fn message(seconds: f64, body: MessageBody) -> LogMessage {
+    LogMessage {
+        timestamp: Utc.timestamp_nanos(at(seconds)),
+        body,
+    }
+}
+
+fn team_info(name: &str) -> MessageField<TeamInfo> {
+    MessageField::some(TeamInfo {
+        name: Some(name.to_string()),
+        score: Some(0),
+        red_cards: Some(0),
+        yellow_cards: Some(0),
+        timeouts: Some(4),
+        timeout_time: Some(300_000_000),
+        goalkeeper: Some(0),
+        ..Default::default()
+    })
+}
+
+/// A referee message sent `seconds` after [`START`] on a sender clock that matches the receive clock
+pub fn referee(seconds: f64, stage: Stage, command: Command, command_counter: u32) -> Referee {
+    Referee {
+        packet_timestamp: Some((at(seconds) / 1000) as u64),
+        stage: Some(EnumOrUnknown::new(stage)),
+        command: Some(EnumOrUnknown::new(command)),
+        command_counter: Some(command_counter),
+        command_timestamp: Some((at(seconds) / 1000) as u64),
+        yellow: team_info("Yellow"),
+        blue: team_info("Blue"),
+        ..Default::default()
+    }
+}
+
+/// A vision packet with only a division A field size
+pub fn geometry() -> SSL_WrapperPacket {
+    SSL_WrapperPacket {
+        geometry: MessageField::some(SSL_GeometryData {
+            field: MessageField::some(SSL_GeometryFieldSize {
+                field_length: Some(12000),
+                field_width: Some(9000),
+                goal_width: Some(1800),
+                goal_depth: Some(180),
+                boundary_width: Some(300),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// A detection frame with the ball and yellow robots at the given positions in meters
+pub fn detection(
+    camera_id: u32,
+    frame_number: u32,
+    t_capture: f64,
+    balls: &[[f64; 2]],
+    yellow: &[(u32, [f64; 2])],
+) -> SSL_WrapperPacket {
+    let frame = SSL_DetectionFrame {
+        frame_number: Some(frame_number),
+        t_capture: Some(t_capture),
+        t_sent: Some(t_capture + 0.005),
+        camera_id: Some(camera_id),
+        balls: balls
+            .iter()
+            .map(|pos| SSL_DetectionBall {
+                confidence: Some(1.0),
+                x: Some((pos[0] * 1000.0) as f32),
+                y: Some((pos[1] * 1000.0) as f32),
+                pixel_x: Some(0.0),
+                pixel_y: Some(0.0),
+                ..Default::default()
+            })
+            .collect(),
+        robots_yellow: yellow
+            .iter()
+            .map(|(id, pos)| SSL_DetectionRobot {
+                confidence: Some(1.0),
+                robot_id: Some(*id),
+                x: Some((pos[0] * 1000.0) as f32),
+                y: Some((pos[1] * 1000.0) as f32),
+                orientation: Some(0.0),
+                pixel_x: Some(0.0),
+                pixel_y: Some(0.0),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    SSL_WrapperPacket {
+        detection: MessageField::some(frame),
+        ..Default::default()
+    }
+}
+
+fn robot_id(team: TeamColor, id: u32) -> MessageField<RobotId> {
+    MessageField::some(RobotId {
+        id: Some(id),
+        team_color: Some(EnumOrUnknown::new(team)),
+        ..Default::default()
+    })
+}
+
+fn vector2(pos: [f64; 2]) -> MessageField<Vector2> {
+    MessageField::some(Vector2 {
+        x: Some(pos[0] as f32),
+        y: Some(pos[1] as f32),
+        ..Default::default()
+    })
+}
+
+fn vector3(pos: [f64; 3]) -> MessageField<Vector3> {
+    MessageField::some(Vector3 {
+        x: Some(pos[0] as f32),
+        y: Some(pos[1] as f32),
+        z: Some(pos[2] as f32),
+        ..Default::default()
+    })
+}
+
+/// A tracker frame from `source` with the ball and robots at the given positions in meters
+pub fn tracked(
+    source: &str,
+    frame_number: u32,
+    timestamp: f64,
+    ball: Option<[f64; 2]>,
+    robots: &[(TeamColor, u32, [f64; 2])],
+) -> TrackerWrapperPacket {
+    let frame = TrackedFrame {
+        frame_number: Some(frame_number),
+        timestamp: Some(timestamp),
+        balls: ball
+            .iter()
+            .map(|pos| TrackedBall {
+                pos: vector3([pos[0], pos[1], 0.0]),
+                ..Default::default()
+            })
+            .collect(),
+        robots: robots
+            .iter()
+            .map(|(team, id, pos)| TrackedRobot {
+                robot_id: robot_id(*team, *id),
+                pos: vector2(*pos),
+                orientation: Some(0.0),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    TrackerWrapperPacket {
+        uuid: Some(format!("{}-uuid", source)),
+        source_name: Some(source.to_string()),
+        tracked_frame: MessageField::some(frame),
+        ..Default::default()
+    }
+}
+
+/// Adds a kick by `kicker` from `pos` with velocity `vel` that started at tracker time `start_timestamp`
+pub fn with_kick(
+    mut packet: TrackerWrapperPacket,
+    kicker: (TeamColor, u32),
+    start_timestamp: f64,
+    pos: [f64; 2],
+    vel: [f64; 3],
+) -> TrackerWrapperPacket {
+    if let Some(frame) = packet.tracked_frame.as_mut() {
+        frame.kicked_ball = MessageField::some(KickedBall {
+            pos: vector2(pos),
+            vel: vector3(vel),
+            start_timestamp: Some(start_timestamp),
+            robot_id: robot_id(kicker.0, kicker.1),
+            ..Default::default()
+        });
+    }
+    packet
+}
+
+/// Sets the orientation in radians and the tracked velocity of every robot in the frame
+pub fn with_robot_motion(
+    mut packet: TrackerWrapperPacket,
+    orientation: f64,
+    vel: Option<[f64; 2]>,
+    vel_angular: Option<f64>,
+) -> TrackerWrapperPacket {
+    if let Some(frame) = packet.tracked_frame.as_mut() {
+        for robot in &mut frame.robots {
+            robot.orientation = Some(orientation as f32);
+            robot.vel = vel.map(vector2).unwrap_or_default();
+            robot.vel_angular = vel_angular.map(|v| v as f32);
+        }
+    }
+    packet
+}
+
+/// Writes `messages` to a new log called `name` in `dir`
+pub fn write_log(dir: &Path, name: &str, messages: &[LogMessage]) -> PathBuf {
+    let path = dir.join(name);
+    let mut writer = LogFileWriter::new(&path).unwrap();
+    for message in messages {
+        writer.write_message(message).unwrap();
+    }
+    writer.flush().unwrap();
+    path
+}
+
+/// Writes `messages` to a new log called `name` in `dir`, followed by an index if `indexed` is set
+pub fn write_raw_log(dir: &Path, name: &str, messages: &[RawMessage], indexed: bool) -> PathBuf {
+    let path = dir.join(name);
+    let mut writer = LogFileWriter::new(&path).unwrap();
+    let mut offsets = Vec::new();
+    let mut offset = HEADER_SIZE as i64;
+    for message in messages {
+        writer.write_raw_message(message).unwrap();
+        offsets.push(offset);
+        offset += RECORD_HEADER_SIZE as i64 + message.data.len() as i64;
+    }
+    if indexed {
+        let mut index = IndexMessage {
+            offsets,
+            index_offset: 0,
+        };
+        index.index_offset = RECORD_HEADER_SIZE as i64 + index.to_bytes().len() as i64;
+        let message = RawMessage {
+            timestamp: messages.last().map_or(START, |m| m.timestamp),
+            message_type: MessageType::Index2021,
+            data: index.to_bytes(),
+        };
+        writer.write_raw_message(&message).unwrap();
+    }
+    writer.flush().unwrap();
+    path
+}
+
+/// Reads all messages of the log at `path` without decoding them
+pub fn read_raw_log(path: &Path) -> Vec<RawMessage> {
+    let mut reader = LogFileReader::new(path).unwrap();
+    let mut messages = Vec::new();
+    loop {
+        match reader.get_next_raw_message() {
+            Ok(message) => messages.push(message),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return messages,
+            Err(e) => panic!("Failed to read test log: {}", e),
+        }
+    }
+}
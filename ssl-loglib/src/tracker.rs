@@ -0,0 +1,193 @@
+use crate::log_file_reader::LogFileReader;
+use crate::protos::vision::messages_robocup_ssl_detection_tracked::{RobotId, TeamColor};
+use crate::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use crate::raw::MessageType;
+use crate::{LogMessage, MessageBody};
+use chrono::{DateTime, TimeZone, Utc};
+use protobuf::{Enum, Message};
+use std::cmp::Ordering;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// Name used to refer to the tracker that sent `packet`, which is its source name or its UUID if it has no name
+pub fn source_label(packet: &TrackerWrapperPacket) -> &str {
+    packet.source_name.as_deref().unwrap_or(packet.uuid())
+}
+
+/// Returns true if `packet` was sent by the tracker with the given source name or UUID
+pub fn is_from_source(packet: &TrackerWrapperPacket, source: &str) -> bool {
+    packet.source_name.as_deref() == Some(source) || packet.uuid() == source
+}
+
+/// A tracker that sent messages in a log
+#[derive(Clone, Debug)]
+pub struct TrackerSource {
+    pub uuid: String,
+    pub name: Option<String>,
+    /// Number of tracker messages from this source
+    pub messages: usize,
+    pub first_timestamp: DateTime<Utc>,
+    pub last_timestamp: DateTime<Utc>,
+}
+
+impl TrackerSource {
+    /// Source name, or the UUID if the tracker has no name
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.uuid)
+    }
+
+    /// Messages per second between the first and last message from this source
+    pub fn message_rate(&self) -> f64 {
+        let duration = (self.last_timestamp - self.first_timestamp).as_seconds_f64();
+        if duration > 0.0 { (self.messages - 1) as f64 / duration } else { 0.0 }
+    }
+}
+
+/// Returns the tracker sources in the log at `path`, in order of their first message
+///
+/// Sources are told apart by UUID. A tracker that restarts during a log gets a new UUID, so it may be listed more
+/// than once with the same name.
+pub fn list_tracker_sources(path: impl AsRef<Path>) -> io::Result<Vec<TrackerSource>> {
+    let mut reader = LogFileReader::new(path)?;
+    let mut sources: Vec<TrackerSource> = Vec::new();
+    loop {
+        let message = match reader.get_next_raw_message() {
+            Ok(m) => m,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        if message.message_type != MessageType::VisionTracker2020 {
+            continue;
+        }
+        let packet = TrackerWrapperPacket::parse_from_bytes(&message.data)?;
+        let timestamp = Utc.timestamp_nanos(message.timestamp);
+        match sources.iter_mut().find(|s| s.uuid == packet.uuid()) {
+            Some(source) => {
+                source.messages += 1;
+                source.last_timestamp = timestamp;
+            }
+            None => sources.push(TrackerSource {
+                uuid: packet.uuid().to_string(),
+                name: packet.source_name.clone(),
+                messages: 1,
+                first_timestamp: timestamp,
+                last_timestamp: timestamp,
+            }),
+        }
+    }
+    Ok(sources)
+}
+
+/// Iterator over log messages that drops tracker messages from all but one source
+///
+/// Index messages are dropped too, because their offsets no longer match once messages are removed. Created by
+/// [`filter_tracker_source`].
+pub struct SourceFilter<I> {
+    messages: I,
+    source: String,
+}
+
+impl<I: Iterator<Item = LogMessage>> Iterator for SourceFilter<I> {
+    type Item = LogMessage;
+
+    fn next(&mut self) -> Option<LogMessage> {
+        self.messages.find(|message| match &message.body {
+            MessageBody::VisionTracker2020(packet) => is_from_source(packet, &self.source),
+            MessageBody::Index2021(_) => false,
+            _ => true,
+        })
+    }
+}
+
+/// Keeps all messages except index messages and tracker messages from sources other than `source`, given as a
+/// source name or UUID
+pub fn filter_tracker_source<I: IntoIterator<Item = LogMessage>>(
+    messages: I,
+    source: impl Into<String>,
+) -> SourceFilter<I::IntoIter> {
+    SourceFilter {
+        messages: messages.into_iter(),
+        source: source.into(),
+    }
+}
+
+/// Upper case name of a team color, such as YELLOW, as used in the exported tables
+pub fn team_name(team_color: TeamColor) -> &'static str {
+    match team_color {
+        TeamColor::TEAM_COLOR_YELLOW => "YELLOW",
+        TeamColor::TEAM_COLOR_BLUE => "BLUE",
+        TeamColor::TEAM_COLOR_UNKNOWN => "UNKNOWN",
+    }
+}
+
+/// Team and ID of a robot
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RobotKey {
+    pub team: TeamColor,
+    pub id: u32,
+}
+
+impl From<&RobotId> for RobotKey {
+    fn from(robot_id: &RobotId) -> Self {
+        RobotKey {
+            team: robot_id.team_color(),
+            id: robot_id.id(),
+        }
+    }
+}
+
+impl Ord for RobotKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.team.value(), self.id).cmp(&(other.team.value(), other.id))
+    }
+}
+
+impl PartialOrd for RobotKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for RobotKey {
+    /// Formats the robot as team letter and ID, for example Y3 or B10
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let team = match self.team {
+            TeamColor::TEAM_COLOR_YELLOW => "Y",
+            TeamColor::TEAM_COLOR_BLUE => "B",
+            TeamColor::TEAM_COLOR_UNKNOWN => "?",
+        };
+        write!(f, "{}{}", team, self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexMessage;
+    use crate::test_util::{geometry, message, tracked};
+
+    #[test]
+    fn filter_keeps_one_source_and_drops_the_index() {
+        let index = IndexMessage {
+            offsets: vec![16],
+            index_offset: 40,
+        };
+        let messages = vec![
+            message(0.0, MessageBody::Vision2014(geometry())),
+            message(0.1, MessageBody::VisionTracker2020(tracked("A", 1, 0.1, None, &[]))),
+            message(0.2, MessageBody::VisionTracker2020(tracked("B", 1, 0.2, None, &[]))),
+            message(0.3, MessageBody::Index2021(index)),
+        ];
+        // Sources can be selected by UUID as well as by name
+        for source in ["A", "A-uuid"] {
+            let kept: Vec<_> = filter_tracker_source(messages.clone(), source)
+                .map(|m| match &m.body {
+                    MessageBody::VisionTracker2020(packet) => source_label(packet).to_string(),
+                    body => format!("{:?}", body.message_type()),
+                })
+                .collect();
+            assert_eq!(kept, ["Vision2014", "A"]);
+        }
+    }
+}
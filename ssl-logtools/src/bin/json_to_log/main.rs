@@ -0,0 +1,51 @@
+use clap::Parser;
+use ssl_loglib::{LogMessage, MessageBody};
+use ssl_loglib::log_file_writer::LogFileWriter;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+#[derive(Parser)]
+#[command(version)]
+/// Convert JSON Lines, as written by log_to_json, back to a game log
+struct Args {
+    /// Path to a JSON Lines file, or - to read from stdin
+    json_path: String,
+    output_path: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let input: Box<dyn BufRead> = if args.json_path == "-" {
+        Box::new(BufReader::new(std::io::stdin().lock()))
+    } else {
+        Box::new(BufReader::new(File::open(&args.json_path)?))
+    };
+
+    let mut writer = LogFileWriter::new(&args.output_path)?;
+    let mut count = 0;
+    let mut skipped_indexes = 0;
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: LogMessage = serde_json::from_str(&line)
+            .map_err(|e| anyhow::Error::msg(format!("Line {}: {}", line_number + 1, e)))?;
+        // The offsets of an index message point into the log it was exported from, not the log written here
+        if let MessageBody::Index2021(_) = message.body {
+            skipped_indexes += 1;
+            continue;
+        }
+        writer.write_message(&message)?;
+        count += 1;
+    }
+    writer.flush()?;
+
+    println!("Wrote {} messages to {}", count, args.output_path);
+    if skipped_indexes > 0 {
+        println!("Skipped {} index messages, since their offsets do not match the new log", skipped_indexes);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::summary::LogSummary;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashSet;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS files (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    hash TEXT NOT NULL UNIQUE,
+    size INTEGER NOT NULL,
+    yellow_team TEXT,
+    blue_team TEXT,
+    match_type TEXT,
+    division TEXT,
+    start_time TEXT,
+    duration REAL NOT NULL,
+    yellow_score INTEGER,
+    blue_score INTEGER,
+    indexed INTEGER NOT NULL,
+    compressed INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS message_counts (
+    file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    message_type TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (file_id, message_type)
+);
+CREATE TABLE IF NOT EXISTS game_events (
+    file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    log_time REAL NOT NULL,
+    type TEXT NOT NULL,
+    event_id TEXT,
+    origin TEXT NOT NULL,
+    details TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS goals (
+    file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    log_time REAL NOT NULL,
+    team TEXT NOT NULL,
+    yellow_score INTEGER NOT NULL,
+    blue_score INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS game_events_file_id ON game_events(file_id);
+CREATE INDEX IF NOT EXISTS goals_file_id ON goals(file_id);
+";
+
+pub struct Catalog {
+    connection: Connection,
+}
+
+impl Catalog {
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Catalog { connection })
+    }
+
+    /// Returns the path stored for a file with the given content hash, if it has been cataloged
+    pub fn path_for_hash(&self, hash: &str) -> rusqlite::Result<Option<String>> {
+        self.connection
+            .query_row("SELECT path FROM files WHERE hash = ?1", [hash], |row| row.get(0))
+            .optional()
+    }
+
+    /// Moves the entry for a file with the given content hash to `path`, replacing any other entry for `path`
+    pub fn update_path(&mut self, hash: &str, path: &str) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM files WHERE path = ?1 AND hash != ?2", params![path, hash])?;
+        transaction.execute("UPDATE files SET path = ?1 WHERE hash = ?2", params![path, hash])?;
+        transaction.commit()
+    }
+
+    /// Removes the entries for files under `dir` that are not in `found`, returning their paths
+    pub fn remove_missing(&mut self, dir: &Path, found: &HashSet<String>) -> rusqlite::Result<Vec<String>> {
+        let transaction = self.connection.transaction()?;
+        let missing: Vec<String> = transaction
+            .prepare("SELECT path FROM files ORDER BY path")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter(|path| {
+                path.as_ref()
+                    .map_or(true, |path| Path::new(path).starts_with(dir) && !found.contains(path))
+            })
+            .collect::<rusqlite::Result<_>>()?;
+        for path in &missing {
+            transaction.execute("DELETE FROM files WHERE path = ?1", [path])?;
+        }
+        transaction.commit()?;
+        Ok(missing)
+    }
+
+    /// Replaces any entry for `path` with a new entry built from `summary`
+    pub fn insert(&mut self, path: &str, hash: &str, size: i64, summary: &LogSummary) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM files WHERE path = ?1", [path])?;
+        transaction.execute(
+            "INSERT INTO files (path, hash, size, yellow_team, blue_team, match_type, division, start_time, \
+             duration, yellow_score, blue_score, indexed, compressed) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                path,
+                hash,
+                size,
+                summary.yellow_team,
+                summary.blue_team,
+                summary.match_type,
+                summary.division,
+                summary.start_time.map(|t| t.to_rfc3339()),
+                summary.duration,
+                summary.yellow_score,
+                summary.blue_score,
+                summary.indexed,
+                summary.compressed,
+            ],
+        )?;
+        let file_id = transaction.last_insert_rowid();
+
+        for (message_type, count) in &summary.message_counts {
+            transaction.execute(
+                "INSERT INTO message_counts (file_id, message_type, count) VALUES (?1, ?2, ?3)",
+                params![file_id, message_type, count],
+            )?;
+        }
+        for event in &summary.game_events {
+            transaction.execute(
+                "INSERT INTO game_events (file_id, log_time, type, event_id, origin, details) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![file_id, event.log_time, event.event_type, event.event_id, event.origin, event.details],
+            )?;
+        }
+        for goal in &summary.goals {
+            transaction.execute(
+                "INSERT INTO goals (file_id, log_time, team, yellow_score, blue_score) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![file_id, goal.log_time, goal.team, goal.yellow_score, goal.blue_score],
+            )?;
+        }
+
+        transaction.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_files_under_the_directory_are_removed() {
+        let mut catalog = Catalog::open(":memory:").unwrap();
+        let paths = ["/logs/a.log", "/logs/day1/b.log", "/logs2/c.log", "/other/d.log"];
+        for (i, path) in paths.iter().enumerate() {
+            catalog.insert(path, &i.to_string(), 0, &LogSummary::default()).unwrap();
+        }
+
+        let found = HashSet::from(["/logs/a.log".to_string()]);
+        assert_eq!(catalog.remove_missing(Path::new("/logs"), &found).unwrap(), ["/logs/day1/b.log"]);
+        assert_eq!(catalog.path_for_hash("1").unwrap(), None);
+        for hash in ["0", "2", "3"] {
+            assert!(catalog.path_for_hash(hash).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn moving_a_file_replaces_the_entry_at_its_new_path() {
+        let mut catalog = Catalog::open(":memory:").unwrap();
+        catalog.insert("/logs/game.log", "old", 0, &LogSummary::default()).unwrap();
+        catalog.insert("/logs/new/game.log", "new", 0, &LogSummary::default()).unwrap();
+
+        catalog.update_path("new", "/logs/game.log").unwrap();
+        assert_eq!(catalog.path_for_hash("new").unwrap().as_deref(), Some("/logs/game.log"));
+        assert_eq!(catalog.path_for_hash("old").unwrap(), None);
+    }
+}
@@ -0,0 +1,178 @@
+mod database;
+mod summary;
+
+use clap::Parser;
+use database::Catalog;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use summary::summarize_log;
+
+#[derive(Parser)]
+#[command(version)]
+/// Catalog a directory of game logs in a SQLite database
+struct Args {
+    /// Directory to search for .log and .log.gz files
+    log_dir: String,
+    #[arg(short, long, default_value_t = ("catalog.db").to_string())]
+    /// SQLite database to create or update
+    database: String,
+}
+
+fn is_log_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name.ends_with(".log") || name.ends_with(".log.gz")
+}
+
+/// Recursively collects all log files under `dir`
+fn find_log_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_log_files(&path, files)?;
+        } else if is_log_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct UpdateCounts {
+    added: usize,
+    unchanged: usize,
+    removed: usize,
+    failed: usize,
+}
+
+/// Adds new log files under `log_dir` to the catalog and updates or removes the entries of moved and deleted ones
+fn update_catalog(catalog: &mut Catalog, log_dir: &Path) -> anyhow::Result<UpdateCounts> {
+    let mut log_files = Vec::new();
+    find_log_files(log_dir, &mut log_files)?;
+
+    // All files are hashed first so that entries can be matched against the current contents of their paths
+    let mut hashes = HashMap::new();
+    for log_file in log_files {
+        let path = std::fs::canonicalize(&log_file)?;
+        let hash = hash_file(&path)?;
+        hashes.insert(path.to_string_lossy().to_string(), (path, hash));
+    }
+    let mut paths: Vec<&String> = hashes.keys().collect();
+    paths.sort();
+
+    let mut counts = UpdateCounts::default();
+    for path_string in paths {
+        let (path, hash) = &hashes[path_string];
+        match catalog.path_for_hash(hash)? {
+            Some(existing) if existing == *path_string => {
+                counts.unchanged += 1;
+                continue;
+            }
+            Some(existing) => {
+                // Only a file that still has these contents is a duplicate; otherwise the entry belongs to this path
+                let existing_hash = match hashes.get(&existing) {
+                    Some((_, existing_hash)) => Some(existing_hash.clone()),
+                    None => hash_file(Path::new(&existing)).ok(),
+                };
+                if existing_hash.as_ref() == Some(hash) {
+                    println!("Skipping {} (same contents as {})", path_string, existing);
+                } else {
+                    println!("Moved {}", path_string);
+                    catalog.update_path(hash, path_string)?;
+                }
+                counts.unchanged += 1;
+                continue;
+            }
+            None => {}
+        }
+
+        match summarize_log(path) {
+            Ok(summary) => {
+                catalog.insert(path_string, hash, i64::try_from(path.metadata()?.len())?, &summary)?;
+                println!("Added {}", path_string);
+                counts.added += 1;
+            }
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path_string, e);
+                counts.failed += 1;
+            }
+        }
+    }
+
+    // Files that were deleted or moved out of the directory since the last run
+    let found = hashes.into_keys().collect();
+    let removed = catalog.remove_missing(log_dir, &found)?;
+    for path in &removed {
+        println!("Removed {}", path);
+    }
+    counts.removed = removed.len();
+    Ok(counts)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let log_dir = std::fs::canonicalize(&args.log_dir)?;
+    let mut catalog = Catalog::open(&args.database)?;
+    let counts = update_catalog(&mut catalog, &log_dir)?;
+
+    println!(
+        "{} added, {} unchanged, {} removed, {} failed",
+        counts.added, counts.unchanged, counts.removed, counts.failed
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssl_loglib::MessageBody;
+    use ssl_loglib::test_util::{message, write_log};
+
+    fn catalog_path(catalog: &Catalog, path: &Path) -> Option<String> {
+        catalog.path_for_hash(&hash_file(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn swapped_files_are_not_skipped_as_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = std::fs::canonicalize(dir.path()).unwrap();
+        let a = write_log(&log_dir, "a.log", &[message(0.0, MessageBody::Blank(vec![1]))]);
+        let b = write_log(&log_dir, "b.log", &[message(0.0, MessageBody::Blank(vec![2]))]);
+        let mut catalog = Catalog::open(":memory:").unwrap();
+        assert_eq!(update_catalog(&mut catalog, &log_dir).unwrap().added, 2);
+
+        let swap = log_dir.join("swap.log");
+        std::fs::rename(&a, &swap).unwrap();
+        std::fs::rename(&b, &a).unwrap();
+        std::fs::rename(&swap, &b).unwrap();
+        update_catalog(&mut catalog, &log_dir).unwrap();
+
+        for path in [&a, &b] {
+            assert_eq!(catalog_path(&catalog, path).as_deref(), Some(path.to_str().unwrap()));
+        }
+    }
+
+    #[test]
+    fn copies_of_a_cataloged_file_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = std::fs::canonicalize(dir.path()).unwrap();
+        let a = write_log(&log_dir, "a.log", &[message(0.0, MessageBody::Blank(vec![1]))]);
+        let mut catalog = Catalog::open(":memory:").unwrap();
+        update_catalog(&mut catalog, &log_dir).unwrap();
+
+        std::fs::copy(&a, log_dir.join("copy.log")).unwrap();
+        let counts = update_catalog(&mut catalog, &log_dir).unwrap();
+        assert_eq!(counts, UpdateCounts { unchanged: 2, ..Default::default() });
+        assert_eq!(catalog_path(&catalog, &a).as_deref(), Some(a.to_str().unwrap()));
+    }
+}
@@ -0,0 +1,264 @@
+use chrono::{DateTime, TimeZone, Utc};
+use flate2::read::GzDecoder;
+use protobuf::Message;
+use protobuf_json_mapping::PrintOptions;
+use ssl_loglib::log_file_reader::verify_log_preamble;
+use ssl_loglib::protos::refbox::ssl_gc_common::Division;
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::Referee;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper_legacy::SSL_WrapperPacket as SSL_WrapperPacket_Legacy;
+use ssl_loglib::raw::{MessageType, extract_next_raw_message};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// Division A fields are 12m long and division B fields are 9m long
+const DIV_A_MIN_FIELD_LENGTH: i32 = 10500;
+
+pub struct GameEventRecord {
+    /// Seconds from the beginning of the log
+    pub log_time: f64,
+    pub event_type: String,
+    pub event_id: Option<String>,
+    pub origin: String,
+    /// Full game event as JSON
+    pub details: String,
+}
+
+pub struct GoalRecord {
+    /// Seconds from the beginning of the log
+    pub log_time: f64,
+    pub team: &'static str,
+    pub yellow_score: u32,
+    pub blue_score: u32,
+}
+
+#[derive(Default)]
+pub struct LogSummary {
+    pub yellow_team: Option<String>,
+    pub blue_team: Option<String>,
+    pub match_type: Option<String>,
+    pub division: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    /// Seconds between the first and last message
+    pub duration: f64,
+    pub yellow_score: Option<u32>,
+    pub blue_score: Option<u32>,
+    pub indexed: bool,
+    pub compressed: bool,
+    pub message_counts: BTreeMap<String, i64>,
+    pub game_events: Vec<GameEventRecord>,
+    pub goals: Vec<GoalRecord>,
+}
+
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "gz")
+}
+
+fn division_from_field_length(field_length: i32) -> String {
+    let division = if field_length >= DIV_A_MIN_FIELD_LENGTH {
+        Division::DIV_A
+    } else {
+        Division::DIV_B
+    };
+    format!("{:?}", division)
+}
+
+/// Reads a plain or gzip compressed log file and collects match information from it
+pub fn summarize_log(path: &Path) -> io::Result<LogSummary> {
+    let file = BufReader::new(File::open(path)?);
+    let compressed = is_compressed(path);
+    let mut reader: Box<dyn Read> = if compressed {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(file)
+    };
+    verify_log_preamble(&mut reader)?;
+
+    let mut summary = LogSummary {
+        compressed,
+        ..Default::default()
+    };
+    let mut first_timestamp = None;
+    let mut last_timestamp = 0;
+    let mut seen_game_events = HashSet::new();
+    let mut max_scores: Option<(u32, u32)> = None;
+    let json_options = PrintOptions {
+        proto_field_name: true,
+        ..Default::default()
+    };
+
+    loop {
+        let message = match extract_next_raw_message(&mut reader) {
+            Ok(m) => m,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        *summary
+            .message_counts
+            .entry(format!("{:?}", message.message_type))
+            .or_default() += 1;
+        if message.message_type == MessageType::Index2021 {
+            summary.indexed = true;
+            continue;
+        }
+        let start = *first_timestamp.get_or_insert(message.timestamp);
+        last_timestamp = message.timestamp;
+        let log_time = (message.timestamp - start) as f64 / 1.0e9;
+
+        match message.message_type {
+            MessageType::Refbox2013 => {
+                let referee = Referee::parse_from_bytes(&message.data)?;
+                if !referee.yellow.name().is_empty() {
+                    summary.yellow_team = Some(referee.yellow.name().to_string());
+                }
+                if !referee.blue.name().is_empty() {
+                    summary.blue_team = Some(referee.blue.name().to_string());
+                }
+                if referee.match_type.is_some() {
+                    summary.match_type = Some(format!("{:?}", referee.match_type()));
+                }
+
+                // Scores corrected by the referee go down and back up, so only new highs are goals
+                let scores = (referee.yellow.score(), referee.blue.score());
+                if let Some((yellow, blue)) = max_scores {
+                    for (team, highest, current) in
+                        [("YELLOW", yellow, scores.0), ("BLUE", blue, scores.1)]
+                    {
+                        if current > highest {
+                            summary.goals.push(GoalRecord {
+                                log_time,
+                                team,
+                                yellow_score: scores.0,
+                                blue_score: scores.1,
+                            });
+                        }
+                    }
+                }
+                let (yellow, blue) = max_scores.unwrap_or(scores);
+                max_scores = Some((yellow.max(scores.0), blue.max(scores.1)));
+                summary.yellow_score = Some(scores.0);
+                summary.blue_score = Some(scores.1);
+
+                for game_event in &referee.game_events {
+                    // Game events are repeated in every referee message until they are cleared
+                    let key = if game_event.id().is_empty() {
+                        game_event.write_to_bytes()?
+                    } else {
+                        game_event.id().as_bytes().to_vec()
+                    };
+                    if !seen_game_events.insert(key) {
+                        continue;
+                    }
+                    summary.game_events.push(GameEventRecord {
+                        log_time,
+                        event_type: format!("{:?}", game_event.type_()),
+                        event_id: game_event.id.clone(),
+                        origin: game_event.origin.join(","),
+                        details: protobuf_json_mapping::print_to_string_with_options(game_event, &json_options)
+                            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?,
+                    });
+                }
+            }
+            MessageType::Vision2014 if summary.division.is_none() => {
+                let packet = SSL_WrapperPacket::parse_from_bytes(&message.data)?;
+                if let Some(field_length) = packet.geometry.field.as_ref().and_then(|f| f.field_length) {
+                    summary.division = Some(division_from_field_length(field_length));
+                }
+            }
+            MessageType::Vision2010 if summary.division.is_none() => {
+                let packet = SSL_WrapperPacket_Legacy::parse_from_bytes(&message.data)?;
+                if let Some(field_length) = packet.geometry.field.as_ref().and_then(|f| f.field_length) {
+                    summary.division = Some(division_from_field_length(field_length));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(start) = first_timestamp {
+        summary.start_time = Some(Utc.timestamp_nanos(start));
+        summary.duration = (last_timestamp - start) as f64 / 1.0e9;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use protobuf::EnumOrUnknown;
+    use ssl_loglib::protos::refbox::ssl_gc_game_event::GameEvent;
+    use ssl_loglib::protos::refbox::ssl_gc_game_event::game_event::Type;
+    use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use ssl_loglib::test_util::{geometry, message, referee, write_log};
+    use ssl_loglib::{LogMessage, MessageBody};
+
+    /// A referee message with the given scores and a game event with `event_id`, if set
+    fn scores(seconds: f64, yellow: u32, blue: u32, event_id: Option<&str>) -> LogMessage {
+        let mut referee = referee(seconds, Stage::NORMAL_FIRST_HALF, Command::STOP, 1);
+        referee.yellow.mut_or_insert_default().score = Some(yellow);
+        referee.blue.mut_or_insert_default().score = Some(blue);
+        if let Some(id) = event_id {
+            referee.game_events.push(GameEvent {
+                id: Some(id.to_string()),
+                type_: Some(EnumOrUnknown::new(Type::POSSIBLE_GOAL)),
+                origin: vec!["GC".to_string()],
+                ..Default::default()
+            });
+        }
+        message(seconds, MessageBody::Refbox2013(referee))
+    }
+
+    /// Yellow scores, the goal is taken back and given again, and blue scores
+    fn game() -> Vec<LogMessage> {
+        vec![
+            message(0.0, MessageBody::Vision2014(geometry())),
+            scores(1.0, 0, 0, None),
+            scores(2.0, 1, 0, Some("goal")),
+            scores(3.0, 0, 0, Some("goal")),
+            scores(4.0, 1, 0, None),
+            scores(5.0, 1, 1, None),
+        ]
+    }
+
+    #[test]
+    fn log_is_summarized() {
+        let dir = tempfile::tempdir().unwrap();
+        let summary = summarize_log(&write_log(dir.path(), "game.log", &game())).unwrap();
+        assert_eq!(summary.yellow_team.as_deref(), Some("Yellow"));
+        assert_eq!(summary.blue_team.as_deref(), Some("Blue"));
+        assert_eq!(summary.division.as_deref(), Some("DIV_A"));
+        assert_eq!(summary.duration, 5.0);
+        assert_eq!((summary.yellow_score, summary.blue_score), (Some(1), Some(1)));
+        assert!(!summary.indexed && !summary.compressed);
+        assert_eq!(summary.message_counts["Refbox2013"], 5);
+        assert_eq!(summary.message_counts["Vision2014"], 1);
+        // The game event is repeated in two messages but recorded once
+        assert_eq!(summary.game_events.len(), 1);
+        assert_eq!(summary.game_events[0].event_type, "POSSIBLE_GOAL");
+        assert_eq!(summary.game_events[0].log_time, 2.0);
+
+        // The corrected goal is not counted twice
+        let goals: Vec<_> = summary.goals.iter().map(|g| (g.log_time, g.team, g.yellow_score, g.blue_score)).collect();
+        assert_eq!(goals, [(2.0, "YELLOW", 1, 0), (5.0, "BLUE", 1, 1)]);
+    }
+
+    #[test]
+    fn compressed_log_is_summarized() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = std::fs::read(write_log(dir.path(), "game.log", &game())).unwrap();
+        let path = dir.path().join("game.log.gz");
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        io::copy(&mut data.as_slice(), &mut encoder).unwrap();
+        encoder.finish().unwrap();
+
+        let summary = summarize_log(&path).unwrap();
+        assert!(summary.compressed);
+        assert_eq!(summary.goals.len(), 2);
+        assert_eq!(summary.message_counts["Refbox2013"], 5);
+    }
+}
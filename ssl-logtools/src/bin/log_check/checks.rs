@@ -0,0 +1,546 @@
+use ssl_loglib::index::{INDEX_MARKER, IndexMessage};
+use ssl_loglib::log_file_reader::HEADER_SIZE;
+use ssl_loglib::protos::vision::messages_robocup_ssl_detection::SSL_DetectionFrame;
+use ssl_loglib::raw::{MessageType, RECORD_HEADER_SIZE, RawMessage, extract_next_raw_message};
+use ssl_loglib::{LogMessage, MessageBody};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in a log, with one detail line per occurrence
+pub struct Finding {
+    pub severity: Severity,
+    pub summary: String,
+    pub details: Vec<String>,
+}
+
+impl Finding {
+    fn new(severity: Severity, summary: impl Into<String>) -> Self {
+        Finding {
+            severity,
+            summary: summary.into(),
+            details: Vec::new(),
+        }
+    }
+
+    fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+pub struct CheckOptions {
+    /// Longest allowed time between two messages of the same type in nanoseconds
+    pub max_gap: i64,
+}
+
+#[derive(Default)]
+struct CameraState {
+    last_frame_number: Option<u32>,
+    discontinuities: Vec<String>,
+    missing_frames: u64,
+}
+
+/// Collects problems while reading through a log one message at a time
+pub struct LogChecker {
+    options: CheckOptions,
+    first_timestamp: Option<i64>,
+    latest_timestamp: i64,
+    timestamp_regressions: Vec<String>,
+    last_by_type: HashMap<MessageType, i64>,
+    gaps: BTreeMap<String, Vec<(i64, i64)>>,
+    cameras: BTreeMap<u32, CameraState>,
+    has_geometry: bool,
+    last_command_counter: Option<u32>,
+    command_counter_regressions: Vec<String>,
+    decode_errors: Vec<String>,
+    /// Byte offsets of every record except the index
+    record_offsets: Vec<u64>,
+    index: Option<(u64, IndexMessage)>,
+    records_after_index: usize,
+}
+
+fn format_seconds(nanos: i64) -> String {
+    format!("{:.3}s", nanos as f64 / 1.0e9)
+}
+
+impl LogChecker {
+    pub fn new(options: CheckOptions) -> Self {
+        LogChecker {
+            options,
+            first_timestamp: None,
+            latest_timestamp: i64::MIN,
+            timestamp_regressions: Vec::new(),
+            last_by_type: HashMap::new(),
+            gaps: BTreeMap::new(),
+            cameras: BTreeMap::new(),
+            has_geometry: false,
+            last_command_counter: None,
+            command_counter_regressions: Vec::new(),
+            decode_errors: Vec::new(),
+            record_offsets: Vec::new(),
+            index: None,
+            records_after_index: 0,
+        }
+    }
+
+    /// Time of `timestamp` relative to the first message
+    fn log_time(&self, timestamp: i64) -> String {
+        format_seconds(timestamp - self.first_timestamp.unwrap_or(timestamp))
+    }
+
+    /// Checks one record that starts `offset` bytes into the file
+    pub fn check_record(&mut self, offset: u64, raw_message: &RawMessage) {
+        let message = match LogMessage::try_from(raw_message) {
+            Ok(message) => message,
+            Err(e) => {
+                self.decode_errors.push(format!(
+                    "{:?} message at offset {}: {}",
+                    raw_message.message_type, offset, e
+                ));
+                self.add_record_offset(offset);
+                return;
+            }
+        };
+
+        if let MessageBody::Index2021(index) = &message.body
+            && self.index.is_none()
+        {
+            self.index = Some((offset, index.clone()));
+            return;
+        }
+        self.add_record_offset(offset);
+        if message.body.message_type() == MessageType::Index2021 {
+            return;
+        }
+
+        let timestamp = raw_message.timestamp;
+        self.first_timestamp.get_or_insert(timestamp);
+        if timestamp < self.latest_timestamp {
+            self.timestamp_regressions.push(format!(
+                "at {}: {} earlier than the previous message",
+                self.log_time(timestamp),
+                format_seconds(self.latest_timestamp - timestamp)
+            ));
+        }
+        self.latest_timestamp = self.latest_timestamp.max(timestamp);
+
+        if let Some(last) = self.last_by_type.insert(raw_message.message_type, timestamp)
+            && timestamp - last > self.options.max_gap
+        {
+            self.gaps
+                .entry(format!("{:?}", raw_message.message_type))
+                .or_default()
+                .push((last, timestamp - last));
+        }
+
+        match &message.body {
+            MessageBody::Vision2014(packet) => {
+                self.has_geometry |= packet.geometry.is_some();
+                if let Some(detection) = packet.detection.as_ref() {
+                    self.check_detection(timestamp, detection);
+                }
+            }
+            MessageBody::Vision2010(packet) => {
+                self.has_geometry |= packet.geometry.is_some();
+                if let Some(detection) = packet.detection.as_ref() {
+                    self.check_detection(timestamp, detection);
+                }
+            }
+            MessageBody::Refbox2013(referee) => {
+                let counter = referee.command_counter();
+                if let Some(last) = self.last_command_counter
+                    && counter < last
+                {
+                    self.command_counter_regressions.push(format!(
+                        "at {}: {} -> {}",
+                        self.log_time(timestamp),
+                        last,
+                        counter
+                    ));
+                }
+                self.last_command_counter = Some(counter);
+            }
+            _ => {}
+        }
+    }
+
+    fn add_record_offset(&mut self, offset: u64) {
+        self.record_offsets.push(offset);
+        if self.index.is_some() {
+            self.records_after_index += 1;
+        }
+    }
+
+    fn check_detection(&mut self, timestamp: i64, detection: &SSL_DetectionFrame) {
+        let log_time = self.log_time(timestamp);
+        let camera = self.cameras.entry(detection.camera_id()).or_default();
+        let frame_number = detection.frame_number();
+        if let Some(last) = camera.last_frame_number
+            && frame_number != last.wrapping_add(1)
+        {
+            if frame_number > last {
+                camera.missing_frames += u64::from(frame_number - last - 1);
+            }
+            camera
+                .discontinuities
+                .push(format!("at {}: frame {} -> {}", log_time, last, frame_number));
+        }
+        camera.last_frame_number = Some(frame_number);
+    }
+
+    /// Finishes checking after the last complete record
+    ///
+    /// `end_offset` is where reading stopped and `trailer` is the last bytes of the file.
+    pub fn finish(self, file_size: u64, end_offset: u64, trailer: &[u8]) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        if !self.decode_errors.is_empty() {
+            findings.push(
+                Finding::new(
+                    Severity::Error,
+                    format!("{} messages could not be decoded", self.decode_errors.len()),
+                )
+                .with_details(self.decode_errors),
+            );
+        }
+
+        if !self.timestamp_regressions.is_empty() {
+            findings.push(
+                Finding::new(
+                    Severity::Error,
+                    format!("{} non-monotonic receive timestamps", self.timestamp_regressions.len()),
+                )
+                .with_details(self.timestamp_regressions),
+            );
+        }
+
+        let first_timestamp = self.first_timestamp.unwrap_or_default();
+        for (message_type, gaps) in self.gaps {
+            let longest = gaps.iter().map(|(_, length)| *length).max().unwrap_or_default();
+            let details = gaps
+                .iter()
+                .map(|(start, length)| {
+                    format!("at {}: {}", format_seconds(start - first_timestamp), format_seconds(*length))
+                })
+                .collect();
+            findings.push(
+                Finding::new(
+                    Severity::Warning,
+                    format!(
+                        "{} gaps longer than {} ms in {} messages (longest {})",
+                        gaps.len(),
+                        self.options.max_gap / 1_000_000,
+                        message_type,
+                        format_seconds(longest)
+                    ),
+                )
+                .with_details(details),
+            );
+        }
+
+        for (camera_id, camera) in self.cameras {
+            if !camera.discontinuities.is_empty() {
+                findings.push(
+                    Finding::new(
+                        Severity::Warning,
+                        format!(
+                            "Camera {}: {} frame number discontinuities, {} frames missing",
+                            camera_id,
+                            camera.discontinuities.len(),
+                            camera.missing_frames
+                        ),
+                    )
+                    .with_details(camera.discontinuities),
+                );
+            }
+        }
+
+        if !self.has_geometry {
+            findings.push(Finding::new(Severity::Warning, "No vision geometry"));
+        }
+
+        if !self.command_counter_regressions.is_empty() {
+            findings.push(
+                Finding::new(
+                    Severity::Warning,
+                    format!(
+                        "{} referee command counter regressions",
+                        self.command_counter_regressions.len()
+                    ),
+                )
+                .with_details(self.command_counter_regressions),
+            );
+        }
+
+        let has_marker = trailer.ends_with(INDEX_MARKER);
+        match self.index {
+            None if has_marker => findings.push(Finding::new(
+                Severity::Error,
+                "File ends with an index marker but has no readable index message",
+            )),
+            None => findings.push(Finding::new(Severity::Warning, "Log file is not indexed")),
+            Some((index_start, index)) => {
+                let mut problems = Vec::new();
+                if self.records_after_index > 0 {
+                    problems.push(format!("{} messages follow the index", self.records_after_index));
+                }
+                if !has_marker || end_offset != file_size {
+                    problems.push("index is not at the end of the file".to_string());
+                }
+                let expected_index_offset = file_size as i64 - index_start as i64;
+                if index.index_offset != expected_index_offset {
+                    problems.push(format!(
+                        "index offset is {}, expected {}",
+                        index.index_offset, expected_index_offset
+                    ));
+                }
+                let expected_offsets = &self.record_offsets[..self.record_offsets.len() - self.records_after_index];
+                if index.offsets.len() != expected_offsets.len() {
+                    problems.push(format!(
+                        "index has {} offsets, expected {}",
+                        index.offsets.len(),
+                        expected_offsets.len()
+                    ));
+                }
+                let wrong_offsets = index
+                    .offsets
+                    .iter()
+                    .zip(expected_offsets)
+                    .filter(|(actual, expected)| **actual != **expected as i64)
+                    .count();
+                if wrong_offsets > 0 {
+                    problems.push(format!("{} offsets do not point to the start of a message", wrong_offsets));
+                }
+                if !problems.is_empty() {
+                    findings.push(Finding::new(Severity::Error, "Inconsistent index").with_details(problems));
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+/// Checks every record in `reader`, which starts after the preamble and version of the log
+///
+/// `file_size` is the size of the whole log and `trailer` is the last bytes of the file.
+pub fn check_log<R: Read>(reader: &mut R, file_size: u64, trailer: &[u8], options: CheckOptions) -> Vec<Finding> {
+    let mut checker = LogChecker::new(options);
+    let mut offset = HEADER_SIZE;
+    let mut findings = Vec::new();
+    loop {
+        match extract_next_raw_message(reader) {
+            Ok(message) => {
+                checker.check_record(offset, &message);
+                offset += RECORD_HEADER_SIZE + message.data.len() as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                if offset < file_size {
+                    findings.push(Finding::new(
+                        Severity::Error,
+                        format!("Truncated record at offset {} ({} trailing bytes)", offset, file_size - offset),
+                    ));
+                }
+                break;
+            }
+            Err(e) => {
+                findings.push(Finding::new(
+                    Severity::Error,
+                    format!("Unreadable record at offset {}: {}", offset, e),
+                ));
+                break;
+            }
+        }
+    }
+    findings.extend(checker.finish(file_size, offset, trailer));
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssl_loglib::log_file_reader::verify_log_preamble;
+    use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use ssl_loglib::raw::write_raw_message;
+    use ssl_loglib::test_util::{at, detection, geometry, message, referee};
+
+    /// Geometry followed by a detection frame and a referee message every 100 ms for one second
+    fn clean_messages() -> Vec<LogMessage> {
+        let mut messages = vec![message(0.0, MessageBody::Vision2014(geometry()))];
+        for i in 1..=10u32 {
+            let time = f64::from(i) * 0.1;
+            messages.push(message(time, MessageBody::Vision2014(detection(0, i, time, &[], &[]))));
+            let referee = referee(time, Stage::NORMAL_FIRST_HALF, Command::STOP, i / 5);
+            messages.push(message(time, MessageBody::Refbox2013(referee)));
+        }
+        messages
+    }
+
+    /// Writes the header and `messages`, returning the log and the offset of each record
+    fn write_records(messages: &[LogMessage]) -> (Vec<u8>, Vec<i64>) {
+        let mut data = b"SSL_LOG_FILE".to_vec();
+        data.extend_from_slice(&1i32.to_be_bytes());
+        let mut offsets = Vec::new();
+        for message in messages {
+            offsets.push(data.len() as i64);
+            write_raw_message(&mut data, &RawMessage::try_from(message).unwrap()).unwrap();
+        }
+        (data, offsets)
+    }
+
+    /// Appends an index with `offsets` that ends the file
+    fn append_index(data: &mut Vec<u8>, offsets: Vec<i64>) {
+        let mut index = IndexMessage {
+            offsets,
+            index_offset: 0,
+        };
+        index.index_offset = RECORD_HEADER_SIZE as i64 + index.to_bytes().len() as i64;
+        let message = RawMessage {
+            timestamp: at(2.0),
+            message_type: MessageType::Index2021,
+            data: index.to_bytes(),
+        };
+        write_raw_message(data, &message).unwrap();
+    }
+
+    fn indexed_log(messages: &[LogMessage]) -> Vec<u8> {
+        let (mut data, offsets) = write_records(messages);
+        append_index(&mut data, offsets);
+        data
+    }
+
+    fn check(data: &[u8]) -> Vec<Finding> {
+        let mut reader = data;
+        verify_log_preamble(&mut reader).unwrap();
+        let trailer = &data[data.len().saturating_sub(7)..];
+        check_log(&mut reader, data.len() as u64, trailer, CheckOptions { max_gap: 500_000_000 })
+    }
+
+    fn summaries(findings: &[Finding]) -> Vec<(Severity, &str)> {
+        findings.iter().map(|f| (f.severity, f.summary.as_str())).collect()
+    }
+
+    #[test]
+    fn clean_log_has_no_findings() {
+        assert!(summaries(&check(&indexed_log(&clean_messages()))).is_empty());
+
+        let (data, _) = write_records(&clean_messages());
+        assert_eq!(summaries(&check(&data)), [(Severity::Warning, "Log file is not indexed")]);
+    }
+
+    #[test]
+    fn earlier_receive_timestamps_are_errors() {
+        let mut messages = clean_messages();
+        let late = referee(0.55, Stage::NORMAL_FIRST_HALF, Command::STOP, 2);
+        messages.push(message(0.55, MessageBody::Refbox2013(late)));
+        let findings = check(&indexed_log(&messages));
+        assert_eq!(summaries(&findings), [(Severity::Error, "1 non-monotonic receive timestamps")]);
+        assert_eq!(findings[0].details, ["at 0.550s: 0.450s earlier than the previous message"]);
+    }
+
+    #[test]
+    fn gaps_and_skipped_frames_are_warnings() {
+        // Frames 4 to 6 and the referee messages from 0.3 to 0.8 s are missing
+        let messages: Vec<LogMessage> = clean_messages()
+            .into_iter()
+            .filter(|m| {
+                let time = m.timestamp.timestamp_nanos_opt().unwrap();
+                match &m.body {
+                    MessageBody::Vision2014(packet) => packet.detection.is_none() || !(at(0.35)..at(0.65)).contains(&time),
+                    _ => !(at(0.25)..at(0.85)).contains(&time),
+                }
+            })
+            .collect();
+        let findings = check(&indexed_log(&messages));
+        assert_eq!(
+            summaries(&findings),
+            [
+                (Severity::Warning, "1 gaps longer than 500 ms in Refbox2013 messages (longest 0.700s)"),
+                (Severity::Warning, "Camera 0: 1 frame number discontinuities, 3 frames missing"),
+            ]
+        );
+        assert_eq!(findings[0].details, ["at 0.200s: 0.700s"]);
+        assert_eq!(findings[1].details, ["at 0.700s: frame 3 -> 7"]);
+    }
+
+    #[test]
+    fn missing_geometry_and_command_counter_regressions_are_warnings() {
+        let mut messages = clean_messages().split_off(1);
+        let reset = referee(1.1, Stage::NORMAL_FIRST_HALF, Command::STOP, 1);
+        messages.push(message(1.1, MessageBody::Refbox2013(reset)));
+        let findings = check(&indexed_log(&messages));
+        assert_eq!(
+            summaries(&findings),
+            [
+                (Severity::Warning, "No vision geometry"),
+                (Severity::Warning, "1 referee command counter regressions"),
+            ]
+        );
+        // Times are relative to the first message, which is now the first detection frame
+        assert_eq!(findings[1].details, ["at 1.000s: 2 -> 1"]);
+    }
+
+    #[test]
+    fn index_must_match_the_records() {
+        let (mut data, mut offsets) = write_records(&clean_messages());
+        offsets.pop();
+        offsets[0] += 1;
+        append_index(&mut data, offsets);
+        let findings = check(&data);
+        assert_eq!(summaries(&findings), [(Severity::Error, "Inconsistent index")]);
+        assert_eq!(
+            findings[0].details,
+            ["index has 20 offsets, expected 21", "1 offsets do not point to the start of a message"]
+        );
+
+        let mut data = indexed_log(&clean_messages());
+        let extra = referee(1.1, Stage::NORMAL_FIRST_HALF, Command::STOP, 2);
+        let extra = RawMessage::try_from(&message(1.1, MessageBody::Refbox2013(extra))).unwrap();
+        write_raw_message(&mut data, &extra).unwrap();
+        let findings = check(&data);
+        assert_eq!(summaries(&findings), [(Severity::Error, "Inconsistent index")]);
+        let index_size = RECORD_HEADER_SIZE as i64 + 8 * (clean_messages().len() as i64 + 1) + INDEX_MARKER.len() as i64;
+        let extra_size = RECORD_HEADER_SIZE as i64 + extra.data.len() as i64;
+        assert_eq!(
+            findings[0].details,
+            [
+                "1 messages follow the index".to_string(),
+                "index is not at the end of the file".to_string(),
+                format!("index offset is {}, expected {}", index_size, index_size + extra_size),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_bytes_are_errors() {
+        let (data, offsets) = write_records(&clean_messages());
+        let last_offset = *offsets.last().unwrap();
+        let truncated = &data[..data.len() - 3];
+        let trailing = truncated.len() as i64 - last_offset;
+        let findings = check(truncated);
+        let truncation = format!("Truncated record at offset {} ({} trailing bytes)", last_offset, trailing);
+        assert_eq!(
+            summaries(&findings),
+            [(Severity::Error, truncation.as_str()), (Severity::Warning, "Log file is not indexed")]
+        );
+
+        // A marker without an index message is not a valid index
+        let (mut data, _) = write_records(&clean_messages());
+        let end = data.len();
+        data.extend_from_slice(INDEX_MARKER);
+        let truncation = format!("Truncated record at offset {} (7 trailing bytes)", end);
+        assert_eq!(
+            summaries(&check(&data)),
+            [
+                (Severity::Error, truncation.as_str()),
+                (Severity::Error, "File ends with an index marker but has no readable index message"),
+            ]
+        );
+    }
+}
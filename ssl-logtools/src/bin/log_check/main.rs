@@ -0,0 +1,77 @@
+mod checks;
+
+use checks::{CheckOptions, Severity, check_log};
+use anyhow::Context;
+use clap::Parser;
+use ssl_loglib::index::INDEX_MARKER;
+use ssl_loglib::log_file_reader::verify_log_preamble;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek};
+
+/// Number of occurrences to list for each problem unless --verbose is set
+const MAX_DETAILS: usize = 5;
+
+#[derive(Parser)]
+#[command(version)]
+/// Check a game log for problems such as corruption, gaps, and dropped frames
+struct Args {
+    log_path: String,
+    #[arg(short = 'g', long, default_value_t = 500)]
+    /// Report gaps longer than this many milliseconds between messages of the same type
+    max_gap: u64,
+    #[arg(long)]
+    /// Exit with an error if there are any warnings
+    strict: bool,
+    #[arg(short, long)]
+    /// List every occurrence of each problem
+    verbose: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut file = File::open(&args.log_path)?;
+    let file_size = file.metadata()?.len();
+    let mut trailer = Vec::new();
+    file.seek(io::SeekFrom::Start(file_size.saturating_sub(INDEX_MARKER.len() as u64)))?;
+    file.read_to_end(&mut trailer)?;
+    file.rewind()?;
+
+    let mut reader = BufReader::new(file);
+    verify_log_preamble(&mut reader).context("Not a valid log file")?;
+
+    let options = CheckOptions {
+        max_gap: i64::try_from(args.max_gap)? * 1_000_000,
+    };
+    let mut findings = check_log(&mut reader, file_size, &trailer, options);
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+
+    for finding in &findings {
+        let label = match finding.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        println!("{}: {}", label, finding.summary);
+        let shown = if args.verbose { finding.details.len() } else { MAX_DETAILS };
+        for detail in finding.details.iter().take(shown) {
+            println!("    {}", detail);
+        }
+        if finding.details.len() > shown {
+            println!("    ... and {} more", finding.details.len() - shown);
+        }
+    }
+
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    let warnings = findings.iter().filter(|f| f.severity == Severity::Warning).count();
+    if errors == 0 && warnings == 0 {
+        println!("OK");
+    } else {
+        println!("{} errors, {} warnings", errors, warnings);
+    }
+
+    if errors > 0 || (args.strict && warnings > 0) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use clap::Parser;
+use protobuf::Enum;
+use ssl_loglib::cut::{CutRange, cut_log};
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+
+#[derive(Parser)]
+#[command(version)]
+/// Cut a time range, stage, or command window out of a game log
+struct Args {
+    log_path: String,
+    output_path: String,
+    #[arg(short, long, conflicts_with_all = ["stage", "command"])]
+    /// Start time in seconds from the beginning of the log
+    start_time: Option<f64>,
+    #[arg(short, long, conflicts_with_all = ["stage", "command"])]
+    /// End time in seconds from the beginning of the log
+    end_time: Option<f64>,
+    #[arg(long, value_parser = parse_stage, conflicts_with = "command")]
+    /// Keep all messages during the given referee stage (ex. NORMAL_SECOND_HALF)
+    stage: Option<Stage>,
+    #[arg(long, value_parser = parse_command)]
+    /// Keep a window around each time the referee issues the given command (ex. GOAL_BLUE)
+    command: Option<Command>,
+    #[arg(short, long, default_value_t = 15.0, requires = "command")]
+    /// Seconds to keep before each command
+    before: f64,
+    #[arg(short, long, default_value_t = 15.0, requires = "command")]
+    /// Seconds to keep after each command
+    after: f64,
+}
+
+fn parse_stage(s: &str) -> Result<Stage, String> {
+    Stage::from_str(s).ok_or(format!("Unknown stage: {}", s))
+}
+
+fn parse_command(s: &str) -> Result<Command, String> {
+    Command::from_str(s).ok_or(format!("Unknown command: {}", s))
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let range = if let Some(stage) = args.stage {
+        CutRange::Stage(stage)
+    } else if let Some(command) = args.command {
+        CutRange::Command {
+            command,
+            before: args.before,
+            after: args.after,
+        }
+    } else {
+        CutRange::Time {
+            start: args.start_time,
+            end: args.end_time,
+        }
+    };
+
+    let count = cut_log(&args.log_path, &args.output_path, &range)?;
+    if count == 0 {
+        eprintln!("Warning: no messages matched the requested range.");
+    }
+    println!("Wrote {} messages to {}", count, args.output_path);
+
+    Ok(())
+}
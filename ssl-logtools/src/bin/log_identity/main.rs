@@ -0,0 +1,74 @@
+use clap::Parser;
+use ssl_loglib::analysis::identity::{IdentityIssue, IdentityOptions, SuspiciousSegment, analyze_identities};
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+use ssl_loglib::tracker::RobotKey;
+
+#[derive(Parser)]
+#[command(version)]
+/// Find robot ID swaps, duplicate IDs, and phantom robots in the raw vision of a game log
+struct Args {
+    log_path: String,
+    #[arg(long, default_value_t = 6.0)]
+    /// Highest plausible robot speed in m/s
+    max_speed: f64,
+    #[arg(long, default_value_t = 0.3)]
+    /// Distance in meters between two detections of one ID above which they count as a duplicate
+    duplicate_distance: f64,
+    #[arg(long, default_value_t = 0.5)]
+    /// Issues less than this many seconds apart are merged into one segment
+    segment_gap: f64,
+}
+
+fn robot_label(segment: &SuspiciousSegment) -> String {
+    match segment.id {
+        Some(id) => RobotKey { team: segment.team, id }.to_string(),
+        None => match segment.team {
+            TeamColor::TEAM_COLOR_YELLOW => "Yellow".to_string(),
+            TeamColor::TEAM_COLOR_BLUE => "Blue".to_string(),
+            TeamColor::TEAM_COLOR_UNKNOWN => "Unknown".to_string(),
+        },
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let options = IdentityOptions {
+        max_speed: args.max_speed,
+        duplicate_distance: args.duplicate_distance,
+        segment_gap: args.segment_gap,
+        ..Default::default()
+    };
+    let mut segments = analyze_identities(&args.log_path, options)?;
+    if segments.is_empty() {
+        println!("No suspicious robot detections found.");
+        return Ok(());
+    }
+
+    let log_start = LogFileReader::new(&args.log_path)?.get_next_message()?.timestamp;
+    segments.sort_by_key(|s| (s.team as i32, s.id, s.start));
+
+    println!(
+        "{: <8} {: >10} {: >10}  {: <14} {: >8}  {: <5}",
+        "Robot", "Start (s)", "End (s)", "Issue", "Count", "Worst"
+    );
+    for segment in &segments {
+        let (issue, worst) = match segment.issue {
+            IdentityIssue::Jump => ("jump", format!("{:.2} m", segment.worst)),
+            IdentityIssue::DuplicateId => ("duplicate ID", format!("{:.2} m apart", segment.worst)),
+            IdentityIssue::ExtraRobots => ("extra robots", format!("{} robots", segment.worst)),
+        };
+        println!(
+            "{: <8} {: >10.3} {: >10.3}  {: <14} {: >8}  {}",
+            robot_label(segment),
+            (segment.start - log_start).as_seconds_f64(),
+            (segment.end - log_start).as_seconds_f64(),
+            issue,
+            segment.count,
+            worst
+        );
+    }
+
+    Ok(())
+}
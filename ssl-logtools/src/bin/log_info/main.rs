@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use clap::Parser;
-use ssl_loglib::MessageBody;
+use ssl_loglib::analysis::Analyzer;
+use ssl_loglib::analysis::cameras::{CameraStats, CameraStatsAnalyzer};
 use ssl_loglib::log_file_reader::LogFileReader;
 use ssl_loglib::raw::MessageType;
 use std::collections::HashMap;
@@ -10,6 +11,57 @@ use std::io;
 #[command(version)]
 struct Args {
     log_path: String,
+    #[arg(long)]
+    /// Show frame rate, dropped frames, and coverage statistics for each camera
+    vision: bool,
+}
+
+fn print_camera_stats(cameras: &[CameraStats]) {
+    if cameras.is_empty() {
+        println!("No vision detection frames found.");
+        return;
+    }
+    println!(
+        "{: <7} {: >8} {: >10} {: >16} {: >8} {: >8}  {: <32} {: >9}",
+        "Camera", "Frames", "Rate (Hz)", "Dropped", "Ball", "Robots", "Coverage (m)", "Area (m²)"
+    );
+    for camera in cameras {
+        let frame_rate = camera.frame_rate.map_or("-".to_string(), |r| format!("{:.2}", r));
+        let dropped = format!("{} ({:.1}%)", camera.dropped_frames, camera.drop_fraction() * 100.0);
+        let (coverage, area) = match camera.coverage {
+            Some(c) => (
+                format!(
+                    "x {:.2}..{:.2}, y {:.2}..{:.2}",
+                    c.min_x / 1000.0,
+                    c.max_x / 1000.0,
+                    c.min_y / 1000.0,
+                    c.max_y / 1000.0
+                ),
+                format!("{:.1}", c.area()),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{: <7} {: >8} {: >10} {: >16} {: >7.1}% {: >8.2}  {: <32} {: >9}",
+            camera.camera_id,
+            camera.frames,
+            frame_rate,
+            dropped,
+            camera.ball_fraction() * 100.0,
+            camera.average_robots(),
+            coverage,
+            area
+        );
+        if camera.out_of_order_frames > 0 {
+            println!("        {} frames arrived out of order", camera.out_of_order_frames);
+        }
+        if camera.duplicate_frames > 0 {
+            println!("        {} frames were received more than once", camera.duplicate_frames);
+        }
+        if camera.restarts > 0 {
+            println!("        Frame numbers restarted {} times", camera.restarts);
+        }
+    }
 }
 
 fn main() -> io::Result<()> {
@@ -25,17 +77,13 @@ fn main() -> io::Result<()> {
 
     let mut first_timestamp: Option<DateTime<Utc>> = None;
     let mut last_timestamp: Option<DateTime<Utc>> = None;
+    let mut camera_stats = CameraStatsAnalyzer::new();
 
     for message in reader {
-        let message_type = match message.body {
-            MessageBody::Blank(_) => MessageType::Blank,
-            MessageBody::Unkown(_) => MessageType::Unkown,
-            MessageBody::Vision2010(_) => MessageType::Vision2010,
-            MessageBody::Refbox2013(_) => MessageType::Refbox2013,
-            MessageBody::Vision2014(_) => MessageType::Vision2014,
-            MessageBody::VisionTracker2020(_) => MessageType::VisionTracker2020,
-            MessageBody::Index2021(_) => MessageType::Index2021,
-        };
+        if args.vision {
+            camera_stats.push(&message);
+        }
+        let message_type = message.body.message_type();
         match counts.get_mut(&message_type) {
             Some(c) => {
                 *c += 1;
@@ -66,5 +114,10 @@ fn main() -> io::Result<()> {
         }
     }
 
+    if args.vision {
+        println!();
+        print_camera_stats(&camera_stats.finish());
+    }
+
     Ok(())
 }
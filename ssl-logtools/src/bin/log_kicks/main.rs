@@ -0,0 +1,100 @@
+use clap::Parser;
+use ssl_loglib::analysis::kicks::{Kick, KickOutcome, MAX_BALL_SPEED, analyze_kicks};
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+use ssl_loglib::tracker::team_name;
+
+#[derive(Parser)]
+#[command(version)]
+/// List the kicks in a game log with a leaderboard of the fastest kicks per team
+struct Args {
+    log_path: String,
+    #[arg(short, long)]
+    /// Source name or UUID of the tracker to use. Defaults to the first source that reports kicks.
+    source: Option<String>,
+    #[arg(short, long, default_value_t = 5)]
+    /// Number of kicks per team in the leaderboard
+    top: usize,
+}
+
+fn outcome_label(outcome: KickOutcome) -> String {
+    match outcome {
+        KickOutcome::Goal {
+            scored_by: Some(team @ (TeamColor::TEAM_COLOR_YELLOW | TeamColor::TEAM_COLOR_BLUE)),
+        } => format!("goal for {}", team_name(team)),
+        KickOutcome::Goal { .. } => "goal".to_string(),
+        KickOutcome::Out => "out".to_string(),
+        KickOutcome::Received(robot) => format!("received by {}", robot),
+        KickOutcome::Intercepted(robot) => format!("intercepted by {}", robot),
+        KickOutcome::Touched(robot) => format!("touched by {}", robot),
+        KickOutcome::Unknown => "-".to_string(),
+    }
+}
+
+fn kicker_label(kick: &Kick) -> String {
+    kick.kicker.map_or("?".to_string(), |r| r.to_string())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let report = analyze_kicks(&args.log_path, args.source)?;
+    let Some(source) = &report.source else {
+        println!("No kicks found in log file.");
+        return Ok(());
+    };
+    let log_start = LogFileReader::new(&args.log_path)?.get_next_message()?.timestamp;
+    let log_time = |kick: &Kick| (kick.timestamp - log_start).as_seconds_f64();
+
+    println!("{} kicks reported by {}", report.kicks.len(), source);
+    if report.kicks.is_empty() {
+        return Ok(());
+    }
+    println!();
+    println!("{: >9}  {: <6} {: >12}  {: <5}  {: <7}", "Time (s)", "Kicker", "Speed (m/s)", "Type", "Outcome");
+    for kick in &report.kicks {
+        let flag = if kick.is_unpunished_too_fast() { "  (too fast, no game event)" } else { "" };
+        println!(
+            "{: >9.2}  {: <6} {: >12.2}  {: <5}  {}{}",
+            log_time(kick),
+            kicker_label(kick),
+            kick.speed(),
+            if kick.is_chip() { "chip" } else { "flat" },
+            outcome_label(kick.outcome),
+            flag
+        );
+    }
+
+    for team in [TeamColor::TEAM_COLOR_YELLOW, TeamColor::TEAM_COLOR_BLUE] {
+        let fastest = report.fastest(team, args.top);
+        if fastest.is_empty() {
+            continue;
+        }
+        println!();
+        println!("Fastest kicks by {}", team_name(team));
+        for (rank, kick) in fastest.iter().enumerate() {
+            println!(
+                "{: >3}. {: <6} {: >6.2} m/s at {:.2}s",
+                rank + 1,
+                kicker_label(kick),
+                kick.speed(),
+                log_time(kick)
+            );
+        }
+    }
+
+    let unpunished: Vec<&Kick> = report.kicks.iter().filter(|k| k.is_unpunished_too_fast()).collect();
+    if !unpunished.is_empty() {
+        println!();
+        println!(
+            "Warning: {} kicks faster than {} m/s without a BotKickedBallTooFast game event",
+            unpunished.len(),
+            MAX_BALL_SPEED
+        );
+        for kick in unpunished {
+            println!("    at {:.2}s: {} kicked {:.2} m/s", log_time(kick), kicker_label(kick), kick.speed());
+        }
+    }
+
+    Ok(())
+}
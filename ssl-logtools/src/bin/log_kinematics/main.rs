@@ -0,0 +1,121 @@
+use clap::Parser;
+use serde::Serialize;
+use ssl_loglib::analysis::Distribution;
+use ssl_loglib::analysis::kinematics::{KinematicsStats, analyze_kinematics};
+use ssl_loglib::tracker::team_name;
+
+#[derive(Parser)]
+#[command(version)]
+/// Report speed, acceleration, and angular speed statistics for every robot in a game log
+struct Args {
+    log_path: String,
+    #[arg(short, long)]
+    /// Source name or UUID of the tracker to use. Defaults to the first source in the log.
+    source: Option<String>,
+    #[arg(long)]
+    /// Print JSON instead of a table
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct DistributionSummary {
+    mean: Option<f64>,
+    p50: Option<f64>,
+    p95: Option<f64>,
+    max: Option<f64>,
+}
+
+impl From<&Distribution> for DistributionSummary {
+    fn from(distribution: &Distribution) -> Self {
+        DistributionSummary {
+            mean: distribution.mean(),
+            p50: distribution.percentile(50.0),
+            p95: distribution.percentile(95.0),
+            max: distribution.max(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RobotRow {
+    team: &'static str,
+    id: u32,
+    stage: Option<String>,
+    tracked_time: f64,
+    distance: f64,
+    speed: DistributionSummary,
+    acceleration: DistributionSummary,
+    angular_speed: DistributionSummary,
+}
+
+fn to_row(stats: &KinematicsStats) -> RobotRow {
+    RobotRow {
+        team: team_name(stats.robot.team),
+        id: stats.robot.id,
+        stage: stats.stage.map(|s| format!("{:?}", s)),
+        tracked_time: stats.tracked_time,
+        distance: stats.distance,
+        speed: DistributionSummary::from(&stats.speed),
+        acceleration: DistributionSummary::from(&stats.acceleration),
+        angular_speed: DistributionSummary::from(&stats.angular_speed),
+    }
+}
+
+fn format_value(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |v| format!("{:.2}", v))
+}
+
+fn print_table(stats: &[KinematicsStats]) {
+    let mut current_stage = None;
+    for (i, robot) in stats.iter().enumerate() {
+        if i == 0 || robot.stage != current_stage {
+            current_stage = robot.stage;
+            if i > 0 {
+                println!();
+            }
+            println!("{}", current_stage.map_or("Before first referee message".to_string(), |s| format!("{:?}", s)));
+            println!(
+                "{: <6} {: >9} {: >9} {: >21} {: >17} {: >17}",
+                "Robot", "Time (s)", "Dist (m)", "Speed p50/p95/max", "Acc p95/max", "Ang p95/max"
+            );
+        }
+        println!(
+            "{: <6} {: >9.1} {: >9.1} {: >21} {: >17} {: >17}",
+            robot.robot.to_string(),
+            robot.tracked_time,
+            robot.distance,
+            format!(
+                "{}/{}/{}",
+                format_value(robot.speed.percentile(50.0)),
+                format_value(robot.speed.percentile(95.0)),
+                format_value(robot.speed.max())
+            ),
+            format!(
+                "{}/{}",
+                format_value(robot.acceleration.percentile(95.0)),
+                format_value(robot.acceleration.max())
+            ),
+            format!(
+                "{}/{}",
+                format_value(robot.angular_speed.percentile(95.0)),
+                format_value(robot.angular_speed.max())
+            )
+        );
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let stats = analyze_kinematics(&args.log_path, args.source)?;
+    if args.json {
+        let rows: Vec<RobotRow> = stats.iter().map(to_row).collect();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else if stats.is_empty() {
+        println!("No tracked robots found in log file.");
+    } else {
+        print_table(&stats);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use clap::Parser;
+use ssl_loglib::analysis::Distribution;
+use ssl_loglib::analysis::latency::analyze_latency;
+
+#[derive(Parser)]
+#[command(version)]
+/// Report vision pipeline latency for each camera in a game log
+struct Args {
+    log_path: String,
+    #[arg(long)]
+    /// Print a histogram of each latency distribution
+    histogram: bool,
+    #[arg(short, long, default_value_t = 1.0)]
+    /// Histogram bin width in milliseconds
+    bin_width: f64,
+}
+
+const PERCENTILES: [f64; 5] = [0.0, 50.0, 90.0, 99.0, 100.0];
+const HISTOGRAM_WIDTH: usize = 50;
+/// Clock offsets larger than this (in seconds) suggest the vision PC and recorder clocks are not synchronized
+const SYNC_THRESHOLD: f64 = 0.05;
+
+fn print_row(label: &str, distribution: &Distribution) {
+    print!("  {: <26}", label);
+    for percent in PERCENTILES {
+        let value = distribution.percentile(percent).unwrap_or(f64::NAN);
+        print!(" {: >9.3}", value * 1000.0);
+    }
+    println!(" {: >9.3}", distribution.mean().unwrap_or(f64::NAN) * 1000.0);
+}
+
+fn print_histogram(label: &str, distribution: &Distribution, bin_width: f64) {
+    let bins = distribution.histogram(bin_width);
+    let largest = bins.iter().map(|(_, count)| *count).max().unwrap_or_default().max(1);
+    println!("  {} (ms)", label);
+    for (start, count) in bins {
+        let bar = "#".repeat(count * HISTOGRAM_WIDTH / largest);
+        println!("  {: >10.1} {: >7} {}", start * 1000.0, count, bar);
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let cameras = analyze_latency(&args.log_path)?;
+    if cameras.is_empty() {
+        println!("No vision detection frames found in log file.");
+        return Ok(());
+    }
+
+    for camera in cameras {
+        let clock_offset = camera.clock_offset.unwrap_or_default();
+        println!(
+            "Camera {}: {} frames, clock offset {:+.3} ms",
+            camera.camera_id,
+            camera.frames,
+            clock_offset * 1000.0
+        );
+        if clock_offset.abs() > SYNC_THRESHOLD {
+            println!("  Warning: vision PC and recorder clocks do not appear to be synchronized");
+        }
+
+        let send_to_network = camera.send_to_receive.shifted(-clock_offset);
+        // The camera clock has an arbitrary epoch, so only the variation around the median is shown
+        let camera_jitter = camera
+            .camera_to_capture
+            .shifted(-camera.camera_to_capture.percentile(50.0).unwrap_or_default());
+        let mut rows = vec![
+            ("capture -> send", &camera.capture_to_send),
+            ("send -> receive", &camera.send_to_receive),
+            ("send -> receive - offset", &send_to_network),
+            ("capture -> receive", &camera.capture_to_receive),
+        ];
+        if !camera.camera_to_capture.is_empty() {
+            rows.push(("camera -> capture jitter", &camera_jitter));
+        }
+
+        println!(
+            "  {: <26} {: >9} {: >9} {: >9} {: >9} {: >9} {: >9}",
+            "(ms)", "min", "p50", "p90", "p99", "max", "mean"
+        );
+        for (label, distribution) in &rows {
+            print_row(label, distribution);
+        }
+        if args.histogram {
+            for (label, distribution) in rows {
+                println!();
+                print_histogram(label.trim(), distribution, args.bin_width / 1000.0);
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use clap::Parser;
+use ssl_loglib::merge::{DEFAULT_DEDUPLICATE_WINDOW, MergeInput, merge_logs};
+
+#[derive(Parser)]
+#[command(version)]
+/// Merge multiple game logs into one log in timestamp order
+struct Args {
+    #[arg(required = true, num_args = 2..)]
+    log_paths: Vec<String>,
+    #[arg(short, long, default_value_t = ("merged.log").to_string())]
+    output_path: String,
+    #[arg(short, long, allow_negative_numbers = true)]
+    /// Seconds added to the timestamps of each input log. Give once per input, in the same order as the inputs.
+    time_offset: Vec<f64>,
+    #[arg(short, long)]
+    /// Drop messages with the same type and payload as a message received shortly before
+    deduplicate: bool,
+    #[arg(short = 'w', long, default_value_t = DEFAULT_DEDUPLICATE_WINDOW * 1000.0)]
+    /// Time window in milliseconds in which --deduplicate looks for an identical message
+    deduplicate_window: f64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    if !args.time_offset.is_empty() && args.time_offset.len() != args.log_paths.len() {
+        return Err(anyhow::Error::msg(format!(
+            "Got {} time offsets for {} input logs",
+            args.time_offset.len(),
+            args.log_paths.len()
+        )));
+    }
+
+    let inputs: Vec<MergeInput> = args
+        .log_paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| MergeInput {
+            path: path.into(),
+            time_offset: args.time_offset.get(i).copied().unwrap_or(0.0),
+        })
+        .collect();
+
+    let deduplicate_window = args.deduplicate.then_some(args.deduplicate_window / 1000.0);
+    let summary = merge_logs(&inputs, &args.output_path, deduplicate_window)?;
+    println!("Wrote {} messages to {}", summary.written, args.output_path);
+    if args.deduplicate {
+        println!("Dropped {} duplicate messages", summary.duplicates);
+    }
+
+    Ok(())
+}
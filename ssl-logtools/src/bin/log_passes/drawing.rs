@@ -0,0 +1,155 @@
+use skia_safe::{Canvas, Color, Font, FontMgr, FontStyle, Paint, PaintStyle, PathBuilder, Point, Rect, svg};
+use ssl_loglib::analysis::passes::{PassNetwork, PassNode};
+use ssl_loglib::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+use ssl_loglib::tracker::team_name;
+
+const PIXELS_PER_METER: f32 = 80.0;
+/// Division A field length and width in meters, used when the log has no field geometry
+const DEFAULT_FIELD_SIZE: [f64; 2] = [12.0, 9.0];
+/// Space around the field lines in meters
+const MARGIN: f32 = 0.3;
+/// Space above each field for the team name, in meters
+const LABEL_HEIGHT: f32 = 0.5;
+const CENTER_CIRCLE_RADIUS: f32 = 0.5;
+const NODE_RADIUS: f32 = 0.12;
+/// Extra node radius for each pass kicked or received
+const NODE_RADIUS_PER_PASS: f32 = 0.02;
+const EDGE_WIDTH: f32 = 0.02;
+/// Extra edge width for each completed pass
+const EDGE_WIDTH_PER_PASS: f32 = 0.015;
+const ARROW_LENGTH: f32 = 0.15;
+
+const FIELD_COLOR: Color = Color::from_rgb(0, 120, 40);
+const LINE_COLOR: Color = Color::WHITE;
+const YELLOW_COLOR: Color = Color::from_rgb(255, 220, 0);
+const BLUE_COLOR: Color = Color::from_rgb(40, 90, 255);
+
+/// Maps field coordinates in meters to image pixels, with the y axis pointing up
+struct FieldTransform {
+    half_length: f32,
+    half_width: f32,
+    /// Top of the field's margin in the image, in meters
+    top: f32,
+}
+
+impl FieldTransform {
+    fn point(&self, pos: [f64; 2]) -> Point {
+        Point::new(
+            (pos[0] as f32 + self.half_length + MARGIN) * PIXELS_PER_METER,
+            (self.top + self.half_width - pos[1] as f32 + MARGIN) * PIXELS_PER_METER,
+        )
+    }
+}
+
+fn paint(color: Color, style: PaintStyle, stroke_width: f32) -> Paint {
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    paint.set_color(color);
+    paint.set_style(style);
+    paint.set_stroke_width(stroke_width * PIXELS_PER_METER);
+    paint
+}
+
+fn team_color(team: TeamColor) -> Color {
+    match team {
+        TeamColor::TEAM_COLOR_BLUE => BLUE_COLOR,
+        _ => YELLOW_COLOR,
+    }
+}
+
+fn node_radius(node: &PassNode) -> f32 {
+    NODE_RADIUS + NODE_RADIUS_PER_PASS * (node.passes + node.received) as f32
+}
+
+fn draw_field(canvas: &Canvas, transform: &FieldTransform) {
+    let lines = paint(LINE_COLOR, PaintStyle::Stroke, 0.01);
+    let half_length = f64::from(transform.half_length);
+    let half_width = f64::from(transform.half_width);
+    let top_left = transform.point([-half_length, half_width]);
+    let bottom_right = transform.point([half_length, -half_width]);
+    canvas.draw_rect(Rect::new(top_left.x, top_left.y, bottom_right.x, bottom_right.y), &lines);
+    canvas.draw_line(transform.point([0.0, half_width]), transform.point([0.0, -half_width]), &lines);
+    canvas.draw_circle(transform.point([0.0, 0.0]), CENTER_CIRCLE_RADIUS * PIXELS_PER_METER, &lines);
+}
+
+fn draw_network(canvas: &Canvas, transform: &FieldTransform, network: &PassNetwork, font: Option<&Font>) {
+    let color = team_color(network.team);
+    let find_node = |robot| network.nodes.iter().find(|node| node.robot == robot);
+
+    for edge in &network.edges {
+        let (Some(from), Some(to)) = (find_node(edge.from), find_node(edge.to)) else {
+            continue;
+        };
+        let start = transform.point(from.pos);
+        let end = transform.point(to.pos);
+        let length = (end - start).length();
+        if length == 0.0 {
+            continue;
+        }
+        let direction = (end - start) * (1.0 / length);
+        let normal = Point::new(-direction.y, direction.x);
+        // Arrows start and end at the node circles
+        let start = start + direction * (node_radius(from) * PIXELS_PER_METER);
+        let tip = end - direction * (node_radius(to) * PIXELS_PER_METER);
+        let width = EDGE_WIDTH + EDGE_WIDTH_PER_PASS * edge.count as f32;
+        let base = tip - direction * ((ARROW_LENGTH + width) * PIXELS_PER_METER);
+        canvas.draw_line(start, base, &paint(color, PaintStyle::Stroke, width));
+
+        let head_width = (ARROW_LENGTH / 2.0 + width) * PIXELS_PER_METER;
+        let mut head = PathBuilder::new();
+        head.move_to(tip)
+            .line_to(base + normal * head_width)
+            .line_to(base - normal * head_width)
+            .close();
+        canvas.draw_path(&head.detach(), &paint(color, PaintStyle::Fill, 0.0));
+    }
+
+    let outline = paint(Color::BLACK, PaintStyle::Stroke, 0.01);
+    let fill = paint(color, PaintStyle::Fill, 0.0);
+    let text = paint(Color::BLACK, PaintStyle::Fill, 0.0);
+    for node in &network.nodes {
+        let center = transform.point(node.pos);
+        let radius = node_radius(node) * PIXELS_PER_METER;
+        canvas.draw_circle(center, radius, &fill);
+        canvas.draw_circle(center, radius, &outline);
+        if let Some(font) = font {
+            let label = node.robot.id.to_string();
+            let (width, bounds) = font.measure_str(&label, Some(&text));
+            canvas.draw_str(&label, center - Point::new(width / 2.0, bounds.center_y()), font, &text);
+        }
+    }
+}
+
+/// Draws each pass network on its own field, stacked top to bottom, and returns the SVG document
+///
+/// `field_size` is the field length and width in meters. If it is unknown, a division A field is drawn.
+pub fn draw_networks(field_size: [f64; 2], networks: &[PassNetwork]) -> Vec<u8> {
+    let field_size = if field_size.iter().all(|size| *size > 0.0) { field_size } else { DEFAULT_FIELD_SIZE };
+    let panel_height = LABEL_HEIGHT + field_size[1] as f32 + 2.0 * MARGIN;
+    let bounds = Rect::from_wh(
+        (field_size[0] as f32 + 2.0 * MARGIN) * PIXELS_PER_METER,
+        panel_height * networks.len().max(1) as f32 * PIXELS_PER_METER,
+    );
+    let canvas = svg::Canvas::new(bounds, None);
+    canvas.draw_rect(bounds, &paint(FIELD_COLOR, PaintStyle::Fill, 0.0));
+
+    let typeface = FontMgr::default().legacy_make_typeface(None, FontStyle::bold());
+    let font = typeface.as_ref().map(|typeface| Font::new(typeface.clone(), 0.15 * PIXELS_PER_METER));
+    let label_font = typeface.as_ref().map(|typeface| Font::new(typeface.clone(), 0.3 * PIXELS_PER_METER));
+    for (i, network) in networks.iter().enumerate() {
+        let panel_top = i as f32 * panel_height;
+        let transform = FieldTransform {
+            half_length: field_size[0] as f32 / 2.0,
+            half_width: field_size[1] as f32 / 2.0,
+            top: panel_top + LABEL_HEIGHT,
+        };
+        if let Some(label_font) = &label_font {
+            let origin = Point::new(MARGIN, panel_top + LABEL_HEIGHT) * PIXELS_PER_METER;
+            let label = paint(team_color(network.team), PaintStyle::Fill, 0.0);
+            canvas.draw_str(team_name(network.team), origin, label_font, &label);
+        }
+        draw_field(&canvas, &transform);
+        draw_network(&canvas, &transform, network, font.as_ref());
+    }
+    canvas.end().as_bytes().to_vec()
+}
@@ -0,0 +1,208 @@
+use clap::Parser;
+use serde::Serialize;
+use ssl_loglib::analysis::passes::{Pass, PassNetwork, PassReport, analyze_passes};
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::vision::messages_robocup_ssl_detection_tracked::TeamColor;
+use ssl_loglib::tracker::team_name;
+
+mod drawing;
+
+#[derive(Parser)]
+#[command(version)]
+/// Detect passes in a game log and build the pass network of each team
+struct Args {
+    log_path: String,
+    #[arg(short, long)]
+    /// Source name or UUID of the tracker to use. Defaults to the first source that reports kicks.
+    source: Option<String>,
+    #[arg(short, long)]
+    /// Write the pass networks and passes as a JSON graph to this path
+    json: Option<String>,
+    #[arg(short = 'g', long)]
+    /// Draw the pass networks on the field as an SVG image at this path
+    svg: Option<String>,
+}
+
+const TEAMS: [TeamColor; 2] = [TeamColor::TEAM_COLOR_YELLOW, TeamColor::TEAM_COLOR_BLUE];
+
+#[derive(Serialize)]
+struct Graph {
+    source: Option<String>,
+    field_length: f64,
+    field_width: f64,
+    teams: Vec<TeamGraph>,
+    passes: Vec<PassRow>,
+}
+
+#[derive(Serialize)]
+struct TeamGraph {
+    team: &'static str,
+    passes: usize,
+    completed: usize,
+    intercepted: usize,
+    intercepts: usize,
+    success_rate: Option<f64>,
+    nodes: Vec<NodeRow>,
+    edges: Vec<EdgeRow>,
+}
+
+#[derive(Serialize)]
+struct NodeRow {
+    id: u32,
+    x: f64,
+    y: f64,
+    passes: usize,
+    completed: usize,
+    received: usize,
+    intercepts: usize,
+    success_rate: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct EdgeRow {
+    from: u32,
+    to: u32,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct PassRow {
+    time: f64,
+    passer: String,
+    receiver: String,
+    completed: bool,
+    start: [f64; 2],
+    end: Option<[f64; 2]>,
+    speed: f64,
+}
+
+fn to_team_graph(network: &PassNetwork) -> TeamGraph {
+    TeamGraph {
+        team: team_name(network.team),
+        passes: network.passes,
+        completed: network.completed,
+        intercepted: network.intercepted,
+        intercepts: network.intercepts,
+        success_rate: network.success_rate(),
+        nodes: network
+            .nodes
+            .iter()
+            .map(|node| NodeRow {
+                id: node.robot.id,
+                x: node.pos[0],
+                y: node.pos[1],
+                passes: node.passes,
+                completed: node.completed,
+                received: node.received,
+                intercepts: node.intercepts,
+                success_rate: node.success_rate(),
+            })
+            .collect(),
+        edges: network
+            .edges
+            .iter()
+            .map(|edge| EdgeRow {
+                from: edge.from.id,
+                to: edge.to.id,
+                count: edge.count,
+            })
+            .collect(),
+    }
+}
+
+fn to_graph(report: &PassReport, networks: &[PassNetwork], log_time: impl Fn(&Pass) -> f64) -> Graph {
+    Graph {
+        source: report.source.clone(),
+        field_length: report.field_size[0],
+        field_width: report.field_size[1],
+        teams: networks.iter().map(to_team_graph).collect(),
+        passes: report
+            .passes
+            .iter()
+            .map(|pass| PassRow {
+                time: log_time(pass),
+                passer: pass.passer.to_string(),
+                receiver: pass.receiver().to_string(),
+                completed: pass.is_completed(),
+                start: pass.start_pos,
+                end: pass.end_pos,
+                speed: pass.speed,
+            })
+            .collect(),
+    }
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0))
+}
+
+fn print_network(network: &PassNetwork) {
+    println!(
+        "{}: {} passes, {} completed ({}), {} intercepted, {} intercepts",
+        team_name(network.team),
+        network.passes,
+        network.completed,
+        format_rate(network.success_rate()),
+        network.intercepted,
+        network.intercepts
+    );
+    if network.nodes.is_empty() {
+        return;
+    }
+    println!(
+        "  {: <6} {: >6} {: >9} {: >8} {: >10} {: >8}",
+        "Robot", "Passes", "Completed", "Received", "Intercepts", "Success"
+    );
+    for node in &network.nodes {
+        println!(
+            "  {: <6} {: >6} {: >9} {: >8} {: >10} {: >8}",
+            node.robot.to_string(),
+            node.passes,
+            node.completed,
+            node.received,
+            node.intercepts,
+            format_rate(node.success_rate())
+        );
+    }
+    for edge in &network.edges {
+        println!("  {} -> {}: {}", edge.from, edge.to, edge.count);
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let report = analyze_passes(&args.log_path, args.source)?;
+    let Some(source) = &report.source else {
+        println!("No kicks found in log file.");
+        return Ok(());
+    };
+    let log_start = LogFileReader::new(&args.log_path)?.get_next_message()?.timestamp;
+    let log_time = |pass: &Pass| (pass.timestamp - log_start).as_seconds_f64();
+    let networks: Vec<PassNetwork> = TEAMS.iter().map(|team| report.network(*team)).collect();
+
+    println!("{} passes reported by {}", report.passes.len(), source);
+    for pass in &report.passes {
+        println!(
+            "{: >9.2}  {} -> {}{}",
+            log_time(pass),
+            pass.passer,
+            pass.receiver(),
+            if pass.is_completed() { "" } else { " (intercepted)" }
+        );
+    }
+    for network in &networks {
+        println!();
+        print_network(network);
+    }
+
+    if let Some(path) = &args.json {
+        let graph = to_graph(&report, &networks, log_time);
+        std::fs::write(path, serde_json::to_string_pretty(&graph)?)?;
+    }
+    if let Some(path) = &args.svg {
+        std::fs::write(path, drawing::draw_networks(report.field_size, &networks))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::io::BufRead;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+pub const HELP: &str = "Commands:
+  p         pause / resume
+  s         step one vision frame (while paused)
+  n         jump to the next referee command
+  +N / -N   jump forward / back N seconds
+  x N       set playback speed to N
+  q         quit
+  h         show this help";
+
+const DEFAULT_JUMP_SECONDS: f64 = 10.0;
+
+#[derive(Clone, Copy, Debug)]
+pub enum PlayerCommand {
+    TogglePause,
+    Step,
+    NextRefereeCommand,
+    Jump(f64),
+    SetSpeed(f64),
+    Quit,
+    Help,
+}
+
+fn parse_command(line: &str) -> Result<PlayerCommand, String> {
+    let line = line.trim();
+    let parse_number = |s: &str| {
+        s.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid number: {}", s.trim()))
+    };
+    match line {
+        "p" | "pause" => Ok(PlayerCommand::TogglePause),
+        "s" | "step" => Ok(PlayerCommand::Step),
+        "n" | "next" => Ok(PlayerCommand::NextRefereeCommand),
+        "q" | "quit" => Ok(PlayerCommand::Quit),
+        "h" | "help" | "?" => Ok(PlayerCommand::Help),
+        "+" => Ok(PlayerCommand::Jump(DEFAULT_JUMP_SECONDS)),
+        "-" => Ok(PlayerCommand::Jump(-DEFAULT_JUMP_SECONDS)),
+        _ => {
+            if let Some(seconds) = line.strip_prefix('+') {
+                Ok(PlayerCommand::Jump(parse_number(seconds)?))
+            } else if let Some(seconds) = line.strip_prefix('-') {
+                Ok(PlayerCommand::Jump(-parse_number(seconds)?))
+            } else if let Some(speed) = line.strip_prefix('x') {
+                let speed = parse_number(speed)?;
+                if speed <= 0.0 || !speed.is_finite() {
+                    return Err("Playback speed must be a positive number".to_string());
+                }
+                Ok(PlayerCommand::SetSpeed(speed))
+            } else {
+                Err(format!("Unknown command: {}", line))
+            }
+        }
+    }
+}
+
+/// Reads commands from stdin on a background thread, one command per line
+pub fn spawn_command_reader(sender: Sender<PlayerCommand>) {
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_command(&line) {
+                Ok(command) => {
+                    if sender.send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    });
+}
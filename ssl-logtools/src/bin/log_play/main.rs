@@ -0,0 +1,107 @@
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use socket2::{Domain, Protocol, Socket, Type};
+use ssl_loglib::raw::MessageType;
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::sync::mpsc;
+use std::time::Duration;
+
+mod commands;
+mod player;
+
+#[derive(Parser)]
+#[command(version)]
+/// Replay a game log onto the network in real time
+///
+/// While playing, type commands followed by Enter to control playback. Type 'h' for a list of commands.
+struct Args {
+    log_path: String,
+    #[arg(long, default_value = "224.5.23.2:10006")]
+    vision_address: SocketAddrV4,
+    #[arg(long, default_value = "224.5.23.2:10010")]
+    tracker_address: SocketAddrV4,
+    #[arg(long, default_value = "224.5.23.1:10003")]
+    referee_address: SocketAddrV4,
+    #[arg(short, long, default_value = "0.0.0.0")]
+    /// Address of the network interface to send multicast packets on
+    interface: Ipv4Addr,
+    #[arg(short = 'x', long, default_value_t = 1.0)]
+    /// Playback speed factor
+    speed: f64,
+    #[arg(short, long)]
+    /// Start time in seconds from the beginning of the log
+    start_time: Option<f64>,
+    #[arg(short, long)]
+    /// End time in seconds from the beginning of the log
+    end_time: Option<f64>,
+    #[arg(short, long = "loop")]
+    /// Restart playback from the start time when the end is reached
+    loop_playback: bool,
+    #[arg(long)]
+    /// Do not send vision messages
+    no_vision: bool,
+    #[arg(long)]
+    /// Do not send tracker messages
+    no_tracker: bool,
+    #[arg(long)]
+    /// Do not send referee messages
+    no_referee: bool,
+}
+
+fn open_multicast_socket(interface: Ipv4Addr) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_multicast_if_v4(&interface)?;
+    socket.set_multicast_loop_v4(true)?;
+    Ok(socket.into())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    if args.speed <= 0.0 || !args.speed.is_finite() {
+        return Err(anyhow::Error::msg("Playback speed must be a positive number"));
+    }
+
+    let mut destinations = HashMap::new();
+    if !args.no_vision {
+        destinations.insert(MessageType::Vision2014, args.vision_address);
+    }
+    if !args.no_tracker {
+        destinations.insert(MessageType::VisionTracker2020, args.tracker_address);
+    }
+    if !args.no_referee {
+        destinations.insert(MessageType::Refbox2013, args.referee_address);
+    }
+    if destinations.is_empty() {
+        return Err(anyhow::Error::msg("All message types are disabled"));
+    }
+
+    let socket = open_multicast_socket(args.interface)?;
+    let options = player::PlayerOptions {
+        speed: args.speed,
+        start_time: args.start_time,
+        end_time: args.end_time,
+        loop_playback: args.loop_playback,
+    };
+    let mut player = player::Player::new(&args.log_path, socket, destinations, options)?;
+    if player.is_indexed() {
+        eprintln!("Using log index for seeking.");
+    }
+    eprintln!("Type 'h' and press Enter for playback commands.");
+
+    let (command_sender, command_receiver) = mpsc::channel();
+    commands::spawn_command_reader(command_sender);
+
+    let status = ProgressBar::new_spinner();
+    status.set_style(ProgressStyle::with_template("{msg}")?);
+    status.enable_steady_tick(Duration::from_millis(100));
+
+    player.run(command_receiver, &status)?;
+
+    status.finish_and_clear();
+    eprintln!("Sent {} messages.", player.messages_sent());
+
+    Ok(())
+}
@@ -0,0 +1,496 @@
+use crate::commands::{HELP, PlayerCommand};
+use indicatif::ProgressBar;
+use protobuf::Message;
+use ssl_loglib::index::IndexMessage;
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::Referee;
+use ssl_loglib::raw::{MessageType, RawMessage};
+use ssl_loglib::seconds_to_nanos;
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddrV4, UdpSocket};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest time to wait before checking for commands and refreshing the status line
+const STATUS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How far before an indexed seek target to start reading, so the referee state is current after seeking
+const SEEK_LOOKBACK_NANOS: i64 = 1_000_000_000;
+
+pub struct PlayerOptions {
+    pub speed: f64,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    pub loop_playback: bool,
+}
+
+pub struct Player {
+    reader: LogFileReader,
+    index: Option<IndexMessage>,
+    socket: UdpSocket,
+    destinations: HashMap<MessageType, SocketAddrV4>,
+    speed: f64,
+    paused: bool,
+    loop_playback: bool,
+    log_start: i64,
+    range_start: i64,
+    range_end: i64,
+    next_message: Option<RawMessage>,
+    /// Wall clock time and log timestamp that playback timing is measured from
+    clock_anchor: Option<(Instant, i64)>,
+    current_time: i64,
+    referee: Option<Referee>,
+    sent: usize,
+}
+
+impl Player {
+    pub fn new(
+        log_path: &str,
+        socket: UdpSocket,
+        destinations: HashMap<MessageType, SocketAddrV4>,
+        options: PlayerOptions,
+    ) -> io::Result<Self> {
+        let mut reader = LogFileReader::new(log_path)?;
+        let index = if reader.is_indexed().unwrap_or(false) {
+            reader.read_index().ok()
+        } else {
+            None
+        };
+        let log_start = match reader.get_next_raw_message() {
+            Ok(message) => message.timestamp,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} does not contain any messages", log_path),
+                ));
+            }
+            Err(e) => return Err(e),
+        };
+        reader.rewind()?;
+
+        let range_start = log_start + options.start_time.map_or(0, seconds_to_nanos);
+        let range_end = options.end_time.map_or(i64::MAX, |t| log_start + seconds_to_nanos(t));
+
+        let mut player = Player {
+            reader,
+            index,
+            socket,
+            destinations,
+            speed: options.speed,
+            paused: false,
+            loop_playback: options.loop_playback,
+            log_start,
+            range_start,
+            range_end,
+            next_message: None,
+            clock_anchor: None,
+            current_time: log_start,
+            referee: None,
+            sent: 0,
+        };
+        player.seek_to(range_start)?;
+        Ok(player)
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.index.is_some()
+    }
+
+    pub fn messages_sent(&self) -> usize {
+        self.sent
+    }
+
+    pub fn run(&mut self, commands: Receiver<PlayerCommand>, status: &ProgressBar) -> io::Result<()> {
+        let mut commands_open = true;
+        loop {
+            status.set_message(self.status_string());
+
+            if self.next_message.is_none() {
+                self.next_message = self.read_next()?;
+            }
+            let at_end = self
+                .next_message
+                .as_ref()
+                .is_none_or(|m| m.timestamp > self.range_end);
+
+            if at_end && !self.paused {
+                if self.loop_playback {
+                    self.seek_to(self.range_start)?;
+                    continue;
+                }
+                break;
+            }
+
+            let wait = match &self.next_message {
+                Some(message) if !self.paused && !at_end => {
+                    let (wall_anchor, log_anchor) =
+                        *self.clock_anchor.get_or_insert((Instant::now(), message.timestamp));
+                    let offset = (message.timestamp - log_anchor).max(0) as f64 / 1.0e9 / self.speed;
+                    let send_time = wall_anchor + Duration::from_secs_f64(offset);
+                    let now = Instant::now();
+                    if send_time <= now {
+                        if let Some(message) = self.next_message.take() {
+                            self.send(message)?;
+                        }
+                        continue;
+                    }
+                    (send_time - now).min(STATUS_INTERVAL)
+                }
+                _ => STATUS_INTERVAL,
+            };
+
+            if !commands_open {
+                thread::sleep(wait);
+                continue;
+            }
+            match commands.recv_timeout(wait) {
+                Ok(PlayerCommand::Quit) => break,
+                Ok(command) => self.handle_command(command, status)?,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => commands_open = false,
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_command(&mut self, command: PlayerCommand, status: &ProgressBar) -> io::Result<()> {
+        match command {
+            PlayerCommand::TogglePause => {
+                self.paused = !self.paused;
+                self.clock_anchor = Some((Instant::now(), self.current_time));
+            }
+            PlayerCommand::Step => {
+                if self.paused {
+                    self.step()?;
+                } else {
+                    status.println("Pause playback before stepping.");
+                }
+            }
+            PlayerCommand::NextRefereeCommand => self.jump_to_next_referee_command()?,
+            PlayerCommand::Jump(seconds) => {
+                self.seek_to(self.current_time + seconds_to_nanos(seconds))?;
+            }
+            PlayerCommand::SetSpeed(speed) => {
+                self.speed = speed;
+                self.clock_anchor = Some((Instant::now(), self.current_time));
+            }
+            PlayerCommand::Help => status.println(HELP),
+            PlayerCommand::Quit => {}
+        }
+        Ok(())
+    }
+
+    fn read_next(&mut self) -> io::Result<Option<RawMessage>> {
+        loop {
+            match self.reader.get_next_raw_message() {
+                Ok(m) if m.message_type == MessageType::Index2021 => continue,
+                Ok(m) => return Ok(Some(m)),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn take_next(&mut self) -> io::Result<Option<RawMessage>> {
+        match self.next_message.take() {
+            Some(m) => Ok(Some(m)),
+            None => self.read_next(),
+        }
+    }
+
+    /// Updates the playback position and referee state with a message that was sent or skipped over
+    fn observe(&mut self, message: &RawMessage) -> io::Result<()> {
+        self.current_time = message.timestamp;
+        if message.message_type == MessageType::Refbox2013 {
+            self.referee = Some(Referee::parse_from_bytes(&message.data)?);
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, message: RawMessage) -> io::Result<()> {
+        self.observe(&message)?;
+        if let Some(destination) = self.destinations.get(&message.message_type) {
+            self.socket.send_to(&message.data, destination)?;
+            self.sent += 1;
+        }
+        Ok(())
+    }
+
+    /// Moves playback to the first message at or after `timestamp`, using the log index if there is one
+    fn seek_to(&mut self, timestamp: i64) -> io::Result<()> {
+        let target = timestamp.clamp(self.range_start, self.range_end);
+        if let Some(index) = &self.index {
+            self.reader.seek_to_timestamp(index, target - SEEK_LOOKBACK_NANOS)?;
+            self.next_message = None;
+            self.referee = None;
+        } else if target < self.current_time {
+            self.reader.rewind()?;
+            self.next_message = None;
+            self.referee = None;
+        }
+
+        loop {
+            match self.take_next()? {
+                Some(message) if message.timestamp < target => self.observe(&message)?,
+                next => {
+                    self.next_message = next;
+                    break;
+                }
+            }
+        }
+        self.current_time = target;
+        self.clock_anchor = None;
+        Ok(())
+    }
+
+    /// Sends messages up to and including the next vision frame
+    fn step(&mut self) -> io::Result<()> {
+        while let Some(message) = self.take_next()? {
+            if message.timestamp > self.range_end {
+                self.next_message = Some(message);
+                break;
+            }
+            let is_vision = matches!(message.message_type, MessageType::Vision2014 | MessageType::Vision2010);
+            self.send(message)?;
+            if is_vision {
+                break;
+            }
+        }
+        self.clock_anchor = Some((Instant::now(), self.current_time));
+        Ok(())
+    }
+
+    /// Skips ahead to the next referee message with a new command
+    fn jump_to_next_referee_command(&mut self) -> io::Result<()> {
+        let current_counter = self.referee.as_ref().map(|r| r.command_counter());
+        while let Some(message) = self.take_next()? {
+            if message.timestamp > self.range_end {
+                self.next_message = Some(message);
+                break;
+            }
+            if message.message_type == MessageType::Refbox2013 {
+                let referee = Referee::parse_from_bytes(&message.data)?;
+                if current_counter != Some(referee.command_counter()) {
+                    self.observe(&message)?;
+                    self.next_message = Some(message);
+                    break;
+                }
+            }
+            self.observe(&message)?;
+        }
+        self.clock_anchor = None;
+        Ok(())
+    }
+
+    fn status_string(&self) -> String {
+        let log_time = (self.current_time - self.log_start) as f64 / 1.0e9;
+        let match_state = match &self.referee {
+            Some(referee) => {
+                let stage_time = match referee.stage_time_left {
+                    Some(micros) => {
+                        let seconds = micros / 1_000_000;
+                        format!(
+                            " {}{:0>2}:{:0>2}",
+                            if micros < 0 { "-" } else { "" },
+                            (seconds / 60).abs(),
+                            (seconds % 60).abs()
+                        )
+                    }
+                    None => "".to_string(),
+                };
+                format!("{:?}{} | {:?}", referee.stage(), stage_time, referee.command())
+            }
+            None => "No referee data".to_string(),
+        };
+        format!(
+            "Log time {:>8.1}s | {} | x{}{}",
+            log_time,
+            match_state,
+            self.speed,
+            if self.paused { " | PAUSED" } else { "" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+        use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use ssl_loglib::test_util::{START, referee, write_raw_log};
+    use std::net::Ipv4Addr;
+    use std::path::{Path, PathBuf};
+
+    fn at_millis(millis: i64) -> i64 {
+        START + millis * 1_000_000
+    }
+
+    fn referee_message(millis: i64, command_counter: u32) -> RawMessage {
+        let referee = referee(millis as f64 / 1000.0, Stage::NORMAL_FIRST_HALF, Command::FORCE_START, command_counter);
+        RawMessage {
+            timestamp: at_millis(millis),
+            message_type: MessageType::Refbox2013,
+            data: referee.write_to_bytes().unwrap(),
+        }
+    }
+
+    fn vision_message(millis: i64, message_type: MessageType) -> RawMessage {
+        RawMessage {
+            timestamp: at_millis(millis),
+            message_type,
+            data: Vec::new(),
+        }
+    }
+
+    /// Writes a log with a referee message every 100 ms for `seconds` seconds and a new command every second
+    fn write_referee_log(dir: &Path, seconds: i64, indexed: bool) -> PathBuf {
+        let messages: Vec<RawMessage> = (0..seconds * 1000)
+            .step_by(100)
+            .map(|millis| referee_message(millis, (millis / 1000) as u32 + 1))
+            .collect();
+        write_raw_log(dir, "game.log", &messages, indexed)
+    }
+
+    fn player(path: &Path, start_time: Option<f64>, end_time: Option<f64>) -> io::Result<Player> {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let options = PlayerOptions {
+            speed: 1.0,
+            start_time,
+            end_time,
+            loop_playback: false,
+        };
+        Player::new(path.to_str().unwrap(), socket, HashMap::new(), options)
+    }
+
+    #[test]
+    fn empty_log_is_a_descriptive_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_referee_log(dir.path(), 0, false);
+
+        let error = player(&path, None, None).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("does not contain any messages"));
+    }
+
+    fn command_counter(player: &Player) -> Option<u32> {
+        player.referee.as_ref().map(|r| r.command_counter())
+    }
+
+    fn next_timestamp(player: &Player) -> Option<i64> {
+        player.next_message.as_ref().map(|m| m.timestamp)
+    }
+
+    #[test]
+    fn seek_without_index_reads_forward_and_rewinds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_referee_log(dir.path(), 3, false);
+        let mut player = player(&path, None, None).unwrap();
+        assert!(!player.is_indexed());
+
+        player.seek_to(at_millis(1550)).unwrap();
+        assert_eq!(player.current_time, at_millis(1550));
+        assert_eq!(next_timestamp(&player), Some(at_millis(1600)));
+        assert_eq!(command_counter(&player), Some(2));
+
+        player.seek_to(at_millis(250)).unwrap();
+        assert_eq!(next_timestamp(&player), Some(at_millis(300)));
+        assert_eq!(command_counter(&player), Some(1));
+    }
+
+    #[test]
+    fn seek_with_index_restores_referee_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_referee_log(dir.path(), 4, true);
+        let mut player = player(&path, None, None).unwrap();
+        assert!(player.is_indexed());
+
+        player.seek_to(at_millis(2500)).unwrap();
+        assert_eq!(next_timestamp(&player), Some(at_millis(2500)));
+        assert_eq!(command_counter(&player), Some(3));
+
+        player.seek_to(at_millis(1000)).unwrap();
+        assert_eq!(next_timestamp(&player), Some(at_millis(1000)));
+        assert_eq!(command_counter(&player), Some(1));
+    }
+
+    #[test]
+    fn seek_is_limited_to_the_playback_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_referee_log(dir.path(), 4, false);
+        let mut player = player(&path, Some(1.0), Some(2.0)).unwrap();
+        assert_eq!(next_timestamp(&player), Some(at_millis(1000)));
+
+        player.seek_to(at_millis(5000)).unwrap();
+        assert_eq!(player.current_time, at_millis(2000));
+        assert_eq!(next_timestamp(&player), Some(at_millis(2000)));
+
+        player.seek_to(at_millis(0)).unwrap();
+        assert_eq!(player.current_time, at_millis(1000));
+        assert_eq!(next_timestamp(&player), Some(at_millis(1000)));
+    }
+
+    #[test]
+    fn seek_with_index_clears_referee_state_before_the_first_referee_message() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut messages: Vec<RawMessage> = (0..4000)
+            .step_by(100)
+            .map(|millis| vision_message(millis, MessageType::Vision2014))
+            .collect();
+        messages.insert(26, referee_message(2550, 1));
+        let path = write_raw_log(dir.path(), "game.log", &messages, true);
+        let mut player = player(&path, None, None).unwrap();
+
+        player.seek_to(at_millis(3000)).unwrap();
+        assert_eq!(command_counter(&player), Some(1));
+
+        player.seek_to(at_millis(500)).unwrap();
+        assert_eq!(command_counter(&player), None);
+    }
+
+    #[test]
+    fn step_sends_messages_up_to_the_next_vision_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let messages = [
+            referee_message(0, 1),
+            vision_message(10, MessageType::Vision2010),
+            referee_message(20, 1),
+            vision_message(30, MessageType::Vision2014),
+            referee_message(40, 2),
+        ];
+        let path = write_raw_log(dir.path(), "game.log", &messages, false);
+        let mut player = player(&path, None, Some(0.035)).unwrap();
+
+        player.step().unwrap();
+        assert_eq!(player.current_time, at_millis(10));
+        player.step().unwrap();
+        assert_eq!(player.current_time, at_millis(30));
+
+        // The last referee message is after the end of the playback range
+        player.step().unwrap();
+        assert_eq!(player.current_time, at_millis(30));
+        assert_eq!(next_timestamp(&player), Some(at_millis(40)));
+        assert_eq!(command_counter(&player), Some(1));
+    }
+
+    #[test]
+    fn jump_stops_at_the_next_referee_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_referee_log(dir.path(), 3, false);
+        let mut player = player(&path, None, Some(1.55)).unwrap();
+
+        player.jump_to_next_referee_command().unwrap();
+        assert_eq!(next_timestamp(&player), Some(at_millis(0)));
+        assert_eq!(command_counter(&player), Some(1));
+
+        player.jump_to_next_referee_command().unwrap();
+        assert_eq!(next_timestamp(&player), Some(at_millis(1000)));
+        assert_eq!(command_counter(&player), Some(2));
+
+        // The next command starts after the end of the playback range
+        player.jump_to_next_referee_command().unwrap();
+        assert_eq!(player.current_time, at_millis(1500));
+        assert_eq!(next_timestamp(&player), Some(at_millis(1600)));
+        assert_eq!(command_counter(&player), Some(2));
+    }
+}
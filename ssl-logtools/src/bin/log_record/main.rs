@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use protobuf::Message;
+use ssl_loglib::log_file_writer::LogFileWriter;
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::Referee;
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+use ssl_loglib::raw::{MessageType, RawMessage};
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+mod receiver;
+
+#[derive(Parser)]
+#[command(version)]
+/// Record SSL vision, tracker, and referee multicast traffic to log files
+struct Args {
+    #[arg(default_value_t = (".").to_string())]
+    /// Directory to write log files to
+    output_dir: String,
+    #[arg(long, default_value = "224.5.23.2:10006")]
+    vision_address: SocketAddrV4,
+    #[arg(long, default_value = "224.5.23.2:10010")]
+    tracker_address: SocketAddrV4,
+    #[arg(long, default_value = "224.5.23.1:10003")]
+    referee_address: SocketAddrV4,
+    #[arg(short, long, default_value = "0.0.0.0")]
+    /// Address of the network interface to join multicast groups on
+    interface: Ipv4Addr,
+    #[arg(short, long)]
+    /// Only record while a game is running
+    games_only: bool,
+}
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tracks whether a game is running based on referee messages
+#[derive(Default)]
+struct GameTracker {
+    in_game: bool,
+    last_stage: Option<Stage>,
+}
+
+impl GameTracker {
+    /// Returns true if this message starts or stops a game
+    fn update(&mut self, referee: &Referee) -> bool {
+        let stage = referee.stage();
+        let restarted = stage == Stage::NORMAL_FIRST_HALF_PRE
+            && self.last_stage.is_some_and(|s| s != Stage::NORMAL_FIRST_HALF_PRE);
+        self.last_stage = Some(stage);
+
+        let in_game = if self.in_game {
+            stage != Stage::POST_GAME && !restarted
+        } else {
+            match stage {
+                Stage::POST_GAME => false,
+                Stage::NORMAL_FIRST_HALF_PRE => referee.command() != Command::HALT,
+                _ => true,
+            }
+        };
+        let changed = in_game != self.in_game;
+        self.in_game = in_game;
+        changed
+    }
+}
+
+fn sanitize_team_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn log_file_path(output_dir: &str, start_time: DateTime<Utc>, referee: Option<&Referee>) -> PathBuf {
+    let time_string = start_time.format("%Y-%m-%d_%H-%M-%S").to_string();
+    let file_name = match referee {
+        Some(r) => format!(
+            "{}_{}-vs-{}.log",
+            time_string,
+            sanitize_team_name(r.yellow.name()),
+            sanitize_team_name(r.blue.name())
+        ),
+        None => format!("{}.log", time_string),
+    };
+    PathBuf::from(output_dir).join(file_name)
+}
+
+fn open_log(path: PathBuf) -> anyhow::Result<LogFileWriter> {
+    eprintln!("Recording to {}", path.display());
+    Ok(LogFileWriter::new(path)?)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    std::fs::create_dir_all(&args.output_dir)?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        ctrlc::set_handler(move || running.store(false, Ordering::SeqCst))?;
+    }
+
+    let (sender, messages) = mpsc::channel::<RawMessage>();
+    // Packets are classified by content, so groups shared by several message types only need one socket
+    let mut addresses = vec![args.vision_address, args.tracker_address, args.referee_address];
+    addresses.sort();
+    addresses.dedup();
+    for address in addresses {
+        let socket = receiver::open_multicast_socket(address, args.interface)?;
+        eprintln!("Listening on {}", address);
+        receiver::spawn_receiver(socket, address, sender.clone())?;
+    }
+    drop(sender);
+
+    let mut game_tracker = GameTracker::default();
+    let mut writer = if args.games_only {
+        None
+    } else {
+        Some(open_log(log_file_path(&args.output_dir, Utc::now(), None))?)
+    };
+    let mut last_flush = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        let message = match messages.recv_timeout(FLUSH_INTERVAL) {
+            Ok(m) => Some(m),
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+
+        if let Some(message) = message {
+            let mut game_referee = None;
+            if message.message_type == MessageType::Refbox2013 {
+                let referee = Referee::parse_from_bytes(&message.data)?;
+                if game_tracker.update(&referee) {
+                    game_referee = Some(referee);
+                }
+            }
+
+            // A new game's log starts with the referee message that started the game
+            if let Some(referee) = &game_referee
+                && game_tracker.in_game
+            {
+                eprintln!("Game started.");
+                if let Some(mut w) = writer.take() {
+                    w.flush()?;
+                }
+                writer = Some(open_log(log_file_path(&args.output_dir, Utc::now(), Some(referee)))?);
+            }
+
+            if let Some(w) = writer.as_mut() {
+                w.write_raw_message(&message)?;
+            }
+
+            // A game's log ends with the referee message that stopped the game
+            if game_referee.is_some() && !game_tracker.in_game {
+                eprintln!("Game stopped.");
+                if let Some(mut w) = writer.take() {
+                    w.flush()?;
+                }
+                if !args.games_only {
+                    writer = Some(open_log(log_file_path(&args.output_dir, Utc::now(), None))?);
+                }
+            }
+        }
+
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            if let Some(w) = writer.as_mut() {
+                w.flush()?;
+            }
+            last_flush = Instant::now();
+        }
+    }
+
+    if let Some(mut w) = writer {
+        w.flush()?;
+    }
+    eprintln!("Recording stopped.");
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use chrono::Utc;
+use protobuf::Message;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::Referee;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use ssl_loglib::raw::{MessageType, RawMessage};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddrV4, UdpSocket};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// How often each receiver reports the packets it dropped
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+pub fn open_multicast_socket(address: SocketAddrV4, interface: Ipv4Addr) -> anyhow::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SockAddr::from(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, address.port())))?;
+    socket.join_multicast_v4(address.ip(), &interface)?;
+    Ok(socket.into())
+}
+
+/// Finds the message type a packet decodes as, regardless of the group it was received on
+///
+/// Referee messages have the most required fields and vision packets the fewest, so the types are tried in that
+/// order. Tracker and vision packets only count if they contain a frame or geometry, since an unrelated packet can
+/// easily decode as a message with only optional fields.
+pub fn classify_packet(data: &[u8]) -> Option<MessageType> {
+    if Referee::parse_from_bytes(data).is_ok() {
+        return Some(MessageType::Refbox2013);
+    }
+    if TrackerWrapperPacket::parse_from_bytes(data).is_ok_and(|packet| packet.tracked_frame.is_some()) {
+        return Some(MessageType::VisionTracker2020);
+    }
+    if SSL_WrapperPacket::parse_from_bytes(data)
+        .is_ok_and(|packet| packet.detection.is_some() || packet.geometry.is_some())
+    {
+        return Some(MessageType::Vision2014);
+    }
+    None
+}
+
+/// Counts dropped packets and reports them at most once per [`DROP_REPORT_INTERVAL`]
+struct DropCounter {
+    address: SocketAddrV4,
+    invalid: usize,
+    receive_errors: usize,
+    last_error: Option<io::Error>,
+    last_report: Instant,
+}
+
+impl DropCounter {
+    fn new(address: SocketAddrV4) -> Self {
+        DropCounter {
+            address,
+            invalid: 0,
+            receive_errors: 0,
+            last_error: None,
+            last_report: Instant::now(),
+        }
+    }
+
+    fn report_if_due(&mut self) {
+        if self.last_report.elapsed() < DROP_REPORT_INTERVAL {
+            return;
+        }
+        if self.invalid > 0 {
+            eprintln!(
+                "Dropped {} packets on {} that are not SSL vision, tracker, or referee messages",
+                self.invalid, self.address
+            );
+        }
+        if let Some(error) = self.last_error.take() {
+            eprintln!("Failed to receive {} packets on {}: {}", self.receive_errors, self.address, error);
+        }
+        self.invalid = 0;
+        self.receive_errors = 0;
+        self.last_report = Instant::now();
+    }
+}
+
+/// Receives packets on a background thread, forwarding SSL messages with their type and receive timestamp
+pub fn spawn_receiver(
+    socket: UdpSocket,
+    address: SocketAddrV4,
+    sender: Sender<RawMessage>,
+) -> io::Result<thread::JoinHandle<()>> {
+    // Wake up regularly so dropped packets are reported even when no more packets arrive
+    socket.set_read_timeout(Some(DROP_REPORT_INTERVAL))?;
+    Ok(thread::spawn(move || {
+        let mut buffer = vec![0; MAX_PACKET_SIZE];
+        let mut drops = DropCounter::new(address);
+        loop {
+            drops.report_if_due();
+            let size = match socket.recv(&mut buffer) {
+                Ok(size) => size,
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+                Err(e) => {
+                    drops.receive_errors += 1;
+                    drops.last_error = Some(e);
+                    continue;
+                }
+            };
+            let timestamp = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+            let data = &buffer[..size];
+            let Some(message_type) = classify_packet(data) else {
+                drops.invalid += 1;
+                continue;
+            };
+            let message = RawMessage {
+                timestamp,
+                message_type,
+                data: data.to_vec(),
+            };
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::{Command, Stage};
+    use ssl_loglib::test_util::{detection, referee, tracked};
+    use std::sync::mpsc;
+
+    fn referee_bytes() -> Vec<u8> {
+        referee(0.0, Stage::NORMAL_FIRST_HALF_PRE, Command::HALT, 1).write_to_bytes().unwrap()
+    }
+
+    fn tracker_bytes() -> Vec<u8> {
+        tracked("Tracker", 1, 1.0, None, &[]).write_to_bytes().unwrap()
+    }
+
+    fn vision_bytes() -> Vec<u8> {
+        detection(0, 1, 1.0, &[], &[]).write_to_bytes().unwrap()
+    }
+
+    #[test]
+    fn packets_are_classified_by_content() {
+        assert_eq!(classify_packet(&referee_bytes()), Some(MessageType::Refbox2013));
+        assert_eq!(classify_packet(&tracker_bytes()), Some(MessageType::VisionTracker2020));
+        assert_eq!(classify_packet(&vision_bytes()), Some(MessageType::Vision2014));
+        assert_eq!(classify_packet(b""), None);
+        assert_eq!(classify_packet(b"not a protobuf message"), None);
+    }
+
+    #[test]
+    fn receives_loopback_multicast() {
+        let group = SocketAddrV4::new(Ipv4Addr::new(224, 5, 23, 42), 41042);
+        let socket = open_multicast_socket(group, Ipv4Addr::LOCALHOST).unwrap();
+        let (sender, messages) = mpsc::channel();
+        spawn_receiver(socket, group, sender).unwrap();
+
+        let send_socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP)).unwrap();
+        send_socket.set_multicast_if_v4(&Ipv4Addr::LOCALHOST).unwrap();
+        send_socket.set_multicast_loop_v4(true).unwrap();
+        let send_socket: UdpSocket = send_socket.into();
+        // All types are sent to one group to check that they are told apart by content
+        for data in [referee_bytes(), b"garbage".to_vec(), tracker_bytes(), vision_bytes()] {
+            send_socket.send_to(&data, group).unwrap();
+        }
+
+        let received: Vec<MessageType> = (0..3)
+            .map(|_| messages.recv_timeout(Duration::from_secs(5)).unwrap().message_type)
+            .collect();
+        assert_eq!(
+            received,
+            [MessageType::Refbox2013, MessageType::VisionTracker2020, MessageType::Vision2014]
+        );
+    }
+}
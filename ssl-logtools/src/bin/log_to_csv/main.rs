@@ -0,0 +1,162 @@
+use clap::Parser;
+use protobuf::Enum;
+use serde::Serialize;
+use ssl_loglib::MessageBody;
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::referee::Stage;
+use ssl_loglib::tracker::{source_label, team_name};
+use std::path::Path;
+
+#[derive(Parser)]
+#[command(version)]
+/// Export tracked robot, ball, and referee data from a game log to CSV files
+struct Args {
+    log_path: String,
+    #[arg(default_value_t = (".").to_string())]
+    /// Directory to write robots.csv, balls.csv, and referee.csv to
+    output_dir: String,
+    #[arg(short, long)]
+    /// Start time in seconds from the beginning of the log
+    start_time: Option<f64>,
+    #[arg(short, long)]
+    /// End time in seconds from the beginning of the log
+    end_time: Option<f64>,
+    #[arg(long, value_parser = parse_stage)]
+    /// Only export data from the given referee stage (ex. NORMAL_SECOND_HALF)
+    stage: Option<Stage>,
+}
+
+fn parse_stage(s: &str) -> Result<Stage, String> {
+    Stage::from_str(s).ok_or(format!("Unknown stage: {}", s))
+}
+
+#[derive(Serialize)]
+struct RobotRow<'a> {
+    log_time: f64,
+    frame_timestamp: f64,
+    source: &'a str,
+    team: &'static str,
+    id: u32,
+    pos_x: f32,
+    pos_y: f32,
+    vel_x: Option<f32>,
+    vel_y: Option<f32>,
+    orientation: f32,
+    vel_angular: Option<f32>,
+    visibility: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct BallRow<'a> {
+    log_time: f64,
+    frame_timestamp: f64,
+    source: &'a str,
+    index: usize,
+    pos_x: f32,
+    pos_y: f32,
+    pos_z: f32,
+    vel_x: Option<f32>,
+    vel_y: Option<f32>,
+    vel_z: Option<f32>,
+    visibility: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct RefereeRow {
+    log_time: f64,
+    stage: String,
+    stage_time_left: Option<f64>,
+    command: String,
+    command_counter: u32,
+    yellow_score: u32,
+    blue_score: u32,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let reader = LogFileReader::new(&args.log_path)?;
+
+    let output_dir = Path::new(&args.output_dir);
+    std::fs::create_dir_all(output_dir)?;
+    let mut robots_csv = csv::Writer::from_path(output_dir.join("robots.csv"))?;
+    let mut balls_csv = csv::Writer::from_path(output_dir.join("balls.csv"))?;
+    let mut referee_csv = csv::Writer::from_path(output_dir.join("referee.csv"))?;
+
+    let mut first_timestamp = None;
+    let mut current_stage = None;
+    for message in reader {
+        let first_timestamp = *first_timestamp.get_or_insert(message.timestamp);
+        let log_time = (message.timestamp - first_timestamp).as_seconds_f64();
+        if args.end_time.is_some_and(|t| log_time > t) {
+            break;
+        }
+        if let MessageBody::Refbox2013(referee) = &message.body {
+            current_stage = Some(referee.stage());
+        }
+        if args.start_time.is_some_and(|t| log_time < t) {
+            continue;
+        }
+        if args.stage.is_some() && current_stage != args.stage {
+            continue;
+        }
+
+        match &message.body {
+            MessageBody::Refbox2013(referee) => {
+                referee_csv.serialize(RefereeRow {
+                    log_time,
+                    stage: format!("{:?}", referee.stage()),
+                    stage_time_left: referee.stage_time_left.map(|t| t as f64 / 1.0e6),
+                    command: format!("{:?}", referee.command()),
+                    command_counter: referee.command_counter(),
+                    yellow_score: referee.yellow.score(),
+                    blue_score: referee.blue.score(),
+                })?;
+            }
+            MessageBody::VisionTracker2020(packet) => {
+                let Some(frame) = packet.tracked_frame.as_ref() else {
+                    continue;
+                };
+                let source = source_label(packet);
+                for robot in &frame.robots {
+                    robots_csv.serialize(RobotRow {
+                        log_time,
+                        frame_timestamp: frame.timestamp(),
+                        source,
+                        team: team_name(robot.robot_id.team_color()),
+                        id: robot.robot_id.id(),
+                        pos_x: robot.pos.x(),
+                        pos_y: robot.pos.y(),
+                        vel_x: robot.vel.as_ref().map(|v| v.x()),
+                        vel_y: robot.vel.as_ref().map(|v| v.y()),
+                        orientation: robot.orientation(),
+                        vel_angular: robot.vel_angular,
+                        visibility: robot.visibility,
+                    })?;
+                }
+                for (index, ball) in frame.balls.iter().enumerate() {
+                    balls_csv.serialize(BallRow {
+                        log_time,
+                        frame_timestamp: frame.timestamp(),
+                        source,
+                        index,
+                        pos_x: ball.pos.x(),
+                        pos_y: ball.pos.y(),
+                        pos_z: ball.pos.z(),
+                        vel_x: ball.vel.as_ref().map(|v| v.x()),
+                        vel_y: ball.vel.as_ref().map(|v| v.y()),
+                        vel_z: ball.vel.as_ref().map(|v| v.z()),
+                        visibility: ball.visibility,
+                    })?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    robots_csv.flush()?;
+    balls_csv.flush()?;
+    referee_csv.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use clap::Parser;
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::raw::MessageType;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+#[derive(Parser)]
+#[command(version)]
+/// Convert a game log to JSON Lines, with one JSON object per message
+struct Args {
+    log_path: String,
+    #[arg(short, long)]
+    /// Path to write JSON Lines output to. If not set, output is written to stdout.
+    output_path: Option<String>,
+    #[arg(short, long, value_delimiter = ',')]
+    /// Only export messages of these types, by name or type ID (ex. Refbox2013,VisionTracker2020)
+    types: Vec<MessageType>,
+    #[arg(short, long)]
+    /// Start time in seconds from the beginning of the log
+    start_time: Option<f64>,
+    #[arg(short, long)]
+    /// End time in seconds from the beginning of the log
+    end_time: Option<f64>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut reader = LogFileReader::new(&args.log_path)?;
+
+    let mut output: Box<dyn Write> = match &args.output_path {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+
+    let mut first_timestamp = None;
+    loop {
+        let message = match reader.get_next_message() {
+            Ok(message) => message,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        let first_timestamp = *first_timestamp.get_or_insert(message.timestamp);
+        let log_time = (message.timestamp - first_timestamp).as_seconds_f64();
+        if args.start_time.is_some_and(|t| log_time < t) {
+            continue;
+        }
+        if args.end_time.is_some_and(|t| log_time > t) {
+            break;
+        }
+        let message_type = message.body.message_type();
+        // Index offsets point into this log file, so the index is only exported when asked for explicitly
+        if args.types.is_empty() && message_type == MessageType::Index2021 {
+            continue;
+        }
+        if !args.types.is_empty() && !args.types.contains(&message_type) {
+            continue;
+        }
+        serde_json::to_writer(&mut output, &message)?;
+        output.write_all(b"\n")?;
+    }
+    output.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use clap::Parser;
+use mcap::records::MessageHeader;
+use mcap::{Compression, WriteOptions};
+use protobuf::MessageFull;
+use protobuf::descriptor::FileDescriptorSet;
+use protobuf::reflect::{FileDescriptor, MessageDescriptor};
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::protos::refbox::ssl_gc_referee_message::Referee;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper::SSL_WrapperPacket;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper_legacy::SSL_WrapperPacket as SSL_WrapperPacket_Legacy;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use ssl_loglib::raw::MessageType;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufWriter};
+
+#[derive(Parser)]
+#[command(version)]
+/// Convert a game log to an MCAP file for viewing in Foxglove
+struct Args {
+    log_path: String,
+    #[arg(default_value_t = ("log.mcap").to_string())]
+    output_path: String,
+}
+
+/// Message encoding of channels without a protobuf schema, whose messages are the raw payload bytes
+const RAW_ENCODING: &str = "application/octet-stream";
+
+/// Topic and protobuf message descriptor for each message type. Types without a protobuf payload have no descriptor.
+fn channel_info(message_type: MessageType) -> (String, Option<MessageDescriptor>) {
+    match message_type {
+        MessageType::Vision2010 => ("/vision_legacy".to_string(), Some(SSL_WrapperPacket_Legacy::descriptor())),
+        MessageType::Refbox2013 => ("/referee".to_string(), Some(Referee::descriptor())),
+        MessageType::Vision2014 => ("/vision".to_string(), Some(SSL_WrapperPacket::descriptor())),
+        MessageType::VisionTracker2020 => ("/tracker".to_string(), Some(TrackerWrapperPacket::descriptor())),
+        MessageType::Blank => ("/blank".to_string(), None),
+        MessageType::Unkown => ("/unknown".to_string(), None),
+        MessageType::Index2021 => ("/index".to_string(), None),
+        MessageType::Custom(type_id) => (format!("/custom/{}", type_id), None),
+    }
+}
+
+/// Adds a file and all of its dependencies to the set, dependencies first
+fn add_file_descriptor(file: &FileDescriptor, set: &mut FileDescriptorSet) {
+    if set.file.iter().any(|f| f.name() == file.proto().name()) {
+        return;
+    }
+    for dep in file.deps() {
+        add_file_descriptor(dep, set);
+    }
+    set.file.push(file.proto().clone());
+}
+
+/// Serialized FileDescriptorSet describing a message type, as MCAP expects for protobuf schemas
+fn schema_data(descriptor: &MessageDescriptor) -> protobuf::Result<Vec<u8>> {
+    let mut set = FileDescriptorSet::new();
+    add_file_descriptor(descriptor.file_descriptor(), &mut set);
+    protobuf::Message::write_to_bytes(&set)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut reader = LogFileReader::new(&args.log_path)?;
+
+    let output = BufWriter::new(File::create(&args.output_path)?);
+    let mut writer = WriteOptions::new()
+        .compression(Some(Compression::Zstd))
+        .profile("")
+        .create(output)?;
+
+    let mut channels: HashMap<MessageType, u16> = HashMap::new();
+    let mut sequences: HashMap<u16, u32> = HashMap::new();
+    let mut written = 0;
+
+    loop {
+        let message = match reader.get_next_raw_message() {
+            Ok(m) => m,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let channel_id = match channels.get(&message.message_type) {
+            Some(&id) => id,
+            None => {
+                let id = match channel_info(message.message_type) {
+                    (topic, Some(descriptor)) => {
+                        let schema_id =
+                            writer.add_schema(descriptor.full_name(), "protobuf", &schema_data(&descriptor)?)?;
+                        writer.add_channel(schema_id, &topic, "protobuf", &BTreeMap::new())?
+                    }
+                    // Schema ID 0 marks a channel without a schema
+                    (topic, None) => writer.add_channel(0, &topic, RAW_ENCODING, &BTreeMap::new())?,
+                };
+                channels.insert(message.message_type, id);
+                id
+            }
+        };
+
+        let sequence = sequences.entry(channel_id).or_default();
+        *sequence += 1;
+        let log_time = u64::try_from(message.timestamp)?;
+        writer.write_to_known_channel(
+            &MessageHeader {
+                channel_id,
+                sequence: *sequence,
+                log_time,
+                publish_time: log_time,
+            },
+            &message.data,
+        )?;
+        written += 1;
+    }
+
+    writer.finish()?;
+
+    println!("Wrote {} messages to {}", written, args.output_path);
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use clap::Parser;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use ssl_loglib::columnar::{ColumnarConverter, Table};
+use ssl_loglib::log_file_reader::LogFileReader;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Parser)]
+#[command(version)]
+/// Export detection, tracker, and referee data from a game log to Parquet files
+struct Args {
+    log_path: String,
+    #[arg(default_value_t = (".").to_string())]
+    /// Directory to write one Parquet file per table to
+    output_dir: String,
+    #[arg(short, long, default_value_t = 65536)]
+    /// Number of rows per record batch
+    batch_size: usize,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let reader = LogFileReader::new(&args.log_path)?;
+
+    let output_dir = Path::new(&args.output_dir);
+    std::fs::create_dir_all(output_dir)?;
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut writers = HashMap::new();
+    for table in Table::ALL {
+        let file = File::create(output_dir.join(format!("{}.parquet", table.name())))?;
+        let writer = ArrowWriter::try_new(file, table.schema(), Some(properties.clone()))?;
+        writers.insert(table, writer);
+    }
+
+    let mut converter = ColumnarConverter::new();
+    for message in reader {
+        converter.push(&message);
+        for table in Table::ALL {
+            if converter.len(table) >= args.batch_size {
+                let batch = converter.finish(table)?;
+                writers.get_mut(&table).unwrap().write(&batch)?;
+            }
+        }
+    }
+
+    for table in Table::ALL {
+        let mut writer = writers.remove(&table).unwrap();
+        if !converter.is_empty(table) {
+            writer.write(&converter.finish(table)?)?;
+        }
+        let metadata = writer.close()?;
+        println!("{: <20} {} rows", table.name(), metadata.file_metadata().num_rows());
+    }
+
+    Ok(())
+}
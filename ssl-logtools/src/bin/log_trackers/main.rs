@@ -0,0 +1,186 @@
+use clap::{Parser, Subcommand};
+use protobuf::Message;
+use ssl_loglib::analysis::trackers::compare_trackers;
+use ssl_loglib::analysis::tracking_quality::analyze_tracking_quality;
+use ssl_loglib::log_file_reader::LogFileReader;
+use ssl_loglib::log_file_writer::LogFileWriter;
+use ssl_loglib::protos::vision::messages_robocup_ssl_wrapper_tracked::TrackerWrapperPacket;
+use ssl_loglib::raw::MessageType;
+use ssl_loglib::tracker::{is_from_source, list_tracker_sources};
+use std::io;
+
+#[derive(Parser)]
+#[command(version)]
+/// List, filter, and compare the tracker sources in a game log
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the tracker sources in a log
+    List { log_path: String },
+    /// Write a copy of a log with tracker messages from only one source
+    Filter {
+        log_path: String,
+        output_path: String,
+        /// Source name or UUID of the tracker to keep
+        source: String,
+    },
+    /// Compare the positions and velocities reported by two tracker sources
+    Compare {
+        log_path: String,
+        /// Source name or UUID of the first tracker
+        source_a: String,
+        /// Source name or UUID of the second tracker
+        source_b: String,
+    },
+    /// Compare a tracker source with the raw vision detections
+    Quality {
+        log_path: String,
+        /// Source name or UUID of the tracker. Defaults to the first source in the log.
+        source: Option<String>,
+    },
+}
+
+fn list(log_path: &str) -> anyhow::Result<()> {
+    let sources = list_tracker_sources(log_path)?;
+    if sources.is_empty() {
+        println!("No tracker messages found in log file.");
+        return Ok(());
+    }
+    println!("{: <20} {: <38} {: >9} {: >9}", "Source", "UUID", "Messages", "Rate (Hz)");
+    for source in sources {
+        println!(
+            "{: <20} {: <38} {: >9} {: >9.2}",
+            source.name.as_deref().unwrap_or("-"),
+            source.uuid,
+            source.messages,
+            source.message_rate()
+        );
+    }
+    Ok(())
+}
+
+fn filter(log_path: &str, output_path: &str, source: &str) -> anyhow::Result<()> {
+    if !list_tracker_sources(log_path)?.iter().any(|s| s.label() == source || s.uuid == source) {
+        anyhow::bail!("No tracker source named {} in log file", source);
+    }
+    let mut reader = LogFileReader::new(log_path)?;
+    let mut writer = LogFileWriter::new(output_path)?;
+    let mut count = 0;
+    loop {
+        let message = match reader.get_next_raw_message() {
+            Ok(message) => message,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        // Index offsets point into the input file, so the output is written without an index
+        let keep = match message.message_type {
+            MessageType::Index2021 => false,
+            MessageType::VisionTracker2020 => {
+                is_from_source(&TrackerWrapperPacket::parse_from_bytes(&message.data)?, source)
+            }
+            _ => true,
+        };
+        if keep {
+            writer.write_raw_message(&message)?;
+            count += 1;
+        }
+    }
+    writer.flush()?;
+    println!("Wrote {} messages to {}", count, output_path);
+    Ok(())
+}
+
+fn compare(log_path: &str, source_a: &str, source_b: &str) -> anyhow::Result<()> {
+    let comparison = compare_trackers(log_path, source_a, source_b)?;
+    println!(
+        "{}: {} frames, {}: {} frames, {} frames matched",
+        comparison.source_a, comparison.frames_a, comparison.source_b, comparison.frames_b, comparison.matched_frames
+    );
+    if comparison.matched_frames == 0 {
+        println!("No frames could be matched between the two sources.");
+        return Ok(());
+    }
+    println!();
+    println!(
+        "{: <8} {: >8} {: >14} {: >14} {: >16} {: >10} {: >10}",
+        "Object", "Frames", "Pos RMS (mm)", "Pos max (mm)", "Vel RMS (m/s)", "Only A", "Only B"
+    );
+    for (object, difference) in &comparison.objects {
+        println!(
+            "{: <8} {: >8} {: >14.1} {: >14.1} {: >16} {: >10} {: >10}",
+            object.to_string(),
+            difference.frames,
+            difference.position_rms * 1000.0,
+            difference.position_max * 1000.0,
+            difference.velocity_rms.map_or("-".to_string(), |v| format!("{:.3}", v)),
+            difference.only_in_a,
+            difference.only_in_b
+        );
+    }
+    if let Some(rms) = comparison.robot_position_rms() {
+        println!();
+        println!("All robots: position RMS {:.1} mm", rms * 1000.0);
+    }
+    Ok(())
+}
+
+fn quality(log_path: &str, source: Option<String>) -> anyhow::Result<()> {
+    let quality = analyze_tracking_quality(log_path, source)?;
+    let Some(source) = quality.source else {
+        println!("No tracker messages found in log file.");
+        return Ok(());
+    };
+    println!("{}: {} frames", source, quality.tracker_frames);
+    if quality.tracker_frames == 0 {
+        return Ok(());
+    }
+    println!();
+    println!(
+        "{: <8} {: >10} {: >12} {: >12} {: >12} {: >8} {: >8} {: >8} {: >8} {: >8}",
+        "Object", "Detections", "Res p50 (mm)", "Res p95 (mm)", "Res max (mm)", "Far", "Lost", "Losses", "Ghost", "Ghosts"
+    );
+    for (object, object_quality) in &quality.objects {
+        let residual = |percent: f64| {
+            object_quality
+                .residuals
+                .percentile(percent)
+                .map_or("-".to_string(), |r| format!("{:.1}", r * 1000.0))
+        };
+        println!(
+            "{: <8} {: >10} {: >12} {: >12} {: >12} {: >8} {: >8} {: >8} {: >8} {: >8}",
+            object.to_string(),
+            object_quality.detections,
+            residual(50.0),
+            residual(95.0),
+            residual(100.0),
+            object_quality.unassociated_detections,
+            object_quality.lost_detections,
+            object_quality.track_losses,
+            object_quality.ghost_frames,
+            object_quality.ghost_tracks
+        );
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    match args.command {
+        Command::List { log_path } => list(&log_path),
+        Command::Filter {
+            log_path,
+            output_path,
+            source,
+        } => filter(&log_path, &output_path, &source),
+        Command::Compare {
+            log_path,
+            source_a,
+            source_b,
+        } => compare(&log_path, &source_a, &source_b),
+        Command::Quality { log_path, source } => quality(&log_path, source),
+    }
+}